@@ -1,49 +1,254 @@
 /// <reference types="node" />
 
 export class PeerServer {
-  static create(bindAddr: string): PeerServer;
+  static create(bindAddr: string, options_json?: string | null): PeerServer;
   offer_json(advertised_candidates_json?: string | null): string;
-  accept(): Promise<PeerConnection>;
+  local_address(): string;
+  key_binding_proof(e2ee_public_b64: string): Buffer;
+  accept(): Promise<PeerConnection | null>;
+  accept_with_timeout(timeout_ms: number): Promise<PeerConnection | null>;
+  accept_pending(): Promise<PendingConnection>;
+  enter_drain_mode(): void;
+  drain(timeout_ms: number): Promise<void>;
+  is_draining(): boolean;
+  handshakes_in_progress(): number;
+  open_connections(): number;
   close(error_code: number, reason?: string | null): void;
 }
 
+export class PendingConnection {
+  remote_address(): string;
+  remote_address_validated(): boolean;
+  peer_certificates(): Buffer[] | null;
+  client_hello(): Promise<string>;
+  accept(): Promise<PeerConnection>;
+  reject(error_code: number, reason?: string | null): void;
+}
+
 export class PeerClient {
-  static create(bindAddr: string): PeerClient;
-  connect(offer_json: string): Promise<PeerConnection>;
+  static create(bindAddr: string, options_json?: string | null): PeerClient;
+  local_address(): string;
+  rebind(bind_addr: string): Promise<void>;
+  connect(offer_json: string, options_json?: string | null): Promise<PeerConnection>;
+  connect_0rtt(offer_json: string, options_json?: string | null): Promise<ZeroRttConnection>;
+}
+
+export class ZeroRttConnection {
+  connection(): PeerConnection;
+  early_data(): boolean;
+  accepted(): Promise<boolean>;
 }
 
 export class PeerConnection {
   remote_address(): string;
-  open_bi(): Promise<BiStream>;
+  migration_count(): number;
+  winning_candidate(): string | null;
+  stable_id(): string;
+  remote_address_family(): "ipv4" | "ipv6";
+  server_name(): string | null;
+  alpn_protocol(): string | null;
+  handshake_info(): string;
+  open_bi(no_wait?: boolean | null, trace_id?: string | null): Promise<BiStream>;
+  open_bi_batch(count: number, no_wait?: boolean | null): Promise<BiStream[]>;
+  set_open_stream_limit(max: number): void;
+  outstanding_streams(): number;
+  open_stream_counts(): string;
   accept_bi(): Promise<BiStream>;
+  cancel_accepts(): void;
+  open_uni(): Promise<SendStream>;
+  accept_uni(): Promise<RecvStream>;
+  open_bi_compressed(codec: "zstd" | "gzip"): Promise<CompressedStream>;
+  accept_bi_compressed(): Promise<CompressedStream>;
+  open_bi_encrypted(shared_key: Buffer): Promise<EncryptedStream>;
+  accept_bi_encrypted(shared_key: Buffer): Promise<EncryptedStream>;
+  datagram_exchange(payload: Buffer, timeout_ms: number): Promise<Buffer>;
+  accept_datagram_exchange(): Promise<DatagramRequest>;
+  max_datagram_size(): number | null;
+  send_datagram(data: Buffer): void;
+  read_datagram(): Promise<Buffer | null>;
+  datagrams_dropped(): number;
+  on_datagram(cb: (datagram: Buffer) => void): void;
+  open_datagram_flow(flow_id: number): DatagramFlow;
+  measure_owd(samples: number): Promise<string>;
+  enable_owd_responder(): void;
+  send(data: Buffer, options_json?: string | null): Promise<void>;
+  recv(): Promise<Buffer>;
+  estimated_bandwidth_bps(): number | null;
+  stats(): string;
+  rtt_ms(): number;
+  loss_stats(): string;
+  set_adaptive_rate_limit(target_bps: number): void;
+  effective_rate_bps(): number | null;
+  outstanding_datagrams(): number;
+  set_datagram_queue_limit(limit: number, overflow: string): void;
+  await_send_capacity(min_bytes: number): Promise<void>;
+  start_cwnd_sampling(interval_ms: number, callback: (sample_json: string) => void): void;
+  stop_cwnd_sampling(): void;
+  set_stream_drop_behavior(behavior: string, error_code?: number | null): void;
+  set_inactivity_timeout(ms: number, error_code: number, reason?: string | null): void;
+  set_max_lifetime(max_lifetime_ms: number, grace_ms: number, on_expiring: (grace_ms: number) => void): void;
+  summary(): string;
+  enable_event_log(capacity: number): void;
+  event_log(): string;
+  closed(): Promise<string>;
+  on_close(cb: (reason_json: string) => void): void;
+  is_alive(): boolean;
+  close_reason(): string | null;
   close(error_code: number, reason?: string | null): void;
 }
 
+export class DatagramRequest {
+  tag(): number;
+  payload(): Buffer;
+  respond(payload: Buffer): void;
+}
+
+export class DatagramFlow {
+  flow_id(): number;
+  send(payload: Buffer): void;
+  recv(): Promise<Buffer>;
+  stats(): string;
+}
+
+export class SendStream {
+  write(data: Buffer): Promise<void>;
+  finish(): Promise<void>;
+}
+
+export class RecvStream {
+  read(max_bytes?: number | null): Promise<Buffer | null>;
+}
+
 export class BiStream {
   write(data: Buffer): Promise<void>;
+  write_timeout(data: Buffer, timeout_ms: number, error_code?: number | null): Promise<void>;
+  write_all(chunks: Buffer[]): Promise<void>;
+  write_with_progress(data: Buffer, on_progress: (bytes_written: number) => void): Promise<void>;
+  set_write_coalescing(coalesce_ms: number): void;
+  on_writable(cb: () => void): void;
+  flush(): Promise<void>;
   finish(): Promise<void>;
+  reset(error_code: number): Promise<void>;
+  stop(error_code: number): Promise<void>;
+  set_priority(priority: number): Promise<void>;
+  is_first_stream(): boolean;
+  id(): string;
+  set_drop_behavior(behavior: string, error_code?: number | null): void;
   read(max_bytes?: number | null): Promise<Buffer | null>;
+  read_all_available(max_total: number): Promise<Buffer | null>;
+  cancel_reads(): void;
+  read_exact(n: number): Promise<Buffer | null>;
+  read_vectored(buffers: Buffer[]): Promise<number | null>;
+  write_file(path: string): Promise<number>;
+  recv_to_file(path: string): Promise<number>;
+  write_e2ee(shared_key: Buffer, plaintext: Buffer): Promise<void>;
+  read_e2ee(shared_key: Buffer): Promise<Buffer | null>;
+  send_message(data: Buffer): Promise<void>;
+  recv_message(max_bytes?: number | null): Promise<Buffer | null>;
+  into_encrypted(shared_key: Buffer): Promise<EncryptedStream>;
+  pause_reading(): void;
+  resume_reading(): void;
+  is_reading_paused(): boolean;
+  bytes_written(): number;
+  bytes_read(): number;
+}
+
+export class CompressedStream {
+  codec(): "zstd" | "gzip";
+  write(data: Buffer): Promise<void>;
+  finish(): Promise<void>;
+  read(): Promise<Buffer | null>;
+}
+
+export class EncryptedStream {
+  write(plaintext: Buffer): Promise<void>;
+  finish(): Promise<void>;
+  read(): Promise<Buffer | null>;
 }
 
 export class RelayClient {
   static create(bindAddr: string): RelayClient;
-  connect_transport(relay_json: string): Promise<RelayTransport>;
+  local_address(): string;
+  connect_transport(relay_json: string, options_json?: string | null): Promise<RelayTransport>;
 }
 
 export class RelayTransport {
-  relay_bind(token: string, session_id: string): Promise<boolean>;
-  relay_send(token: string, session_id: string, payload_json: string): Promise<void>;
-  recv_relay_data(): Promise<string>;
-  relay_send_e2ee(token: string, session_id: string, shared_key: Buffer, plaintext_json: string): Promise<void>;
-  recv_relay_data_e2ee(shared_key: Buffer): Promise<string>;
+  relay_bind(token: string, session_id: string, options_json?: string | null): Promise<boolean>;
+  relay_bind_multi(token: string, session_ids: string[]): Promise<string>;
+  relay_unbind(token: string, session_id: string): Promise<boolean>;
+  stats(): string;
+  bound_session_count(): number;
+  is_bound(session_id: string): boolean;
+  relay_list_sessions(token: string): Promise<string[] | null>;
+  reflexive_address(): Promise<string | null>;
+  relay_send(token: string, session_id: string, payload_json: string, compress?: boolean | null): Promise<void>;
+  relay_send_bytes(token: string, session_id: string, payload: Buffer): Promise<void>;
+  recv_relay_data(): Promise<string | null>;
+  recv_relay_data_bytes(): Promise<RelayBytesFrame | null>;
+  stop_receiving(): void;
+  relay_send_e2ee(
+    token: string,
+    session_id: string,
+    shared_key: Buffer,
+    plaintext_json: string,
+    aad?: Buffer | null,
+    compress?: boolean | null,
+  ): Promise<void>;
+  relay_send_fs(token: string, session_id: string, peer_public_b64: string, plaintext_json: string): Promise<void>;
+  recv_relay_data_e2ee(shared_key: Buffer, aad?: Buffer | null): Promise<string | null>;
+  closed(): Promise<string>;
+  close_reason(): string | null;
   close(error_code: number, reason?: string | null): void;
 }
 
+export class RelayBytesFrame {
+  session_id(): string;
+  from(): string;
+  to(): string;
+  payload(): Buffer;
+}
+
+export class RelayReplayGuard {
+  static create(own_sender: string): RelayReplayGuard;
+  decrypt(shared_key: Buffer, session_id: string, payload_json: string, aad?: Buffer | null): string;
+  forget(session_id: string): void;
+}
+
 export class E2eeKeyPairHandle {
   static generate(): E2eeKeyPairHandle;
   public_key_b64(): string;
   shared_key(peer_public_key_b64: string): Buffer;
+  rotate_relay_shared_key(current_key: Buffer, session_id: string): Buffer;
+  open_forward_secret(session_id: string, payload_json: string): string;
 }
 
-export function encrypt_relay_payload(sharedKey: Buffer, sessionId: string, plaintextJson: string): string;
-export function decrypt_relay_payload(sharedKey: Buffer, sessionId: string, payloadJson: string): string;
+export function offer_to_bytes(offer_json: string): Buffer;
+export function offer_from_bytes(bytes: Buffer): string;
+export function derive_session_id(my_public_b64: string, peer_public_b64: string): string;
+export function encrypt_relay_payload(
+  sharedKey: Buffer,
+  sessionId: string,
+  plaintextJson: string,
+  aad?: Buffer | null,
+  seq?: number | null,
+  sender?: string | null,
+): string;
+export function encrypt_relay_payload_async(
+  sharedKey: Buffer,
+  sessionId: string,
+  plaintextJson: string,
+  aad?: Buffer | null,
+  seq?: number | null,
+  sender?: string | null,
+): Promise<string>;
+export function decrypt_relay_payload(sharedKey: Buffer, sessionId: string, payloadJson: string, aad?: Buffer | null): string;
+export function decrypt_relay_payload_async(
+  sharedKey: Buffer,
+  sessionId: string,
+  payloadJson: string,
+  aad?: Buffer | null,
+): Promise<string>;
+export function verify_key_binding(certDer: Buffer, e2eePublicB64: string, proof: Buffer): boolean;
+export function crypto_info(): string;
+export function init_crypto(provider: "ring" | "aws-lc"): void;
+export function validate_socket_addr(value: string): string;
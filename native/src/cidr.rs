@@ -0,0 +1,68 @@
+//! Remote address allowlists for `PeerServer`, from the `allowedCidrs` option.
+//!
+//! Entries are `addr/prefix` (`10.0.0.0/8`, `2001:db8::/32`) or a bare address for a single
+//! host. IPv4-mapped IPv6 remotes (`::ffff:10.1.2.3`, as seen on dual-stack sockets) are
+//! matched against IPv4 entries.
+
+use std::net::IpAddr;
+
+use napi::bindgen_prelude::*;
+
+use crate::error;
+
+#[derive(Clone, Copy)]
+struct Cidr {
+    net: IpAddr,
+    prefix: u32,
+}
+
+impl Cidr {
+    fn parse(raw: &str) -> Result<Self> {
+        let invalid = || error::invalid_argument(format!("invalid CIDR {raw:?}"));
+        let (addr, prefix) = match raw.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (raw, None),
+        };
+        let net = addr.parse::<IpAddr>().map_err(|_| invalid())?.to_canonical();
+        let bits = if net.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u32>()
+                .ok()
+                .filter(|prefix| *prefix <= bits)
+                .ok_or_else(invalid)?,
+            None => bits,
+        };
+        Ok(Self { net, prefix })
+    }
+
+    fn contains(&self, addr: IpAddr) -> bool {
+        match (self.net, addr.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix).unwrap_or(0);
+                u32::from(net) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix).unwrap_or(0);
+                u128::from(net) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+pub(crate) struct Allowlist(Vec<Cidr>);
+
+impl Allowlist {
+    pub fn parse(entries: &[String]) -> Result<Self> {
+        entries
+            .iter()
+            .map(|entry| Cidr::parse(entry))
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+
+    pub fn allows(&self, addr: IpAddr) -> bool {
+        self.0.iter().any(|cidr| cidr.contains(addr))
+    }
+}
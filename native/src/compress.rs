@@ -0,0 +1,177 @@
+//! Transparent per-message compression over a bi stream.
+//!
+//! The opening side first writes a prologue naming the codec: one length byte followed by the
+//! ASCII codec name (`zstd` or `gzip`). The accepting side reads it and uses the same codec.
+//! After the prologue every `write` becomes one length-prefixed frame holding the compressed
+//! message, and `read` returns one decompressed message.
+//!
+//! Compression trades CPU for bandwidth: it pays off for text and JSON, and mostly wastes CPU
+//! on media or other already-compressed payloads. Choose it per stream.
+
+use std::io::{Read, Write};
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::error::{self, quic_err, Code};
+use crate::{framing, to_napi_err, HeldSlot, OpenSlot};
+
+const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+const MAX_MESSAGE_BYTES: u64 = 64 * 1024 * 1024;
+const ZSTD_LEVEL: i32 = 3;
+/// Messages (before compression, or frames before decompression) from this size on are
+/// handled on the blocking pool, so a large one does not stall the runtime thread that also
+/// drives other connections. Below it the hand-off costs more than the codec work.
+const BLOCKING_THRESHOLD: usize = 64 * 1024;
+
+#[derive(Clone, Copy)]
+pub(crate) enum Codec {
+    Zstd,
+    Gzip,
+}
+
+impl Codec {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "zstd" => Ok(Self::Zstd),
+            "gzip" => Ok(Self::Gzip),
+            _ => Err(error::invalid_argument(format!("unsupported codec: {name}"))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Zstd => "zstd",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Zstd => zstd::stream::encode_all(data, ZSTD_LEVEL).map_err(to_napi_err),
+            Self::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).map_err(to_napi_err)?;
+                encoder.finish().map_err(to_napi_err)
+            }
+        }
+    }
+
+    /// Decompresses, refusing output beyond `MAX_MESSAGE_BYTES` so a small frame cannot
+    /// expand without bound.
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let limit = MAX_MESSAGE_BYTES + 1;
+        match self {
+            Self::Zstd => zstd::stream::read::Decoder::new(data)
+                .map_err(to_napi_err)?
+                .take(limit)
+                .read_to_end(&mut out),
+            Self::Gzip => flate2::read::GzDecoder::new(data).take(limit).read_to_end(&mut out),
+        }
+        .map_err(to_napi_err)?;
+        if out.len() as u64 > MAX_MESSAGE_BYTES {
+            return Err(to_napi_err("decompressed message too large"));
+        }
+        Ok(out)
+    }
+
+    async fn compress_off_thread(self, data: Buffer) -> Result<Vec<u8>> {
+        if data.len() < BLOCKING_THRESHOLD {
+            return self.compress(&data);
+        }
+        spawn_blocking(move || self.compress(&data))
+            .await
+            .map_err(to_napi_err)?
+    }
+
+    async fn decompress_off_thread(self, frame: Vec<u8>) -> Result<Vec<u8>> {
+        if frame.len() < BLOCKING_THRESHOLD {
+            return self.decompress(&frame);
+        }
+        spawn_blocking(move || self.decompress(&frame))
+            .await
+            .map_err(to_napi_err)?
+    }
+
+    pub fn prologue(self) -> Vec<u8> {
+        let name = self.name().as_bytes();
+        let mut prologue = Vec::with_capacity(1 + name.len());
+        prologue.push(name.len() as u8);
+        prologue.extend_from_slice(name);
+        prologue
+    }
+
+    pub async fn read_prologue(recv: &mut quinn::RecvStream) -> Result<Self> {
+        let mut len = [0u8; 1];
+        recv.read_exact(&mut len).await.map_err(quic_err)?;
+        let mut name = vec![0u8; len[0] as usize];
+        recv.read_exact(&mut name).await.map_err(quic_err)?;
+        let name = String::from_utf8(name).map_err(|_| to_napi_err("invalid codec prologue"))?;
+        Self::parse(&name)
+    }
+}
+
+#[napi]
+pub struct CompressedStream {
+    codec: Codec,
+    send: tokio::sync::Mutex<Option<quinn::SendStream>>,
+    recv: tokio::sync::Mutex<Option<quinn::RecvStream>>,
+    slot: HeldSlot,
+}
+
+impl CompressedStream {
+    pub(crate) fn new(codec: Codec, send: quinn::SendStream, recv: quinn::RecvStream, slot: Option<OpenSlot>) -> Self {
+        Self {
+            codec,
+            send: tokio::sync::Mutex::new(Some(send)),
+            recv: tokio::sync::Mutex::new(Some(recv)),
+            slot: HeldSlot::new(slot, true, true),
+        }
+    }
+}
+
+#[napi]
+impl CompressedStream {
+    #[napi]
+    pub fn codec(&self) -> String {
+        self.codec.name().to_string()
+    }
+
+    #[napi]
+    pub async fn write(&self, data: Buffer) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some(send) = guard.as_mut() else {
+            return Err(error::coded(Code::StreamClosed, "send stream closed"));
+        };
+        let frame = framing::encode(&self.codec.compress_off_thread(data).await?);
+        send.write_all(&frame).await.map_err(quic_err)?;
+        Ok(())
+    }
+
+    #[napi]
+    pub async fn finish(&self) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some(mut send) = guard.take() else {
+            return Ok(());
+        };
+        self.slot.send_closed();
+        send.finish().map_err(quic_err)?;
+        Ok(())
+    }
+
+    #[napi]
+    pub async fn read(&self) -> Result<Option<Buffer>> {
+        let mut guard = self.recv.lock().await;
+        let Some(recv) = guard.as_mut() else {
+            return Ok(None);
+        };
+        let Some(frame) = framing::read_frame(recv, MAX_FRAME_BYTES).await? else {
+            // FIN
+            *guard = None;
+            self.slot.recv_closed();
+            return Ok(None);
+        };
+        Ok(Some(Buffer::from(self.codec.decompress_off_thread(frame).await?)))
+    }
+}
@@ -0,0 +1,58 @@
+//! The process-wide rustls crypto provider used by every endpoint.
+//!
+//! Nothing is installed at module load so `init_crypto` can still choose the provider; the
+//! first endpoint (or introspection call) installs ring unless a provider was chosen.
+
+use std::sync::{Arc, OnceLock};
+
+use napi::bindgen_prelude::*;
+use rustls::crypto::CryptoProvider;
+
+use crate::to_napi_err;
+
+/// Name of the provider this module installed; rustls providers carry no name of their own.
+static PROVIDER_NAME: OnceLock<&'static str> = OnceLock::new();
+
+/// Installs `name` (`"ring"` or `"aws-lc"`), failing if any provider is already installed.
+pub(crate) fn install(name: &str) -> Result<()> {
+    let (name, provider) = match name {
+        "ring" => ("ring", rustls::crypto::ring::default_provider()),
+        "aws-lc" => ("aws-lc", rustls::crypto::aws_lc_rs::default_provider()),
+        _ => return Err(to_napi_err(format!("unknown crypto provider: {name}"))),
+    };
+    provider
+        .install_default()
+        .map_err(|_| to_napi_err("crypto provider already installed"))?;
+    let _ = PROVIDER_NAME.set(name);
+    Ok(())
+}
+
+/// Returns the installed provider, installing ring first if none was chosen.
+pub(crate) fn provider() -> Result<&'static Arc<CryptoProvider>> {
+    if CryptoProvider::get_default().is_none() {
+        // Losing a race with another installer is fine; whichever won is used.
+        let _ = install("ring");
+    }
+    CryptoProvider::get_default().ok_or_else(|| to_napi_err("no crypto provider installed"))
+}
+
+/// `{ provider, fips, cipher_suites, kx_groups }` for the installed provider. `provider` is
+/// `"unknown"` when something other than this module installed it.
+pub(crate) fn info() -> Result<String> {
+    let provider = provider()?;
+    let info = serde_json::json!({
+        "provider": PROVIDER_NAME.get().copied().unwrap_or("unknown"),
+        "fips": provider.fips(),
+        "cipher_suites": provider
+            .cipher_suites
+            .iter()
+            .map(|suite| format!("{:?}", suite.suite()))
+            .collect::<Vec<_>>(),
+        "kx_groups": provider
+            .kx_groups
+            .iter()
+            .map(|group| format!("{:?}", group.name()))
+            .collect::<Vec<_>>(),
+    });
+    Ok(info.to_string())
+}
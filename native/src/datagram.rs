@@ -0,0 +1,254 @@
+//! Datagram request/response exchange.
+//!
+//! Wire format of an exchange datagram (integers big-endian):
+//! - u8 kind: `0xE1` request, `0xE2` response
+//! - u32 correlation tag, chosen by the requester and echoed unchanged by the responder
+//! - payload
+//!
+//! Datagrams of kind `0xE3` carry unreliable messages (see `messages.rs`) and are forwarded
+//! as-is past the kind byte; kind `0xE4` belongs to datagram flows (see `datagram_flow.rs`).
+//! Kind `0xE0` carries application datagrams from `PeerConnection::send_datagram`, also
+//! forwarded past the kind byte; unread ones beyond `RAW_QUEUE` are dropped and counted. Kinds `0xE5` and
+//! `0xE6` reuse the exchange header for one-way delay probes (see `owd.rs`). Other datagrams
+//! are ignored by the demux.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::datagram_flow::{self, DatagramFlow};
+use crate::datagram_queue::DatagramQueue;
+use crate::error::{self, quic_err, Code};
+use crate::messages::KIND_MESSAGE;
+use crate::owd;
+
+pub(crate) const KIND_RAW: u8 = 0xE0;
+const KIND_REQUEST: u8 = 0xE1;
+const KIND_RESPONSE: u8 = 0xE2;
+const HEADER_BYTES: usize = 5;
+const RAW_QUEUE: usize = 1024;
+const OWD_PROBE_INTERVAL: Duration = Duration::from_millis(10);
+const OWD_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn encode(kind: u8, tag: u32, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(HEADER_BYTES + payload.len());
+    frame.push(kind);
+    frame.extend_from_slice(&tag.to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn decode(datagram: &[u8]) -> Option<(u8, u32, &[u8])> {
+    if datagram.len() < HEADER_BYTES {
+        return None;
+    }
+    let tag = u32::from_be_bytes([datagram[1], datagram[2], datagram[3], datagram[4]]);
+    Some((datagram[0], tag, &datagram[HEADER_BYTES..]))
+}
+
+/// Delivers `(seq, payload)` datagrams to one open flow.
+type FlowSender = mpsc::UnboundedSender<(u32, Vec<u8>)>;
+
+/// Routes incoming datagrams for one connection. Started lazily by the first datagram call and
+/// stops once the connection stops yielding datagrams.
+pub(crate) struct DatagramDemux {
+    datagrams: Arc<DatagramQueue>,
+    pending: std::sync::Mutex<HashMap<u32, oneshot::Sender<Vec<u8>>>>,
+    flows: std::sync::Mutex<HashMap<u32, FlowSender>>,
+    requests: tokio::sync::Mutex<mpsc::UnboundedReceiver<(u32, Vec<u8>)>>,
+    raw: tokio::sync::Mutex<mpsc::Receiver<Vec<u8>>>,
+    next_tag: AtomicU32,
+    owd_responder: AtomicBool,
+    raw_dropped: AtomicU64,
+}
+
+impl DatagramDemux {
+    pub fn start(
+        conn: quinn::Connection,
+        datagrams: Arc<DatagramQueue>,
+        messages: mpsc::UnboundedSender<Vec<u8>>,
+    ) -> Arc<Self> {
+        let (request_tx, request_rx) = mpsc::unbounded_channel();
+        let (raw_tx, raw_rx) = mpsc::channel(RAW_QUEUE);
+        let demux = Arc::new(Self {
+            datagrams,
+            pending: std::sync::Mutex::new(HashMap::new()),
+            flows: std::sync::Mutex::new(HashMap::new()),
+            requests: tokio::sync::Mutex::new(request_rx),
+            raw: tokio::sync::Mutex::new(raw_rx),
+            next_tag: AtomicU32::new(1),
+            owd_responder: AtomicBool::new(false),
+            raw_dropped: AtomicU64::new(0),
+        });
+
+        let weak = Arc::downgrade(&demux);
+        // Sync methods such as `open_datagram_flow` start the demux on the JS thread, outside
+        // the tokio runtime, so this goes through napi's `spawn`.
+        spawn(async move {
+            while let Ok(datagram) = conn.read_datagram().await {
+                let Some(demux) = weak.upgrade() else {
+                    break;
+                };
+                match datagram.first() {
+                    Some(&KIND_MESSAGE) => {
+                        let _ = messages.send(datagram[1..].to_vec());
+                        continue;
+                    }
+                    Some(&KIND_RAW) => {
+                        if raw_tx.try_send(datagram[1..].to_vec()).is_err() {
+                            demux.raw_dropped.fetch_add(1, Ordering::Relaxed);
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+                if let Some((flow_id, seq, payload)) = datagram_flow::decode(&datagram) {
+                    // Datagrams for flows not opened on this side are dropped.
+                    if let Some(flow) = demux.flows.lock().unwrap().get(&flow_id) {
+                        let _ = flow.send((seq, payload.to_vec()));
+                    }
+                    continue;
+                }
+                match decode(&datagram) {
+                    Some((KIND_RESPONSE, tag, payload)) => {
+                        if let Some(waiter) = demux.pending.lock().unwrap().remove(&tag) {
+                            let _ = waiter.send(payload.to_vec());
+                        }
+                    }
+                    Some((KIND_REQUEST, tag, payload)) => {
+                        let _ = request_tx.send((tag, payload.to_vec()));
+                    }
+                    Some((owd::KIND_PROBE, tag, payload)) => {
+                        if demux.owd_responder.load(Ordering::Relaxed) {
+                            let echo = owd::echo_payload(payload, owd::now_micros());
+                            let _ = demux.datagrams.send(encode(owd::KIND_ECHO, tag, &echo));
+                        }
+                    }
+                    Some((owd::KIND_ECHO, tag, payload)) => {
+                        let received = owd::now_micros();
+                        if let Some(waiter) = demux.pending.lock().unwrap().remove(&tag) {
+                            let mut echo = payload.to_vec();
+                            echo.extend_from_slice(&received.to_be_bytes());
+                            let _ = waiter.send(echo);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        demux
+    }
+
+    pub async fn exchange(&self, payload: &[u8], timeout: Duration) -> Result<Vec<u8>> {
+        self.send_tagged(KIND_REQUEST, payload, timeout)
+            .await?
+            .ok_or_else(|| error::coded(Code::TimedOut, "datagram exchange timed out"))
+    }
+
+    /// Sends a tagged datagram and waits for the answer with the same tag; `None` on timeout.
+    async fn send_tagged(&self, kind: u8, payload: &[u8], timeout: Duration) -> Result<Option<Vec<u8>>> {
+        let tag = self.next_tag.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(tag, tx);
+
+        if let Err(err) = self.datagrams.send(encode(kind, tag, payload)) {
+            self.pending.lock().unwrap().remove(&tag);
+            return Err(quic_err(err));
+        }
+
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(response)) => Ok(Some(response)),
+            Ok(Err(_)) => Err(error::coded(Code::ConnectionLost, "connection closed")),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&tag);
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn set_owd_responder(&self, enabled: bool) {
+        self.owd_responder.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Sends `samples` probes one after another, 10 ms apart, waiting up to a second for each
+    /// echo; lost probes are counted but not retried.
+    pub async fn measure_owd(&self, samples: u32) -> Result<serde_json::Value> {
+        let mut collected = Vec::new();
+        for i in 0..samples {
+            if i > 0 {
+                tokio::time::sleep(OWD_PROBE_INTERVAL).await;
+            }
+            let probe = owd::now_micros().to_be_bytes();
+            if let Some(echo) = self.send_tagged(owd::KIND_PROBE, &probe, OWD_PROBE_TIMEOUT).await? {
+                collected.extend(owd::Sample::parse(&echo));
+            }
+        }
+        Ok(owd::summarize(samples, &collected))
+    }
+
+    /// Registers `flow_id` on this side. Opening an id again replaces the earlier handle,
+    /// whose `recv` then fails.
+    pub fn open_flow(&self, flow_id: u32) -> DatagramFlow {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.flows.lock().unwrap().insert(flow_id, tx);
+        DatagramFlow::new(self.datagrams.clone(), flow_id, rx)
+    }
+
+    /// The next application datagram, or `None` once the connection stops yielding datagrams.
+    /// Application datagrams discarded because `RAW_QUEUE` unread ones were waiting.
+    pub fn raw_dropped(&self) -> u64 {
+        self.raw_dropped.load(Ordering::Relaxed)
+    }
+
+    pub async fn next_raw(&self) -> Option<Vec<u8>> {
+        self.raw.lock().await.recv().await
+    }
+
+    pub async fn next_request(&self) -> Result<DatagramRequest> {
+        let (tag, payload) = self
+            .requests
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| error::coded(Code::ConnectionLost, "connection closed"))?;
+        Ok(DatagramRequest {
+            datagrams: self.datagrams.clone(),
+            tag,
+            payload,
+        })
+    }
+}
+
+#[napi]
+pub struct DatagramRequest {
+    datagrams: Arc<DatagramQueue>,
+    tag: u32,
+    payload: Vec<u8>,
+}
+
+#[napi]
+impl DatagramRequest {
+    #[napi]
+    pub fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    #[napi]
+    pub fn payload(&self) -> Buffer {
+        Buffer::from(self.payload.clone())
+    }
+
+    #[napi]
+    pub fn respond(&self, payload: Buffer) -> Result<()> {
+        self.datagrams
+            .send(encode(KIND_RESPONSE, self.tag, &payload))
+            .map_err(quic_err)
+    }
+}
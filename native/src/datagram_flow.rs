@@ -0,0 +1,188 @@
+//! Ordered-but-unreliable datagram flows.
+//!
+//! Wire format of a flow datagram (integers big-endian):
+//! - u8 kind `0xE4`
+//! - u32 flow id, chosen by the application and used by both sides
+//! - u32 sequence number, starting at 0 per flow and direction
+//! - payload
+//!
+//! The receiver delivers in sequence order. Datagrams arriving ahead of a gap wait in a small
+//! reorder window; once the window is full or the gap has waited `REORDER_TIMEOUT`, the gap is
+//! counted as lost and skipped. Datagrams older than the next expected one are dropped.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use tokio::sync::mpsc;
+
+use crate::datagram_queue::DatagramQueue;
+use crate::error::{self, quic_err, Code};
+
+pub(crate) const KIND_FLOW: u8 = 0xE4;
+pub(crate) const HEADER_BYTES: usize = 9;
+const REORDER_WINDOW: u32 = 32;
+const REORDER_TIMEOUT: Duration = Duration::from_millis(50);
+
+pub(crate) fn decode(datagram: &[u8]) -> Option<(u32, u32, &[u8])> {
+    if datagram.len() < HEADER_BYTES || datagram[0] != KIND_FLOW {
+        return None;
+    }
+    let flow_id = u32::from_be_bytes([datagram[1], datagram[2], datagram[3], datagram[4]]);
+    let seq = u32::from_be_bytes([datagram[5], datagram[6], datagram[7], datagram[8]]);
+    Some((flow_id, seq, &datagram[HEADER_BYTES..]))
+}
+
+#[derive(Default)]
+struct FlowStats {
+    delivered: AtomicU32,
+    reordered: AtomicU32,
+    dropped_late: AtomicU32,
+    lost: AtomicU32,
+}
+
+struct FlowReceiver {
+    rx: mpsc::UnboundedReceiver<(u32, Vec<u8>)>,
+    /// `None` until the first datagram, so a flow opened late starts wherever the sender is.
+    next: Option<u32>,
+    pending: BTreeMap<u32, Vec<u8>>,
+    ready: VecDeque<Vec<u8>>,
+    stats: Arc<FlowStats>,
+}
+
+impl FlowReceiver {
+    fn accept(&mut self, seq: u32, payload: Vec<u8>) {
+        let next = *self.next.get_or_insert(seq);
+        if seq < next {
+            self.stats.dropped_late.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if seq > next {
+            self.stats.reordered.fetch_add(1, Ordering::Relaxed);
+            self.pending.insert(seq, payload);
+            if seq - next >= REORDER_WINDOW {
+                self.skip_gap();
+            }
+            return;
+        }
+        self.deliver(seq, payload);
+        self.drain_pending();
+    }
+
+    fn deliver(&mut self, seq: u32, payload: Vec<u8>) {
+        self.ready.push_back(payload);
+        self.stats.delivered.fetch_add(1, Ordering::Relaxed);
+        self.next = Some(seq.wrapping_add(1));
+    }
+
+    fn drain_pending(&mut self) {
+        while let Some(next) = self.next {
+            let Some(payload) = self.pending.remove(&next) else {
+                break;
+            };
+            self.deliver(next, payload);
+        }
+    }
+
+    /// Gives up on the missing datagrams before the oldest buffered one.
+    fn skip_gap(&mut self) {
+        let (Some(next), Some((&seq, _))) = (self.next, self.pending.first_key_value()) else {
+            return;
+        };
+        self.stats.lost.fetch_add(seq - next, Ordering::Relaxed);
+        self.next = Some(seq);
+        self.drain_pending();
+    }
+}
+
+#[napi]
+pub struct DatagramFlow {
+    datagrams: Arc<DatagramQueue>,
+    flow_id: u32,
+    next_seq: AtomicU32,
+    receiver: tokio::sync::Mutex<FlowReceiver>,
+    stats: Arc<FlowStats>,
+}
+
+impl DatagramFlow {
+    pub(crate) fn new(
+        datagrams: Arc<DatagramQueue>,
+        flow_id: u32,
+        rx: mpsc::UnboundedReceiver<(u32, Vec<u8>)>,
+    ) -> Self {
+        let stats = Arc::new(FlowStats::default());
+        Self {
+            datagrams,
+            flow_id,
+            next_seq: AtomicU32::new(0),
+            receiver: tokio::sync::Mutex::new(FlowReceiver {
+                rx,
+                next: None,
+                pending: BTreeMap::new(),
+                ready: VecDeque::new(),
+                stats: stats.clone(),
+            }),
+            stats,
+        }
+    }
+}
+
+#[napi]
+impl DatagramFlow {
+    #[napi]
+    pub fn flow_id(&self) -> u32 {
+        self.flow_id
+    }
+
+    #[napi]
+    pub fn send(&self, payload: Buffer) -> Result<()> {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let mut datagram = Vec::with_capacity(HEADER_BYTES + payload.len());
+        datagram.push(KIND_FLOW);
+        datagram.extend_from_slice(&self.flow_id.to_be_bytes());
+        datagram.extend_from_slice(&seq.to_be_bytes());
+        datagram.extend_from_slice(&payload);
+        self.datagrams.send(datagram).map_err(quic_err)
+    }
+
+    /// Resolves with the next payload in sequence order.
+    #[napi]
+    pub async fn recv(&self) -> Result<Buffer> {
+        let mut receiver = self.receiver.lock().await;
+        loop {
+            if let Some(payload) = receiver.ready.pop_front() {
+                return Ok(Buffer::from(payload));
+            }
+            let next = if receiver.pending.is_empty() {
+                receiver.rx.recv().await
+            } else {
+                match tokio::time::timeout(REORDER_TIMEOUT, receiver.rx.recv()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        receiver.skip_gap();
+                        continue;
+                    }
+                }
+            };
+            let (seq, payload) = next.ok_or_else(|| error::coded(Code::ConnectionLost, "connection closed"))?;
+            receiver.accept(seq, payload);
+        }
+    }
+
+    /// `{ delivered, reordered, dropped_late, lost }`: `reordered` counts datagrams that
+    /// arrived ahead of a gap, `dropped_late` those behind the delivery point, and `lost` the
+    /// sequence numbers skipped over.
+    #[napi]
+    pub fn stats(&self) -> String {
+        serde_json::json!({
+            "delivered": self.stats.delivered.load(Ordering::Relaxed),
+            "reordered": self.stats.reordered.load(Ordering::Relaxed),
+            "dropped_late": self.stats.dropped_late.load(Ordering::Relaxed),
+            "lost": self.stats.lost.load(Ordering::Relaxed),
+        })
+        .to_string()
+    }
+}
@@ -0,0 +1,176 @@
+//! Outgoing datagram accounting, with an optional cap on how many may wait to be sent.
+//!
+//! Every datagram a peer connection sends (exchanges, flows, unreliable messages) goes
+//! through here. quinn reports neither its queue length nor when a datagram leaves, so the
+//! outstanding count is derived: datagrams submitted minus DATAGRAM frames sent. quinn evicts
+//! the oldest queued datagrams without telling anyone when its send buffer fills up; to keep
+//! those from inflating the count forever, it is reset whenever the send buffer is empty.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use napi::bindgen_prelude::*;
+
+/// quinn's default `datagram_send_buffer_size`; the endpoints never change it.
+const SEND_BUFFER_BYTES: usize = 1024 * 1024;
+const PUMP_POLL: Duration = Duration::from_millis(1);
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Overflow {
+    /// `send` fails while the queue is full; for control traffic that must not vanish.
+    Error,
+    /// The new datagram is discarded.
+    DropNewest,
+    /// The new datagram waits here and the oldest one still waiting is discarded; for media,
+    /// where fresh data matters more. Datagrams already handed to quinn cannot be recalled,
+    /// so quinn only gets half the limit (rounded up) and the rest waits here.
+    DropOldest,
+}
+
+impl Overflow {
+    /// How much of `limit` may sit in quinn's queue rather than here.
+    fn quinn_share(self, limit: u64) -> u64 {
+        match self {
+            Self::DropOldest => limit.div_ceil(2),
+            Self::Error | Self::DropNewest => limit,
+        }
+    }
+}
+
+impl Overflow {
+    pub fn parse(policy: &str) -> Result<Self> {
+        match policy {
+            "error" => Ok(Self::Error),
+            "drop_newest" => Ok(Self::DropNewest),
+            "drop_oldest" => Ok(Self::DropOldest),
+            _ => Err(crate::error::invalid_argument(format!(
+                "unknown datagram overflow policy {policy:?}"
+            ))),
+        }
+    }
+}
+
+pub(crate) enum SendError {
+    Full,
+    Quinn(quinn::SendDatagramError),
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Full => f.write_str("datagram send queue full"),
+            Self::Quinn(err) => err.fmt(f),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Datagrams handed to quinn, on the same scale as `frame_tx.datagram`.
+    submitted: u64,
+    limit: Option<(u32, Overflow)>,
+    /// Held back by `DropOldest` until quinn's share of the queue has room.
+    staged: VecDeque<Vec<u8>>,
+    pumping: bool,
+}
+
+pub(crate) struct DatagramQueue {
+    conn: quinn::Connection,
+    state: Mutex<State>,
+}
+
+impl DatagramQueue {
+    pub fn new(conn: quinn::Connection) -> Arc<Self> {
+        Arc::new(Self {
+            conn,
+            state: Mutex::new(State::default()),
+        })
+    }
+
+    /// Datagrams queued but not yet sent, including any held back by `DropOldest`.
+    pub fn outstanding(&self) -> u32 {
+        let mut state = self.state.lock().unwrap();
+        let in_quinn = self.in_quinn(&mut state);
+        (in_quinn + state.staged.len() as u64).min(u32::MAX.into()) as u32
+    }
+
+    /// `0` removes the cap; datagrams held back are then released as quinn accepts them.
+    pub fn set_limit(&self, limit: u32, overflow: Overflow) {
+        self.state.lock().unwrap().limit = (limit > 0).then_some((limit, overflow));
+    }
+
+    pub fn send(self: &Arc<Self>, datagram: Vec<u8>) -> std::result::Result<(), SendError> {
+        let mut state = self.state.lock().unwrap();
+        let Some((limit, overflow)) = state.limit else {
+            return self.submit(&mut state, datagram);
+        };
+        let limit = u64::from(limit);
+        let in_quinn = self.in_quinn(&mut state);
+        if state.staged.is_empty() && in_quinn < overflow.quinn_share(limit) {
+            return self.submit(&mut state, datagram);
+        }
+
+        match overflow {
+            _ if in_quinn + (state.staged.len() as u64) < limit => state.staged.push_back(datagram),
+            Overflow::Error => return Err(SendError::Full),
+            Overflow::DropNewest => return Ok(()),
+            Overflow::DropOldest => {
+                state.staged.push_back(datagram);
+                // The newest datagram always waits, even when quinn holds the whole limit.
+                let room = limit.saturating_sub(in_quinn).max(1) as usize;
+                let excess = state.staged.len().saturating_sub(room);
+                state.staged.drain(..excess);
+            }
+        }
+        if !state.pumping {
+            state.pumping = true;
+            self.start_pump();
+        }
+        Ok(())
+    }
+
+    fn submit(&self, state: &mut State, datagram: Vec<u8>) -> std::result::Result<(), SendError> {
+        self.conn.send_datagram(datagram.into()).map_err(SendError::Quinn)?;
+        state.submitted += 1;
+        Ok(())
+    }
+
+    fn in_quinn(&self, state: &mut State) -> u64 {
+        let sent = self.conn.stats().frame_tx.datagram;
+        if self.conn.datagram_send_buffer_space() >= SEND_BUFFER_BYTES {
+            state.submitted = sent;
+        }
+        state.submitted.saturating_sub(sent)
+    }
+
+    /// Moves held-back datagrams to quinn as room frees up in its share of the limit. `send`
+    /// runs on the JS thread, outside the tokio runtime, so this goes through napi's `spawn`.
+    fn start_pump(self: &Arc<Self>) {
+        let queue = Arc::downgrade(self);
+        spawn(async move {
+            loop {
+                tokio::time::sleep(PUMP_POLL).await;
+                let Some(queue) = queue.upgrade() else {
+                    break;
+                };
+                let mut state = queue.state.lock().unwrap();
+                if queue.conn.close_reason().is_some() {
+                    state.staged.clear();
+                }
+                let share = state
+                    .limit
+                    .map_or(u64::MAX, |(limit, overflow)| overflow.quinn_share(limit.into()));
+                while !state.staged.is_empty() && queue.in_quinn(&mut state) < share {
+                    let datagram = state.staged.pop_front().expect("checked non-empty");
+                    // Best-effort, like the datagram itself.
+                    let _ = queue.submit(&mut state, datagram);
+                }
+                if state.staged.is_empty() {
+                    state.pumping = false;
+                    break;
+                }
+            }
+        });
+    }
+}
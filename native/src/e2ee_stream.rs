@@ -0,0 +1,218 @@
+//! End-to-end encrypted messages over a direct bi stream with ChaCha20-Poly1305. This framing
+//! is specific to streams; it is not the relay payload format and the two do not interoperate.
+//!
+//! The key is pre-shared: nothing is negotiated on the stream, so both ends must already hold
+//! the same 32-byte key, for example from `E2eeKeyPair::derive_relay_shared_key`.
+//!
+//! Each message is one frame (integers big-endian):
+//! - u32 length of the remainder
+//! - u64 sequence number, starting at 0 per direction
+//! - 12-byte random nonce
+//! - ciphertext with the 16-byte Poly1305 tag
+//!
+//! The associated data is the sender's role byte (`0` for the end that opened the stream, `1`
+//! for the end that accepted it) followed by the sequence number. The receiver only accepts the
+//! next expected sequence number from the other role, so replayed, dropped or reordered frames
+//! fail decryption instead of being delivered, and so do frames reflected back at their sender:
+//! both directions share the key and start at sequence 0, so without the role a sender's own
+//! frame 0 would pass as the peer's.
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::error::{self, quic_err, Code};
+use crate::{framing, to_napi_err, HeldSlot, OpenSlot};
+
+const NONCE_BYTES: usize = 12;
+const SEQ_BYTES: usize = 8;
+pub(crate) const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+pub(crate) fn shared_key(shared_key: &[u8]) -> Result<[u8; 32]> {
+    shared_key
+        .try_into()
+        .map_err(|_| error::invalid_argument("shared_key must be 32 bytes"))
+}
+
+/// Which end of the stream a cipher seals for.
+#[derive(Clone, Copy)]
+pub(crate) enum Role {
+    Opener,
+    Acceptor,
+}
+
+impl Role {
+    pub fn of(opened: bool) -> Self {
+        if opened {
+            Self::Opener
+        } else {
+            Self::Acceptor
+        }
+    }
+
+    fn peer(self) -> Self {
+        match self {
+            Self::Opener => Self::Acceptor,
+            Self::Acceptor => Self::Opener,
+        }
+    }
+
+    fn aad(self, seq: &[u8]) -> [u8; 1 + SEQ_BYTES] {
+        let mut aad = [0; 1 + SEQ_BYTES];
+        aad[0] = match self {
+            Self::Opener => 0,
+            Self::Acceptor => 1,
+        };
+        aad[1..].copy_from_slice(seq);
+        aad
+    }
+}
+
+pub(crate) struct MessageCipher {
+    cipher: ChaCha20Poly1305,
+    role: Role,
+    send_seq: u64,
+    recv_seq: u64,
+}
+
+impl MessageCipher {
+    pub fn new(key: &[u8; 32], role: Role) -> Self {
+        Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+            role,
+            send_seq: 0,
+            recv_seq: 0,
+        }
+    }
+
+    /// Encrypts `plaintext` into a complete frame, length prefix included.
+    pub fn seal(&mut self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let seq = self.send_seq.to_be_bytes();
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: &self.role.aad(&seq),
+                },
+            )
+            .map_err(|_| to_napi_err("encryption failed"))?;
+        self.send_seq += 1;
+
+        let mut body = Vec::with_capacity(SEQ_BYTES + NONCE_BYTES + ciphertext.len());
+        body.extend_from_slice(&seq);
+        body.extend_from_slice(&nonce);
+        body.extend_from_slice(&ciphertext);
+        Ok(framing::encode(&body))
+    }
+
+    /// Decrypts a frame body (everything after the length prefix).
+    pub fn open(&mut self, body: &[u8]) -> Result<Vec<u8>> {
+        if body.len() < SEQ_BYTES + NONCE_BYTES {
+            return Err(to_napi_err("truncated e2ee frame"));
+        }
+        let (seq, rest) = body.split_at(SEQ_BYTES);
+        let (nonce, ciphertext) = rest.split_at(NONCE_BYTES);
+        if seq != self.recv_seq.to_be_bytes().as_slice() {
+            return Err(to_napi_err(
+                "unexpected e2ee sequence number (replayed or reordered frame)",
+            ));
+        }
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &self.role.peer().aad(seq),
+                },
+            )
+            .map_err(|_| to_napi_err("decryption failed"))?;
+        self.recv_seq += 1;
+        Ok(plaintext)
+    }
+}
+
+/// The cipher in `slot`, created on first use with `key` for `role`. For `BiStream::write_e2ee`
+/// and `read_e2ee`, which take the key on every call: a different key later is rejected, since
+/// the sequence numbers belong to the first key's stream of messages.
+pub(crate) fn keyed_cipher<'a>(
+    slot: &'a mut Option<([u8; 32], MessageCipher)>,
+    key: &[u8; 32],
+    role: Role,
+) -> Result<&'a mut MessageCipher> {
+    let (bound, cipher) = slot.get_or_insert_with(|| (*key, MessageCipher::new(key, role)));
+    if bound != key {
+        return Err(error::invalid_argument(
+            "shared_key differs from the key this stream was first used with",
+        ));
+    }
+    Ok(cipher)
+}
+
+#[napi]
+pub struct EncryptedStream {
+    send: tokio::sync::Mutex<Option<(quinn::SendStream, MessageCipher)>>,
+    recv: tokio::sync::Mutex<Option<(quinn::RecvStream, MessageCipher)>>,
+    slot: HeldSlot,
+}
+
+impl EncryptedStream {
+    pub(crate) fn new(
+        send: Option<quinn::SendStream>,
+        recv: Option<quinn::RecvStream>,
+        key: &[u8; 32],
+        role: Role,
+        slot: Option<OpenSlot>,
+    ) -> Self {
+        Self {
+            slot: HeldSlot::new(slot, send.is_some(), recv.is_some()),
+            send: tokio::sync::Mutex::new(send.map(|send| (send, MessageCipher::new(key, role)))),
+            recv: tokio::sync::Mutex::new(recv.map(|recv| (recv, MessageCipher::new(key, role)))),
+        }
+    }
+}
+
+#[napi]
+impl EncryptedStream {
+    #[napi]
+    pub async fn write(&self, plaintext: Buffer) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some((send, cipher)) = guard.as_mut() else {
+            return Err(error::coded(Code::StreamClosed, "send stream closed"));
+        };
+        let frame = cipher.seal(&plaintext)?;
+        send.write_all(&frame).await.map_err(quic_err)?;
+        Ok(())
+    }
+
+    #[napi]
+    pub async fn finish(&self) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some((mut send, _)) = guard.take() else {
+            return Ok(());
+        };
+        self.slot.send_closed();
+        send.finish().map_err(quic_err)?;
+        Ok(())
+    }
+
+    /// Resolves with the next decrypted message, or `null` once the peer finishes the stream.
+    #[napi]
+    pub async fn read(&self) -> Result<Option<Buffer>> {
+        let mut guard = self.recv.lock().await;
+        let Some((recv, cipher)) = guard.as_mut() else {
+            return Ok(None);
+        };
+        let Some(body) = framing::read_frame(recv, MAX_FRAME_BYTES).await? else {
+            // FIN
+            *guard = None;
+            self.slot.recv_closed();
+            return Ok(None);
+        };
+        Ok(Some(Buffer::from(cipher.open(&body)?)))
+    }
+}
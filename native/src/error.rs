@@ -0,0 +1,141 @@
+//! Stable error codes.
+//!
+//! napi errors only carry a `Status`, and everything here fails with `GenericFailure`, so a
+//! coded error starts its message with the code instead: `"TIMED_OUT: datagram exchange
+//! timed out"`. The TS wrappers move that prefix into `err.code`. Errors without a code
+//! (misconfiguration, SDK failures) keep a plain message.
+
+use napi::bindgen_prelude::*;
+
+use crate::datagram_queue;
+
+#[derive(Clone, Copy)]
+pub(crate) enum Code {
+    /// The connection is gone: closed by the peer, reset, or failed at the transport level.
+    ConnectionLost,
+    /// The connection idled out, or a bounded wait (exchange, handshake) ran out.
+    TimedOut,
+    /// This side closed the connection or endpoint, or the server is draining.
+    Closed,
+    /// The TLS handshake failed, e.g. the peer's certificate did not match the offer.
+    HandshakeFailed,
+    /// The peer reset the stream we read from, or we reset the one we write to.
+    StreamReset,
+    /// The peer stopped the stream we write to.
+    StreamStopped,
+    /// The stream half was already finished or closed locally, or ended early.
+    StreamClosed,
+    /// A bounded send queue is full.
+    QueueFull,
+    /// A relay transport already has `maxSessions` sessions bound.
+    SessionLimit,
+    /// Malformed or out-of-range input from the caller.
+    InvalidArgument,
+    /// The caller abandoned the call, e.g. with `BiStream::cancel_reads`.
+    Cancelled,
+    /// A relay frame read as end-to-end encrypted arrived in plaintext.
+    NotEncrypted,
+}
+
+impl Code {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ConnectionLost => "CONNECTION_LOST",
+            Self::TimedOut => "TIMED_OUT",
+            Self::Closed => "CLOSED",
+            Self::HandshakeFailed => "HANDSHAKE_FAILED",
+            Self::StreamReset => "STREAM_RESET",
+            Self::StreamStopped => "STREAM_STOPPED",
+            Self::StreamClosed => "STREAM_CLOSED",
+            Self::QueueFull => "QUEUE_FULL",
+            Self::SessionLimit => "SESSION_LIMIT",
+            Self::InvalidArgument => "INVALID_ARGUMENT",
+            Self::Cancelled => "CANCELLED",
+            Self::NotEncrypted => "NOT_ENCRYPTED",
+        }
+    }
+}
+
+pub(crate) fn coded(code: Code, message: impl std::fmt::Display) -> Error {
+    Error::new(Status::GenericFailure, format!("{}: {message}", code.as_str()))
+}
+
+pub(crate) fn invalid_argument(message: impl std::fmt::Display) -> Error {
+    coded(Code::InvalidArgument, message)
+}
+
+/// Errors from quinn (and our datagram queue) that map onto a `Code`.
+pub(crate) trait Coded: std::fmt::Display {
+    fn code(&self) -> Code;
+}
+
+/// For `map_err` on quinn results, like `to_napi_err` for everything else.
+pub(crate) fn quic_err(err: impl Coded) -> Error {
+    coded(err.code(), &err)
+}
+
+impl Coded for quinn::ConnectionError {
+    fn code(&self) -> Code {
+        match self {
+            Self::TimedOut => Code::TimedOut,
+            Self::LocallyClosed => Code::Closed,
+            _ => Code::ConnectionLost,
+        }
+    }
+}
+
+impl Coded for quinn::WriteError {
+    fn code(&self) -> Code {
+        match self {
+            Self::Stopped(_) => Code::StreamStopped,
+            Self::ConnectionLost(err) => err.code(),
+            Self::ClosedStream => Code::StreamClosed,
+            Self::ZeroRttRejected => Code::ConnectionLost,
+        }
+    }
+}
+
+impl Coded for quinn::ReadError {
+    fn code(&self) -> Code {
+        match self {
+            Self::Reset(_) => Code::StreamReset,
+            Self::ConnectionLost(err) => err.code(),
+            Self::ClosedStream => Code::StreamClosed,
+            Self::IllegalOrderedRead => Code::InvalidArgument,
+            Self::ZeroRttRejected => Code::ConnectionLost,
+        }
+    }
+}
+
+impl Coded for quinn::ReadExactError {
+    fn code(&self) -> Code {
+        match self {
+            Self::FinishedEarly(_) => Code::StreamClosed,
+            Self::ReadError(err) => err.code(),
+        }
+    }
+}
+
+impl Coded for quinn::ClosedStream {
+    fn code(&self) -> Code {
+        Code::StreamClosed
+    }
+}
+
+impl Coded for quinn::SendDatagramError {
+    fn code(&self) -> Code {
+        match self {
+            Self::ConnectionLost(err) => err.code(),
+            Self::UnsupportedByPeer | Self::Disabled | Self::TooLarge => Code::InvalidArgument,
+        }
+    }
+}
+
+impl Coded for datagram_queue::SendError {
+    fn code(&self) -> Code {
+        match self {
+            Self::Full => Code::QueueFull,
+            Self::Quinn(err) => err.code(),
+        }
+    }
+}
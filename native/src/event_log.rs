@@ -0,0 +1,42 @@
+//! Opt-in per-connection flight recorder: a bounded ring of the most recent notable events,
+//! kept by the wrapper so it stays readable after the connection closes.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Instant;
+
+pub(crate) struct EventLog {
+    started: Instant,
+    capacity: usize,
+    events: Mutex<VecDeque<serde_json::Value>>,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            started: Instant::now(),
+            capacity,
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Appends `{ t, kind, detail }`, where `t` is milliseconds since the log was enabled,
+    /// evicting the oldest event once full.
+    pub fn record(&self, kind: &str, detail: impl Into<String>) {
+        let event = serde_json::json!({
+            "t": self.started.elapsed().as_millis() as u64,
+            "kind": kind,
+            "detail": detail.into(),
+        });
+        let mut events = self.events.lock().unwrap();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    pub fn to_json(&self) -> String {
+        let events = self.events.lock().unwrap();
+        serde_json::Value::Array(events.iter().cloned().collect()).to_string()
+    }
+}
@@ -0,0 +1,38 @@
+//! Length-prefixed frames: a big-endian u32 length followed by that many bytes.
+
+use napi::bindgen_prelude::*;
+
+use crate::error::{self, quic_err, Code};
+use crate::to_napi_err;
+
+pub(crate) fn encode(body: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(4 + body.len());
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(body);
+    frame
+}
+
+/// Reads one frame body, or `None` on a clean FIN at a frame boundary. Frames longer than
+/// `max_len` are rejected before allocating.
+pub(crate) async fn read_frame(recv: &mut quinn::RecvStream, max_len: usize) -> Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match recv.read_exact(&mut len).await {
+        Ok(()) => {}
+        Err(quinn::ReadExactError::FinishedEarly(0)) => return Ok(None),
+        Err(quinn::ReadExactError::FinishedEarly(_)) => {
+            return Err(error::coded(Code::StreamClosed, "truncated frame"))
+        }
+        Err(quinn::ReadExactError::ReadError(err)) => return Err(quic_err(err)),
+    }
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > max_len {
+        return Err(to_napi_err(format!("frame of {len} bytes exceeds limit of {max_len}")));
+    }
+    let mut body = vec![0u8; len];
+    recv.read_exact(&mut body).await.map_err(|err| match err {
+        quinn::ReadExactError::FinishedEarly(_) => error::coded(Code::StreamClosed, "truncated frame"),
+        quinn::ReadExactError::ReadError(err) => quic_err(err),
+    })?;
+    Ok(Some(body))
+}
@@ -0,0 +1,84 @@
+//! Binding an E2EE public key to a peer's TLS certificate.
+//!
+//! The peer signs its E2EE public key with its certificate's private key, so a relay in the
+//! middle cannot substitute its own E2EE key without also holding the certificate key.
+//!
+//! Proof format (integers big-endian):
+//! - u16 TLS `SignatureScheme` code of the signature (e.g. `0x0403` ECDSA P-256 SHA-256)
+//! - signature over `"gann-e2ee-key-binding-v1" || 0x00 || raw E2EE public key bytes`
+
+use std::sync::Arc;
+
+use base64::Engine as _;
+use napi::bindgen_prelude::*;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::sign::SigningKey;
+use rustls::SignatureScheme;
+
+use crate::{crypto, to_napi_err};
+
+const CONTEXT: &[u8] = b"gann-e2ee-key-binding-v1";
+
+const SCHEMES: &[SignatureScheme] = &[
+    SignatureScheme::ECDSA_NISTP256_SHA256,
+    SignatureScheme::ECDSA_NISTP384_SHA384,
+    SignatureScheme::ED25519,
+    SignatureScheme::RSA_PSS_SHA256,
+];
+
+fn signed_message(e2ee_public_b64: &str) -> Result<Vec<u8>> {
+    let public_key = base64::engine::general_purpose::STANDARD
+        .decode(e2ee_public_b64)
+        .map_err(|_| to_napi_err("invalid e2ee public key"))?;
+    let mut message = Vec::with_capacity(CONTEXT.len() + 1 + public_key.len());
+    message.extend_from_slice(CONTEXT);
+    message.push(0);
+    message.extend_from_slice(&public_key);
+    Ok(message)
+}
+
+pub(crate) fn load_signing_key(key: &PrivateKeyDer<'_>) -> Result<Arc<dyn SigningKey>> {
+    crypto::provider()?
+        .key_provider
+        .load_private_key(key.clone_key())
+        .map_err(to_napi_err)
+}
+
+pub(crate) fn sign(key: &dyn SigningKey, e2ee_public_b64: &str) -> Result<Vec<u8>> {
+    let signer = key
+        .choose_scheme(SCHEMES)
+        .ok_or_else(|| to_napi_err("certificate key has no supported signature scheme"))?;
+    let signature = signer.sign(&signed_message(e2ee_public_b64)?).map_err(to_napi_err)?;
+
+    let mut proof = Vec::with_capacity(2 + signature.len());
+    proof.extend_from_slice(&u16::from(signer.scheme()).to_be_bytes());
+    proof.extend_from_slice(&signature);
+    Ok(proof)
+}
+
+/// `Ok(false)` means the proof is well-formed but not a valid signature by `cert_der`'s key.
+pub(crate) fn verify(cert_der: &[u8], e2ee_public_b64: &str, proof: &[u8]) -> Result<bool> {
+    if proof.len() < 2 {
+        return Err(to_napi_err("truncated key binding proof"));
+    }
+    let scheme = SignatureScheme::from(u16::from_be_bytes([proof[0], proof[1]]));
+    if !SCHEMES.contains(&scheme) {
+        return Err(to_napi_err(format!(
+            "unsupported key binding signature scheme: {scheme:?}"
+        )));
+    }
+    let message = signed_message(e2ee_public_b64)?;
+
+    let cert = CertificateDer::from(cert_der);
+    let cert = webpki::EndEntityCert::try_from(&cert).map_err(to_napi_err)?;
+    let algorithms = crypto::provider()?
+        .signature_verification_algorithms
+        .mapping
+        .iter()
+        .find(|(supported, _)| *supported == scheme)
+        .map(|(_, algorithms)| *algorithms)
+        .ok_or_else(|| to_napi_err(format!("crypto provider cannot verify {scheme:?}")))?;
+    Ok(algorithms
+        .iter()
+        .any(|alg| cert.verify_signature(*alg, &message, &proof[2..]).is_ok()))
+}
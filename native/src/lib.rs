@@ -1,9 +1,48 @@
+use std::collections::HashSet;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::task::Poll;
+use std::time::Duration;
 
 use gann_sdk as rust;
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
 use napi_derive::{module_init, napi};
 
+use crate::error::{quic_err, Code};
+
+mod cidr;
+mod compress;
+mod crypto;
+mod datagram;
+mod datagram_flow;
+mod datagram_queue;
+mod e2ee_stream;
+mod error;
+mod event_log;
+mod forward_secret;
+mod framing;
+mod key_binding;
+mod messages;
+mod owd;
+mod peer;
+mod public_key;
+mod rate_limit;
+mod relay_aad;
+mod relay_bytes;
+mod relay_compress;
+mod relay_control;
+mod relay_payload;
+mod relay_ratchet;
+mod relay_seq;
+mod transport;
+mod uni_stream;
+
+/// JS callback invoked from native tasks with a single argument and no error-first parameter.
+type JsCallback<T> = ThreadsafeFunction<T, (), T, Status, false>;
+
 fn to_napi_err(err: impl ToString) -> Error {
     Error::new(Status::GenericFailure, err.to_string())
 }
@@ -11,180 +50,2497 @@ fn to_napi_err(err: impl ToString) -> Error {
 fn parse_socket_addr(value: String) -> Result<SocketAddr> {
     value
         .parse::<SocketAddr>()
-        .map_err(|_| to_napi_err("invalid socket address"))
+        .map_err(|_| error::invalid_argument("invalid socket address"))
+}
+
+/// Parses `value` with the same rules as every native API taking an address and returns its
+/// normalized form, e.g. for validating user input without connecting. IPv6 scope ids must
+/// be numeric (`[fe80::1%2]:9000`).
+#[napi]
+pub fn validate_socket_addr(value: String) -> Result<String> {
+    Ok(parse_socket_addr(value)?.to_string())
+}
+
+/// Polls `fut` a single time, yielding `None` instead of waiting when it is not ready.
+async fn poll_once<F: Future>(fut: F) -> Option<F::Output> {
+    let mut fut = std::pin::pin!(fut);
+    std::future::poll_fn(|cx| match fut.as_mut().poll(cx) {
+        Poll::Ready(value) => Poll::Ready(Some(value)),
+        Poll::Pending => Poll::Ready(None),
+    })
+    .await
+}
+
+fn parse_options<T: serde::de::DeserializeOwned + Default>(options_json: Option<String>) -> Result<T> {
+    match options_json {
+        None => Ok(T::default()),
+        Some(raw) => serde_json::from_str(&raw).map_err(to_napi_err),
+    }
 }
 
 #[module_init]
 fn init() {
     start_async_runtime();
-    let _ = rustls::crypto::ring::default_provider().install_default();
+}
+
+/// Installs the rustls crypto provider, `"ring"` or `"aws-lc"` (aws-lc-rs, e.g. for FIPS
+/// deployments). Must be called before any endpoint is created; errors if a provider is
+/// already installed. Without it, ring is installed on first use.
+#[napi]
+pub fn init_crypto(provider: String) -> Result<()> {
+    crypto::install(&provider)
+}
+
+/// Describes the active rustls crypto provider as JSON: `{ provider, fips, cipher_suites,
+/// kx_groups }`, for startup checks such as FIPS compliance. Installs the default provider
+/// if none was chosen yet.
+#[napi]
+pub fn crypto_info() -> Result<String> {
+    crypto::info()
 }
 
 #[napi]
 pub struct PeerServer {
-    inner: rust::QuicPeerServer,
+    endpoint: quinn::Endpoint,
+    identity: peer::PeerIdentity,
+    draining: tokio::sync::watch::Sender<bool>,
+    runtime: Option<Arc<transport::DedicatedRuntime>>,
+    handshakes: Arc<peer::Handshakes>,
+    allowlist: Option<cidr::Allowlist>,
+    max_read_bytes: usize,
+}
+
+impl PeerServer {
+    async fn accept_until(&self, deadline: Option<tokio::time::Instant>) -> Result<Option<PeerConnection>> {
+        let mut draining = self.draining.subscribe();
+        let conn = tokio::select! {
+            conn = peer::accept(&self.endpoint, &self.handshakes, self.allowlist.as_ref(), deadline) => conn,
+            _ = draining.wait_for(|draining| *draining) => return Err(error::coded(Code::Closed, "server draining")),
+        };
+        Ok(conn.map(|conn| {
+            PeerConnection::new(conn)
+                .with_runtime(self.runtime.clone())
+                .with_max_read_bytes(self.max_read_bytes)
+        }))
+    }
 }
 
 #[napi]
 impl PeerServer {
     #[napi(factory)]
-    pub fn create(bind_addr: String) -> Result<Self> {
+    pub fn create(bind_addr: String, options_json: Option<String>) -> Result<Self> {
         let bind_addr = parse_socket_addr(bind_addr)?;
-        let server = block_on(async move { rust::create_quic_peer_server(bind_addr) }).map_err(to_napi_err)?;
-        Ok(Self { inner: server })
+        let options: transport::TransportOptions = parse_options(options_json)?;
+        let transport = options.transport_config()?;
+        crypto::provider()?;
+        let alpn = options.alpn()?;
+        let allowlist = options.allowlist()?;
+        let allow_migration = options.allow_migration.unwrap_or(true);
+        let accept_early_data = options.accept_early_data;
+        let max_read_bytes = options.max_read_bytes()?;
+        let runtime = options.runtime()?;
+        let (endpoint, identity) =
+            match &runtime {
+                Some(runtime) => {
+                    let _entered = runtime.enter();
+                    peer::create_server(bind_addr, transport, alpn, allow_migration, accept_early_data)?
+                }
+                None => block_on(async move {
+                    peer::create_server(bind_addr, transport, alpn, allow_migration, accept_early_data)
+                })?,
+            };
+        Ok(Self {
+            endpoint,
+            identity,
+            draining: tokio::sync::watch::Sender::new(false),
+            runtime,
+            handshakes: peer::Handshakes::new(options.max_concurrent_handshakes.unwrap_or(0)),
+            allowlist,
+            max_read_bytes,
+        })
     }
 
+    /// Without advertised candidates (or with an empty list), offers the bound address, with an
+    /// unspecified one expanded to loopback as the Rust SDK does; see `peer::default_candidates`.
     #[napi]
     pub fn offer_json(&self, advertised_candidates_json: Option<String>) -> Result<String> {
-        let candidates: Option<Vec<SocketAddr>> = match advertised_candidates_json {
+        let mut candidates: Vec<SocketAddr> = match advertised_candidates_json {
+            None => Vec::new(),
+            Some(raw) => serde_json::from_str::<Vec<String>>(&raw)
+                .map_err(to_napi_err)?
+                .into_iter()
+                .map(|s| {
+                    s.parse::<SocketAddr>()
+                        .map_err(|_| error::invalid_argument("invalid candidate"))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        };
+        if candidates.is_empty() {
+            candidates = peer::default_candidates(self.endpoint.local_addr().map_err(to_napi_err)?);
+        }
+
+        let offer = self.identity.offer(candidates);
+        serde_json::to_string(&offer).map_err(to_napi_err)
+    }
+
+    /// The address the endpoint is bound to, with the OS-assigned port when bound to port 0.
+    #[napi]
+    pub fn local_address(&self) -> Result<String> {
+        Ok(self.endpoint.local_addr().map_err(to_napi_err)?.to_string())
+    }
+
+    /// Signs `e2ee_public_b64` with this server's certificate key; the peer checks the
+    /// result with `verify_key_binding`. The proof format is documented in `key_binding.rs`.
+    #[napi]
+    pub fn key_binding_proof(&self, e2ee_public_b64: String) -> Result<Buffer> {
+        let proof = key_binding::sign(self.identity.signing_key.as_ref(), &e2ee_public_b64)?;
+        Ok(Buffer::from(proof))
+    }
+
+    /// Resolves `None` once `close` has been called, including for a call already waiting,
+    /// so accept loops can exit. Rejects with "server draining" once `enter_drain_mode` has
+    /// been called.
+    ///
+    /// Safe to call concurrently for a pool of accept workers: each call takes a different
+    /// incoming connection from the endpoint's queue and runs its handshake independently, so
+    /// a slow handshake only holds up its own caller. Waiting calls are served as connections
+    /// arrive, in no particular order.
+    #[napi]
+    pub async fn accept(&self) -> Result<Option<PeerConnection>> {
+        self.accept_until(None).await
+    }
+
+    /// `accept` that also resolves `None` when no client arrives within `timeout_ms`. A
+    /// handshake already underway when the time is up still completes, and its connection
+    /// is returned, so polling accept loops do not abort clients arriving near a tick.
+    #[napi]
+    pub async fn accept_with_timeout(&self, timeout_ms: u32) -> Result<Option<PeerConnection>> {
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms.into());
+        self.accept_until(Some(deadline)).await
+    }
+
+    /// Two-phase accept for admission control: resolves as soon as a client's first packet
+    /// arrives, before any handshake work, with a `PendingConnection` to `accept` or `reject`.
+    /// Rejects with "server draining" like `accept`. As with `accept`, clients outside
+    /// `allowedCidrs` are refused before they get here.
+    #[napi]
+    pub async fn accept_pending(&self) -> Result<PendingConnection> {
+        let mut draining = self.draining.subscribe();
+        let incoming = tokio::select! {
+            incoming = peer::next_allowed(&self.endpoint, self.allowlist.as_ref()) => {
+                incoming.ok_or_else(|| error::coded(Code::Closed, "server closed"))?
+            }
+            _ = draining.wait_for(|draining| *draining) => return Err(error::coded(Code::Closed, "server draining")),
+        };
+        Ok(PendingConnection {
+            remote: incoming.remote_address(),
+            validated: incoming.remote_address_validated(),
+            state: tokio::sync::Mutex::new(Some(Pending::Incoming(Box::new(incoming)))),
+            runtime: self.runtime.clone(),
+            handshakes: self.handshakes.clone(),
+            max_read_bytes: self.max_read_bytes,
+        })
+    }
+
+    /// Stops taking new connections for a rolling deploy: new handshakes are refused and
+    /// `accept` rejects, while established connections keep working until closed.
+    #[napi]
+    pub fn enter_drain_mode(&self) {
+        self.endpoint.set_server_config(None);
+        self.draining.send_replace(true);
+    }
+
+    /// Graceful shutdown: `enter_drain_mode`, then waits up to `timeout_ms` for the open
+    /// connections to be closed by either side, and closes whatever is left with "server
+    /// drained" once the deadline passes.
+    #[napi]
+    pub async fn drain(&self, timeout_ms: u32) -> Result<()> {
+        self.enter_drain_mode();
+        let timeout = Duration::from_millis(timeout_ms.into());
+        if tokio::time::timeout(timeout, self.endpoint.wait_idle()).await.is_err() {
+            self.endpoint.close(0u32.into(), b"server drained");
+        }
+        Ok(())
+    }
+
+    #[napi]
+    pub fn is_draining(&self) -> bool {
+        *self.draining.borrow()
+    }
+
+    /// Handshakes currently running, not counting clients waiting for a slot under
+    /// `maxConcurrentHandshakes`.
+    #[napi]
+    pub fn handshakes_in_progress(&self) -> u32 {
+        self.handshakes.in_progress()
+    }
+
+    /// Connections on this endpoint that are still open, e.g. to wait for a drain to finish.
+    #[napi]
+    pub fn open_connections(&self) -> u32 {
+        self.endpoint.open_connections() as u32
+    }
+
+    #[napi]
+    pub fn close(&self, error_code: u32, reason: Option<String>) {
+        let reason = reason.unwrap_or_else(|| "closed".to_string());
+        self.endpoint.close(error_code.into(), reason.as_bytes());
+    }
+}
+
+/// An incoming connection attempt awaiting an admission decision. Only the client's Initial
+/// packet has been received: the TLS handshake has not started, so nothing is known beyond
+/// the remote address (which may be spoofed unless `remote_address_validated`) until
+/// `client_hello` starts it. Dropping it without a decision ignores the attempt.
+#[napi]
+pub struct PendingConnection {
+    state: tokio::sync::Mutex<Option<Pending>>,
+    remote: SocketAddr,
+    validated: bool,
+    runtime: Option<Arc<transport::DedicatedRuntime>>,
+    handshakes: Arc<peer::Handshakes>,
+    max_read_bytes: usize,
+}
+
+enum Pending {
+    Incoming(Box<quinn::Incoming>),
+    /// Started by `client_hello`, with its JSON.
+    Started(peer::Handshake, String),
+}
+
+fn already_decided() -> Error {
+    to_napi_err("connection already accepted or rejected")
+}
+
+#[napi]
+impl PendingConnection {
+    #[napi]
+    pub fn remote_address(&self) -> String {
+        self.remote.to_string()
+    }
+
+    /// Whether the client proved it owns `remote_address` (via a retry token). Without
+    /// validation the address could be spoofed.
+    #[napi]
+    pub fn remote_address_validated(&self) -> bool {
+        self.validated
+    }
+
+    /// Always `None`: peer endpoints do not request client certificates.
+    #[napi]
+    pub fn peer_certificates(&self) -> Option<Vec<Buffer>> {
+        None
+    }
+
+    /// Starts the handshake, once a slot is free under `maxConcurrentHandshakes`, and resolves
+    /// with `{ server_name, alpn }` JSON as soon as the client's ClientHello is processed,
+    /// before the handshake completes, so the attempt can still be routed, accepted or
+    /// rejected on it. `server_name` is the client's SNI, `alpn` the protocol this server
+    /// chose from the client's offer; either is `null` when absent. rustls exposes neither
+    /// the raw ClientHello nor its other extensions, so JA3-style fingerprints cannot be
+    /// computed. The slot stays taken until `accept` or `reject`.
+    #[napi]
+    pub async fn client_hello(&self) -> Result<String> {
+        let mut state = self.state.lock().await;
+        let mut handshake = match state.take().ok_or_else(already_decided)? {
+            Pending::Started(handshake, hello) => {
+                *state = Some(Pending::Started(handshake, hello.clone()));
+                return Ok(hello);
+            }
+            Pending::Incoming(incoming) => self.handshakes.start(*incoming).await.map_err(quic_err)?,
+        };
+        let data = handshake.client_hello().await.map_err(quic_err)?;
+        let alpn = data
+            .as_ref()
+            .and_then(|data| data.protocol.as_ref())
+            .map(|protocol| String::from_utf8_lossy(protocol).into_owned());
+        let hello = serde_json::json!({
+            "server_name": data.and_then(|data| data.server_name),
+            "alpn": alpn,
+        })
+        .to_string();
+        *state = Some(Pending::Started(handshake, hello.clone()));
+        Ok(hello)
+    }
+
+    /// Runs the handshake, once a slot is free under `maxConcurrentHandshakes`, or finishes
+    /// the one `client_hello` started. Fails if it does not complete.
+    #[napi]
+    pub async fn accept(&self) -> Result<PeerConnection> {
+        let pending = self.state.lock().await.take().ok_or_else(already_decided)?;
+        let conn = match pending {
+            Pending::Incoming(incoming) => self.handshakes.complete(*incoming).await,
+            Pending::Started(handshake, _) => handshake.finish().await,
+        }
+        .map_err(quic_err)?;
+        Ok(PeerConnection::new(conn)
+            .with_runtime(self.runtime.clone())
+            .with_max_read_bytes(self.max_read_bytes))
+    }
+
+    /// Turns the client away with an application close carrying `error_code` and `reason`.
+    /// This answers within the handshake (0.5-RTT), so only the server's first flight is
+    /// spent on it. A client that has not finished its side of the handshake when the close
+    /// arrives fails it with a transport `APPLICATION_ERROR` instead, without the code and
+    /// reason, as QUIC requires. Errors while `client_hello` is still waiting for the
+    /// ClientHello.
+    #[napi]
+    pub fn reject(&self, error_code: u32, reason: Option<String>) -> Result<()> {
+        let reason = reason.unwrap_or_else(|| "rejected".to_string());
+        let pending = self
+            .state
+            .try_lock()
+            .map_err(|_| to_napi_err("client_hello still in progress"))?
+            .take()
+            .ok_or_else(already_decided)?;
+        let connecting = match pending {
+            // quinn spawns the connection's driver, which needs the runtime.
+            Pending::Incoming(incoming) => block_on(async move { incoming.accept() }).map_err(quic_err)?,
+            Pending::Started(handshake, _) => handshake.into_connecting(),
+        };
+        match connecting.into_0rtt() {
+            Ok((conn, _)) => conn.close(error_code.into(), reason.as_bytes()),
+            // Servers always get a 0.5-RTT connection; this arm only keeps the types honest.
+            Err(connecting) => {
+                spawn(async move {
+                    if let Ok(conn) = connecting.await {
+                        conn.close(error_code.into(), reason.as_bytes());
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CandidatePreference {
+    Ipv6,
+    Ipv4,
+    #[default]
+    Race,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConnectOptions {
+    #[serde(default)]
+    candidate_preference: CandidatePreference,
+    handshake_timeout_ms: Option<u32>,
+}
+
+/// Bounds a connect by `timeout_ms`, if set. Dropping the connect on expiry abandons its
+/// half-open connections, so nothing lingers on the endpoint.
+async fn with_handshake_timeout<T>(timeout_ms: Option<u32>, connect: impl Future<Output = Result<T>>) -> Result<T> {
+    let Some(timeout_ms) = timeout_ms else {
+        return connect.await;
+    };
+    tokio::time::timeout(Duration::from_millis(timeout_ms.into()), connect)
+        .await
+        .unwrap_or_else(|_| {
+            Err(error::coded(
+                Code::TimedOut,
+                format!("handshake timed out after {timeout_ms} ms"),
+            ))
+        })
+}
+
+/// Splits candidates into per-family attempts, preferred family first.
+fn candidates_by_family(candidates: Vec<SocketAddr>, prefer_ipv6: bool) -> Vec<Vec<SocketAddr>> {
+    let (preferred, rest): (Vec<_>, Vec<_>) = candidates.into_iter().partition(|addr| addr.is_ipv6() == prefer_ipv6);
+    [preferred, rest]
+        .into_iter()
+        .filter(|group| !group.is_empty())
+        .collect()
+}
+
+#[napi]
+pub struct PeerClient {
+    /// Locked only to hand out a clone for a new connection, so a `rebind` never lands
+    /// halfway through starting one; handshakes then run unlocked and concurrently.
+    endpoint: tokio::sync::Mutex<quinn::Endpoint>,
+    /// Handle to the same endpoint for queries that must not wait behind a `rebind`.
+    handle: quinn::Endpoint,
+    transport: Arc<quinn::TransportConfig>,
+    tls: peer::ClientTls,
+    runtime: Option<Arc<transport::DedicatedRuntime>>,
+    max_read_bytes: usize,
+}
+
+#[napi]
+impl PeerClient {
+    #[napi(factory)]
+    pub fn create(bind_addr: String, options_json: Option<String>) -> Result<Self> {
+        let bind_addr = parse_socket_addr(bind_addr)?;
+        let options: transport::TransportOptions = parse_options(options_json)?;
+        let transport = options.transport_config()?;
+        let tls = peer::ClientTls::new(options.alpn()?, options.pinned_cert_sha256()?);
+        let max_read_bytes = options.max_read_bytes()?;
+        crypto::provider()?;
+        let runtime = options.runtime()?;
+        let endpoint = match &runtime {
+            Some(runtime) => {
+                let _entered = runtime.enter();
+                quinn::Endpoint::client(bind_addr)
+            }
+            None => block_on(async move { quinn::Endpoint::client(bind_addr) }),
+        }
+        .map_err(to_napi_err)?;
+        Ok(Self {
+            handle: endpoint.clone(),
+            endpoint: tokio::sync::Mutex::new(endpoint),
+            transport,
+            tls,
+            runtime,
+            max_read_bytes,
+        })
+    }
+
+    /// The address the endpoint is bound to, with the OS-assigned port when bound to port 0.
+    /// Follows `rebind`.
+    #[napi]
+    pub fn local_address(&self) -> Result<String> {
+        Ok(self.handle.local_addr().map_err(to_napi_err)?.to_string())
+    }
+
+    /// Moves the client endpoint to a new local socket; established connections migrate to it
+    /// on their next packet. Mostly useful to simulate network changes.
+    #[napi]
+    pub async fn rebind(&self, bind_addr: String) -> Result<()> {
+        let bind_addr = parse_socket_addr(bind_addr)?;
+        let socket = std::net::UdpSocket::bind(bind_addr).map_err(to_napi_err)?;
+        self.endpoint.lock().await.rebind(socket).map_err(to_napi_err)
+    }
+
+    #[napi]
+    pub async fn connect(&self, offer_json: String, options_json: Option<String>) -> Result<PeerConnection> {
+        let offer: peer::QuicOffer = serde_json::from_str(&offer_json).map_err(to_napi_err)?;
+        let options: ConnectOptions = parse_options(options_json)?;
+        let target = peer::OfferTarget::new(&offer, self.transport.clone(), &self.tls)?;
+        let candidates = offer.resolve_candidates().await?;
+        let attempts = match options.candidate_preference {
+            CandidatePreference::Race => vec![candidates],
+            CandidatePreference::Ipv6 => candidates_by_family(candidates, true),
+            CandidatePreference::Ipv4 => candidates_by_family(candidates, false),
+        };
+
+        let endpoint = self.endpoint.lock().await.clone();
+        let (conn, winner) = with_handshake_timeout(options.handshake_timeout_ms, async {
+            let mut last_err = None;
+            for attempt in attempts {
+                match target.connect(&endpoint, &attempt).await {
+                    Ok(connected) => return Ok(connected),
+                    Err(err) => last_err = Some(err),
+                }
+            }
+            Err(last_err.unwrap_or_else(|| error::invalid_argument("no candidates")))
+        })
+        .await?;
+        Ok(PeerConnection::new(conn)
+            .with_runtime(self.runtime.clone())
+            .with_max_read_bytes(self.max_read_bytes)
+            .with_sent_server_name(&offer.server_name)
+            .with_winning_candidate(winner))
+    }
+
+    /// Like `connect`, but reuses a session ticket from an earlier connection to the same
+    /// server, if one is cached, to skip the handshake round trip: the connection is handed
+    /// out before the handshake completes, and streams and datagrams sent meanwhile travel as
+    /// 0-RTT early data. Early data can be replayed by an attacker, so only send requests
+    /// that are safe to repeat. The server may also reject it, in which case it is lost; see
+    /// `ZeroRttConnection::accepted`. With 0-RTT, only the first candidate is tried. Tickets
+    /// are cached per server certificate, and only servers created with `acceptEarlyData`
+    /// issue tickets that permit early data. Without such a ticket this falls back to
+    /// `connect`.
+    #[napi]
+    pub async fn connect_0rtt(&self, offer_json: String, options_json: Option<String>) -> Result<ZeroRttConnection> {
+        let offer: peer::QuicOffer = serde_json::from_str(&offer_json).map_err(to_napi_err)?;
+        let target = peer::OfferTarget::new(&offer, self.transport.clone(), &self.tls)?;
+        let first = *offer
+            .resolve_candidates()
+            .await?
+            .first()
+            .ok_or_else(|| error::invalid_argument("no candidates"))?;
+
+        let endpoint = self.endpoint.lock().await.clone();
+        let early = target.connect_0rtt(&endpoint, first)?;
+        let Some((conn, accepted)) = early else {
+            let conn = self.connect(offer_json, options_json).await?;
+            return Ok(ZeroRttConnection::new(conn, None));
+        };
+        let conn = PeerConnection::new(conn)
+            .with_runtime(self.runtime.clone())
+            .with_max_read_bytes(self.max_read_bytes)
+            .with_sent_server_name(&offer.server_name)
+            .with_winning_candidate(first);
+        Ok(ZeroRttConnection::new(conn, Some(accepted)))
+    }
+}
+
+/// Result of `PeerClient::connect_0rtt`.
+#[napi]
+pub struct ZeroRttConnection {
+    conn: std::sync::Mutex<Option<PeerConnection>>,
+    early_data: bool,
+    pending: std::sync::Mutex<Option<quinn::ZeroRttAccepted>>,
+    accepted: tokio::sync::OnceCell<bool>,
+}
+
+impl ZeroRttConnection {
+    fn new(conn: PeerConnection, accepted: Option<quinn::ZeroRttAccepted>) -> Self {
+        Self {
+            conn: std::sync::Mutex::new(Some(conn)),
+            early_data: accepted.is_some(),
+            pending: std::sync::Mutex::new(accepted),
+            accepted: tokio::sync::OnceCell::new(),
+        }
+    }
+}
+
+#[napi]
+impl ZeroRttConnection {
+    /// The connection; can be taken once.
+    #[napi]
+    pub fn connection(&self) -> Result<PeerConnection> {
+        self.conn
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| to_napi_err("connection already taken"))
+    }
+
+    /// Whether the connection was handed out before the handshake, so that data sent right
+    /// away goes out as 0-RTT. `false` when no session ticket was cached.
+    #[napi]
+    pub fn early_data(&self) -> bool {
+        self.early_data
+    }
+
+    /// Resolves once the handshake completes: `true` if the server accepted the early data,
+    /// `false` if it rejected it (anything sent early was dropped and must be resent) or if
+    /// there was none. Writes on streams opened before a rejection fail.
+    #[napi]
+    pub async fn accepted(&self) -> bool {
+        let accepted = self.accepted.get_or_init(|| async {
+            let pending = self.pending.lock().unwrap().take();
+            match pending {
+                Some(accepted) => accepted.await,
+                None => false,
+            }
+        });
+        *accepted.await
+    }
+}
+
+/// Live transport counters for monitoring, shared by peer and relay connections.
+#[derive(serde::Serialize)]
+struct ConnectionStats {
+    rtt_ms: f64,
+    cwnd: u64,
+    /// UDP payload bytes, including QUIC overhead.
+    bytes_sent: u64,
+    bytes_recv: u64,
+    packets_sent: u64,
+    packets_lost: u64,
+    congestion_events: u64,
+    path_mtu: u16,
+}
+
+impl ConnectionStats {
+    fn of(conn: &quinn::Connection) -> Self {
+        let stats = conn.stats();
+        Self {
+            rtt_ms: stats.path.rtt.as_secs_f64() * 1000.0,
+            cwnd: stats.path.cwnd,
+            bytes_sent: stats.udp_tx.bytes,
+            bytes_recv: stats.udp_rx.bytes,
+            packets_sent: stats.path.sent_packets,
+            packets_lost: stats.path.lost_packets,
+            congestion_events: stats.path.congestion_events,
+            path_mtu: stats.path.current_mtu,
+        }
+    }
+
+    fn to_json(conn: &quinn::Connection) -> Result<String> {
+        serde_json::to_string(&Self::of(conn)).map_err(to_napi_err)
+    }
+}
+
+/// Structured form of a close reason, so callers can branch on why a connection ended.
+fn close_reason_json(reason: &quinn::ConnectionError) -> serde_json::Value {
+    use quinn::ConnectionError;
+    match reason {
+        ConnectionError::TimedOut => serde_json::json!({ "kind": "timed_out" }),
+        ConnectionError::LocallyClosed => serde_json::json!({ "kind": "locally_closed" }),
+        ConnectionError::Reset => serde_json::json!({ "kind": "reset" }),
+        ConnectionError::ApplicationClosed(close) => serde_json::json!({
+            "kind": "application_closed",
+            "code": close.error_code.into_inner(),
+            "reason": String::from_utf8_lossy(&close.reason),
+        }),
+        ConnectionError::ConnectionClosed(close) => serde_json::json!({
+            "kind": "connection_closed",
+            "code": u64::from(close.error_code),
+            "reason": String::from_utf8_lossy(&close.reason),
+        }),
+        ConnectionError::TransportError(err) => serde_json::json!({
+            "kind": "transport_error",
+            "code": u64::from(err.code),
+            "reason": err.reason,
+        }),
+        ConnectionError::VersionMismatch => serde_json::json!({ "kind": "version_mismatch" }),
+        ConnectionError::CidsExhausted => serde_json::json!({ "kind": "cids_exhausted" }),
+    }
+}
+
+const SEND_CAPACITY_POLL: Duration = Duration::from_millis(10);
+
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendOptions {
+    reliable: Option<bool>,
+}
+
+#[napi]
+pub struct PeerConnection {
+    inner: quinn::Connection,
+    datagrams: OnceLock<Arc<datagram::DatagramDemux>>,
+    datagram_queue: Arc<datagram_queue::DatagramQueue>,
+    uni_streams: OnceLock<Arc<uni_stream::UniDemux>>,
+    messages: messages::Messages,
+    cwnd_sampler: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    inactivity_watch: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    lifetime_watch: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    datagram_listener: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    open_limit: OnceLock<Arc<tokio::sync::Semaphore>>,
+    outstanding: Arc<AtomicU32>,
+    /// Streams in either direction that are not yet done, for `open_stream_counts`.
+    live_bi: Arc<AtomicU32>,
+    live_uni: Arc<AtomicU32>,
+    events: Arc<OnceLock<event_log::EventLog>>,
+    close_logged: AtomicBool,
+    /// Last observed remote address and how many times it has changed.
+    path: std::sync::Mutex<(SocketAddr, u32)>,
+    established: std::time::Instant,
+    streams_opened: AtomicU32,
+    streams_accepted: AtomicU32,
+    rate_limit: Arc<rate_limit::RateLimit>,
+    drop_behavior: std::sync::Mutex<DropBehavior>,
+    accepts: Canceller,
+    /// The server name this side connected with, on the client side.
+    sent_server_name: Option<String>,
+    /// The offer candidate whose handshake completed first, on the client side.
+    winning_candidate: Option<SocketAddr>,
+    /// The `maxReadBytes` option, passed on to every stream.
+    max_read_bytes: usize,
+    /// Keeps a dedicated endpoint thread alive while this connection is driven on it.
+    _runtime: Option<Arc<transport::DedicatedRuntime>>,
+}
+
+impl PeerConnection {
+    fn new(inner: quinn::Connection) -> Self {
+        let datagram_queue = datagram_queue::DatagramQueue::new(inner.clone());
+        Self {
+            messages: messages::Messages::new(inner.clone(), datagram_queue.clone()),
+            datagrams: OnceLock::new(),
+            datagram_queue,
+            uni_streams: OnceLock::new(),
+            cwnd_sampler: std::sync::Mutex::new(None),
+            inactivity_watch: std::sync::Mutex::new(None),
+            lifetime_watch: std::sync::Mutex::new(None),
+            datagram_listener: std::sync::Mutex::new(None),
+            open_limit: OnceLock::new(),
+            outstanding: Arc::new(AtomicU32::new(0)),
+            live_bi: Arc::new(AtomicU32::new(0)),
+            live_uni: Arc::new(AtomicU32::new(0)),
+            events: Arc::new(OnceLock::new()),
+            close_logged: AtomicBool::new(false),
+            path: std::sync::Mutex::new((inner.remote_address(), 0)),
+            established: std::time::Instant::now(),
+            streams_opened: AtomicU32::new(0),
+            streams_accepted: AtomicU32::new(0),
+            rate_limit: Arc::new(rate_limit::RateLimit::new(inner.clone())),
+            drop_behavior: std::sync::Mutex::new(DropBehavior::default()),
+            accepts: Canceller::new(),
+            sent_server_name: None,
+            winning_candidate: None,
+            max_read_bytes: transport::DEFAULT_MAX_READ_BYTES,
+            _runtime: None,
+            inner,
+        }
+    }
+
+    fn with_runtime(mut self, runtime: Option<Arc<transport::DedicatedRuntime>>) -> Self {
+        self._runtime = runtime;
+        self
+    }
+
+    fn with_max_read_bytes(mut self, max_read_bytes: usize) -> Self {
+        self.max_read_bytes = max_read_bytes;
+        self
+    }
+
+    fn with_sent_server_name(mut self, server_name: &str) -> Self {
+        self.sent_server_name = Some(server_name.to_string());
+        self
+    }
+
+    fn with_winning_candidate(mut self, candidate: SocketAddr) -> Self {
+        self.winning_candidate = Some(candidate);
+        self
+    }
+
+    /// Samples the current path, counting a migration when its remote address changed.
+    fn observe_path(&self) -> (SocketAddr, u32) {
+        let current = self.inner.remote_address();
+        let mut path = self.path.lock().unwrap();
+        if path.0 != current {
+            self.record("migration", format!("{} -> {current}", path.0));
+            *path = (current, path.1 + 1);
+        }
+        *path
+    }
+
+    fn handshake_data(&self) -> Option<quinn::crypto::rustls::HandshakeData> {
+        let data = self.inner.handshake_data()?;
+        data.downcast::<quinn::crypto::rustls::HandshakeData>()
+            .ok()
+            .map(|data| *data)
+    }
+
+    fn record(&self, kind: &str, detail: impl Into<String>) {
+        if let Some(events) = self.events.get() {
+            events.record(kind, detail);
+        }
+    }
+
+    fn bi_stream(&self, send: quinn::SendStream, recv: quinn::RecvStream) -> BiStream {
+        let opened = send.id().initiator() == self.inner.side();
+        BiStream::new(send, recv, opened, self.rate_limit.clone(), self.max_read_bytes)
+            .with_drop_behavior(*self.drop_behavior.lock().unwrap())
+            .with_live(LiveStream::new(&self.live_bi))
+    }
+
+    fn count_stream(&self, kind: &str) {
+        let counter = match kind {
+            "accept_bi" | "accept_uni" => &self.streams_accepted,
+            _ => &self.streams_opened,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the outcome of a stream operation: `kind` with the stream id on success, or
+    /// `<kind>_failed` with the error.
+    fn record_stream<T>(&self, kind: &str, result: Result<T>, id: impl Fn(&T) -> quinn::StreamId) -> Result<T> {
+        if result.is_ok() {
+            self.count_stream(kind);
+        }
+        if self.events.get().is_some() {
+            match &result {
+                Ok(value) => self.record(kind, id(value).to_string()),
+                Err(err) => self.record(&format!("{kind}_failed"), err.reason.clone()),
+            }
+        }
+        result
+    }
+
+    /// Logs how the connection ended the first time it is observed closed. quinn offers no
+    /// close callback, so this runs whenever the log is touched.
+    fn record_close_reason(&self) {
+        if let Some(reason) = self.inner.close_reason() {
+            if !self.close_logged.swap(true, Ordering::AcqRel) {
+                self.record("closed", reason.to_string());
+            }
+        }
+    }
+
+    async fn acquire_open_slot(&self, no_wait: bool) -> Result<OpenSlot> {
+        let permit = match self.open_limit.get() {
             None => None,
-            Some(raw) => Some(
-                serde_json::from_str::<Vec<String>>(&raw)
-                    .map_err(to_napi_err)?
-                    .into_iter()
-                    .map(|s| s.parse::<SocketAddr>().map_err(|_| to_napi_err("invalid candidate")))
-                    .collect::<Result<Vec<_>>>()?,
+            Some(limit) if no_wait => Some(
+                limit
+                    .clone()
+                    .try_acquire_owned()
+                    .map_err(|_| to_napi_err("open stream limit reached"))?,
             ),
+            Some(limit) => Some(limit.clone().acquire_owned().await.map_err(to_napi_err)?),
         };
+        self.outstanding.fetch_add(1, Ordering::Relaxed);
+        Ok(OpenSlot {
+            _permit: permit,
+            outstanding: self.outstanding.clone(),
+        })
+    }
 
-        let offer = self.inner.offer(candidates);
-        serde_json::to_string(&offer).map_err(to_napi_err)
+    fn uni_streams(&self) -> &Arc<uni_stream::UniDemux> {
+        self.uni_streams
+            .get_or_init(|| uni_stream::UniDemux::start(self.inner.clone(), self.messages.incoming_sender()))
+    }
+
+    fn datagrams(&self) -> &Arc<datagram::DatagramDemux> {
+        self.datagrams.get_or_init(|| {
+            datagram::DatagramDemux::start(
+                self.inner.clone(),
+                self.datagram_queue.clone(),
+                self.messages.incoming_sender(),
+            )
+        })
+    }
+}
+
+#[napi]
+impl PeerConnection {
+    /// The remote address of the current path, which follows the peer across migrations.
+    #[napi]
+    pub fn remote_address(&self) -> Result<String> {
+        Ok(self.observe_path().0.to_string())
+    }
+
+    /// The offer candidate this client dialed and won the race on, as resolved (hostname
+    /// candidates give the address they resolved to). Unlike `remote_address` it does not
+    /// follow migrations. `None` on the server side.
+    #[napi]
+    pub fn winning_candidate(&self) -> Option<String> {
+        self.winning_candidate.map(|addr| addr.to_string())
+    }
+
+    /// Remote address changes observed so far. The path is sampled when this or
+    /// `remote_address` is called, so a migration that is reverted between two calls is not
+    /// counted.
+    #[napi]
+    pub fn migration_count(&self) -> u32 {
+        self.observe_path().1
+    }
+
+    /// An identifier for this connection that both peers compute identically, as 32 hex
+    /// digits, for correlating logs across client and server. It is exported from the TLS
+    /// session, so it stays the same across migrations. quinn's own `stable_id` is not used:
+    /// it is a local handle that the other side cannot know.
+    #[napi]
+    pub fn stable_id(&self) -> Result<String> {
+        let mut id = [0u8; 16];
+        self.inner
+            .export_keying_material(&mut id, b"EXPORTER-gann-connection-id", b"")
+            .map_err(|_| to_napi_err("connection id export failed"))?;
+        Ok(id.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+
+    #[napi]
+    pub fn remote_address_family(&self) -> String {
+        let family = if self.inner.remote_address().ip().to_canonical().is_ipv6() {
+            "ipv6"
+        } else {
+            "ipv4"
+        };
+        family.to_string()
+    }
+
+    /// The SNI the client sent, on the server side; `None` on the client side or without SNI.
+    /// To route on it before the handshake completes, use `PendingConnection::client_hello`.
+    #[napi]
+    pub fn server_name(&self) -> Option<String> {
+        self.handshake_data()?.server_name
+    }
+
+    /// The ALPN protocol negotiated in the handshake, if any.
+    #[napi]
+    pub fn alpn_protocol(&self) -> Option<String> {
+        let protocol = self.handshake_data()?.protocol?;
+        Some(String::from_utf8_lossy(&protocol).into_owned())
+    }
+
+    /// `{ side, alpn, server_name }` for audit logging, with `side` `client` or `server`.
+    /// `server_name` is the name connected with on the client side and the SNI received on the
+    /// server side; it and `alpn` are `null` when absent.
+    #[napi]
+    pub fn handshake_info(&self) -> String {
+        let data = self.handshake_data();
+        let server_name = match &self.sent_server_name {
+            Some(sent) => Some(sent.clone()),
+            None => data.as_ref().and_then(|data| data.server_name.clone()),
+        };
+        let alpn = data
+            .and_then(|data| data.protocol)
+            .map(|protocol| String::from_utf8_lossy(&protocol).into_owned());
+        serde_json::json!({
+            "side": if self.sent_server_name.is_some() { "client" } else { "server" },
+            "alpn": alpn,
+            "server_name": server_name,
+        })
+        .to_string()
+    }
+
+    /// With an open stream limit set, waits for a free slot, or errors instead when `no_wait`.
+    ///
+    /// `trace_id` tags this stream's open, send close and recv close events in the event log,
+    /// to correlate them with application traces.
+    #[napi]
+    pub async fn open_bi(&self, no_wait: Option<bool>, trace_id: Option<String>) -> Result<BiStream> {
+        let traced = |detail: String| match &trace_id {
+            Some(trace_id) => format!("{detail} trace_id={trace_id}"),
+            None => detail,
+        };
+        let slot = self
+            .acquire_open_slot(no_wait.unwrap_or(false))
+            .await
+            .inspect_err(|err| self.record("open_bi_failed", traced(err.reason.clone())))?;
+        let (send, recv) = self.inner.open_bi().await.map_err(|err| {
+            self.record("open_bi_failed", traced(err.to_string()));
+            quic_err(err)
+        })?;
+        let detail = traced(send.id().to_string());
+        self.count_stream("open_bi");
+        self.record("open_bi", detail.clone());
+
+        let stream = self.bi_stream(send, recv).with_slot(slot);
+        Ok(match trace_id {
+            Some(_) => stream.with_trace(StreamTrace {
+                events: self.events.clone(),
+                detail,
+            }),
+            None => stream,
+        })
+    }
+
+    /// Opens `count` bi streams in one call. With an open stream limit set, `no_wait` fails
+    /// the whole batch if the slots are not all free; otherwise the call waits for them.
+    /// When the peer's stream limit is below `count`, the call also waits until the peer
+    /// allows more streams, typically as earlier ones close.
+    #[napi]
+    pub async fn open_bi_batch(&self, count: u32, no_wait: Option<bool>) -> Result<Vec<BiStream>> {
+        let no_wait = no_wait.unwrap_or(false);
+        let mut slots = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let slot = self
+                .acquire_open_slot(no_wait)
+                .await
+                .inspect_err(|err| self.record("open_bi_failed", err.reason.clone()))?;
+            slots.push(slot);
+        }
+
+        let mut streams = Vec::with_capacity(count as usize);
+        for slot in slots {
+            let opened = self.inner.open_bi().await.map_err(quic_err);
+            let (send, recv) = self.record_stream("open_bi", opened, |(send, _)| send.id())?;
+            streams.push(self.bi_stream(send, recv).with_slot(slot));
+        }
+        Ok(streams)
+    }
+
+    /// Caps streams opened by this side that are still outstanding, i.e. not yet finished on
+    /// send and read to FIN, and not yet garbage-collected. Compressed and encrypted streams
+    /// count too, and so does the shared message stream of `send`, for the connection's
+    /// lifetime once opened. This is a local guardrail, independent of the peer's stream
+    /// limits. Can only be set once per connection.
+    #[napi]
+    pub fn set_open_stream_limit(&self, max: u32) -> Result<()> {
+        self.open_limit
+            .set(Arc::new(tokio::sync::Semaphore::new(max as usize)))
+            .map_err(|_| to_napi_err("open stream limit already set"))
+    }
+
+    #[napi]
+    pub fn outstanding_streams(&self) -> u32 {
+        self.outstanding.load(Ordering::Relaxed)
+    }
+
+    /// `{ bidi, uni }` JSON: streams opened or accepted through `open_bi`, `accept_bi`,
+    /// `open_uni` and `accept_uni` that are not done yet. A bi stream is done once finished on
+    /// send and read to FIN, a uni stream once finished or read to FIN; either is also done
+    /// when garbage-collected. Unlike `outstanding_streams` this counts the peer's streams
+    /// too, but not the compressed, encrypted or message streams.
+    #[napi]
+    pub fn open_stream_counts(&self) -> String {
+        serde_json::json!({
+            "bidi": self.live_bi.load(Ordering::Relaxed),
+            "uni": self.live_uni.load(Ordering::Relaxed),
+        })
+        .to_string()
+    }
+
+    /// Rejects with `CANCELLED` if `cancel_accepts` is called while waiting.
+    #[napi]
+    pub async fn accept_bi(&self) -> Result<BiStream> {
+        let accepted = self.accepts.run(async { Ok(self.inner.accept_bi().await) }).await?;
+        let (send, recv) = self.record_stream("accept_bi", accepted.map_err(quic_err), |(send, _)| send.id())?;
+        Ok(self.bi_stream(send, recv))
+    }
+
+    /// Makes every `accept_bi` waiting right now reject with `CANCELLED`; streams the peer
+    /// opens stay queued for the next `accept_bi`.
+    #[napi]
+    pub fn cancel_accepts(&self) {
+        self.accepts.cancel();
+    }
+
+    /// Opens a send-only stream, for one-way traffic that never needs a reply. Counts against
+    /// the open stream limit until finished or dropped. The stream carries a leading kind byte
+    /// (see `uni_stream.rs`), so the peer must use `accept_uni`.
+    #[napi]
+    pub async fn open_uni(&self) -> Result<uni_stream::SendStream> {
+        let slot = self
+            .acquire_open_slot(false)
+            .await
+            .inspect_err(|err| self.record("open_uni_failed", err.reason.clone()))?;
+        let opened = self.inner.open_uni().await.map_err(quic_err);
+        let mut send = self.record_stream("open_uni", opened, |send| send.id())?;
+        send.write_all(&[uni_stream::KIND_UNI]).await.map_err(quic_err)?;
+        Ok(uni_stream::SendStream::new(
+            send,
+            self.rate_limit.clone(),
+            slot,
+            LiveStream::new(&self.live_uni),
+        ))
+    }
+
+    #[napi]
+    pub async fn accept_uni(&self) -> Result<uni_stream::RecvStream> {
+        let accepted = match self.uni_streams().accept().await {
+            Some(recv) => Ok(recv),
+            None => Err(self
+                .inner
+                .close_reason()
+                .map_or_else(|| error::coded(Code::ConnectionLost, "connection closed"), quic_err)),
+        };
+        let recv = self.record_stream("accept_uni", accepted, |recv| recv.id())?;
+        Ok(uni_stream::RecvStream::new(
+            recv,
+            self.max_read_bytes,
+            LiveStream::new(&self.live_uni),
+        ))
+    }
+
+    /// Opens a bi stream that compresses each message with `codec` (`"zstd"` or `"gzip"`).
+    /// The codec is announced in a stream prologue, so the peer must use `accept_bi_compressed`.
+    #[napi]
+    pub async fn open_bi_compressed(&self, codec: String) -> Result<compress::CompressedStream> {
+        let codec = compress::Codec::parse(&codec)?;
+        let slot = self
+            .acquire_open_slot(false)
+            .await
+            .inspect_err(|err| self.record("open_bi_failed", err.reason.clone()))?;
+        let opened = self.inner.open_bi().await.map_err(quic_err);
+        let (mut send, recv) = self.record_stream("open_bi", opened, |(send, _)| send.id())?;
+        send.write_all(&codec.prologue()).await.map_err(quic_err)?;
+        Ok(compress::CompressedStream::new(codec, send, recv, Some(slot)))
+    }
+
+    #[napi]
+    pub async fn accept_bi_compressed(&self) -> Result<compress::CompressedStream> {
+        let accepted = self.inner.accept_bi().await.map_err(quic_err);
+        let (send, mut recv) = self.record_stream("accept_bi", accepted, |(send, _)| send.id())?;
+        let codec = compress::Codec::read_prologue(&mut recv).await?;
+        Ok(compress::CompressedStream::new(codec, send, recv, None))
+    }
+
+    #[napi]
+    pub async fn open_bi_encrypted(&self, shared_key: Buffer) -> Result<e2ee_stream::EncryptedStream> {
+        let key = e2ee_stream::shared_key(&shared_key)?;
+        let slot = self
+            .acquire_open_slot(false)
+            .await
+            .inspect_err(|err| self.record("open_bi_failed", err.reason.clone()))?;
+        let opened = self.inner.open_bi().await.map_err(quic_err);
+        let (send, recv) = self.record_stream("open_bi", opened, |(send, _)| send.id())?;
+        let role = e2ee_stream::Role::Opener;
+        Ok(e2ee_stream::EncryptedStream::new(
+            Some(send),
+            Some(recv),
+            &key,
+            role,
+            Some(slot),
+        ))
+    }
+
+    #[napi]
+    pub async fn accept_bi_encrypted(&self, shared_key: Buffer) -> Result<e2ee_stream::EncryptedStream> {
+        let key = e2ee_stream::shared_key(&shared_key)?;
+        let accepted = self.inner.accept_bi().await.map_err(quic_err);
+        let (send, recv) = self.record_stream("accept_bi", accepted, |(send, _)| send.id())?;
+        let role = e2ee_stream::Role::Acceptor;
+        Ok(e2ee_stream::EncryptedStream::new(
+            Some(send),
+            Some(recv),
+            &key,
+            role,
+            None,
+        ))
+    }
+
+    /// Sends `payload` as a tagged datagram and resolves with the peer's matching response.
+    /// The tagging format is documented in `datagram.rs`; datagrams are unreliable, so a lost
+    /// request or response surfaces as a timeout.
+    #[napi]
+    pub async fn datagram_exchange(&self, payload: Buffer, timeout_ms: u32) -> Result<Buffer> {
+        let timeout = Duration::from_millis(timeout_ms.into());
+        let response = self
+            .datagrams()
+            .exchange(&payload, timeout)
+            .await
+            .inspect_err(|err| self.record("datagram_exchange_failed", err.reason.clone()))?;
+        Ok(Buffer::from(response))
+    }
+
+    /// Measures one-way delay with `samples` timestamped datagram probes; the peer must have
+    /// called `enable_owd_responder`. Resolves with `{ sent, received, rtt_ms, forward_ms,
+    /// reverse_ms, clock_offset_ms }` JSON, delays as `{ min, avg, max }`. Forward and reverse
+    /// delays include the offset between the two wall clocks; see `owd.rs`.
+    #[napi]
+    pub async fn measure_owd(&self, samples: u32) -> Result<String> {
+        if samples == 0 {
+            return Err(error::invalid_argument("samples must be greater than 0"));
+        }
+        Ok(self.datagrams().measure_owd(samples).await?.to_string())
+    }
+
+    /// Answers the peer's `measure_owd` probes with this side's receive timestamps.
+    #[napi]
+    pub fn enable_owd_responder(&self) {
+        self.datagrams().set_owd_responder(true);
+    }
+
+    #[napi]
+    pub async fn accept_datagram_exchange(&self) -> Result<datagram::DatagramRequest> {
+        self.datagrams().next_request().await
+    }
+
+    /// Largest payload `send_datagram` accepts on the current path, or `None` if the peer
+    /// does not support datagrams. One byte below quinn's limit, for the kind byte that keeps
+    /// application datagrams apart from the SDK's own (see `datagram.rs`). Can change as the
+    /// path MTU is discovered.
+    #[napi]
+    pub fn max_datagram_size(&self) -> Option<u32> {
+        self.inner.max_datagram_size().map(|max| max.saturating_sub(1) as u32)
+    }
+
+    /// Sends `data` as one unreliable, unordered datagram. Fails rather than truncating when it
+    /// exceeds `max_datagram_size`. Subject to `set_datagram_queue_limit`.
+    #[napi]
+    pub fn send_datagram(&self, data: Buffer) -> Result<()> {
+        let max = self
+            .max_datagram_size()
+            .ok_or_else(|| error::invalid_argument("datagrams not supported by peer"))?;
+        if data.len() > max as usize {
+            return Err(error::invalid_argument(format!(
+                "datagram too large ({} bytes, max {max})",
+                data.len()
+            )));
+        }
+        let mut datagram = Vec::with_capacity(1 + data.len());
+        datagram.push(datagram::KIND_RAW);
+        datagram.extend_from_slice(&data);
+        self.datagram_queue.send(datagram).map_err(quic_err)
+    }
+
+    /// Resolves with the next datagram from the peer's `send_datagram`, or `None` once the
+    /// connection was closed by either application (mirroring FIN on `BiStream::read`). Other
+    /// close reasons reject.
+    #[napi]
+    pub async fn read_datagram(&self) -> Result<Option<Buffer>> {
+        if let Some(datagram) = self.datagrams().next_raw().await {
+            return Ok(Some(Buffer::from(datagram)));
+        }
+        match self.inner.close_reason() {
+            None | Some(quinn::ConnectionError::ApplicationClosed(_) | quinn::ConnectionError::LocallyClosed) => {
+                Ok(None)
+            }
+            Some(reason) => Err(quic_err(reason)),
+        }
+    }
+
+    /// Datagrams from the peer's `send_datagram` that were discarded unread because 1024 were
+    /// already waiting for `read_datagram`. Calling this starts draining quinn's receive buffer
+    /// (`datagramReceiveBufferSize`) like `read_datagram` does; until something does, arrivals
+    /// wait there and an overflow of it, which quinn does not report, goes uncounted.
+    #[napi]
+    pub fn datagrams_dropped(&self) -> i64 {
+        self.datagrams().raw_dropped() as i64
+    }
+
+    /// Calls `cb` with each datagram from the peer's `send_datagram` until the connection
+    /// closes. Datagrams go to the callback instead of `read_datagram`, which then only
+    /// resolves `null` or rejects on close. Registering again replaces the previous callback.
+    #[napi]
+    pub fn on_datagram(&self, cb: JsCallback<Buffer>) {
+        let demux = self.datagrams().clone();
+        let task = spawn(async move {
+            while let Some(datagram) = demux.next_raw().await {
+                cb.call(Buffer::from(datagram), ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        });
+        if let Some(previous) = self.datagram_listener.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+    }
+
+    /// Opens the ordered-but-unreliable datagram flow `flow_id`; both sides open the same id.
+    /// Delivery order, reorder window and loss handling are described in `datagram_flow.rs`.
+    #[napi]
+    pub fn open_datagram_flow(&self, flow_id: u32) -> datagram_flow::DatagramFlow {
+        self.datagrams().open_flow(flow_id)
+    }
+
+    /// Sends one message, reliably on a shared uni stream (the default) or, with
+    /// `{ "reliable": false }`, as a datagram. Unreliable messages too large for a datagram go
+    /// over the stream instead. The peer receives both kinds with `recv`; see `messages.rs`.
+    #[napi]
+    pub async fn send(&self, data: Buffer, options_json: Option<String>) -> Result<()> {
+        let options: SendOptions = parse_options(options_json)?;
+        self.rate_limit.acquire(data.len()).await;
+        let slot = self.acquire_open_slot(false);
+        self.messages.send(&data, options.reliable.unwrap_or(true), slot).await
+    }
+
+    #[napi]
+    pub async fn recv(&self) -> Result<Buffer> {
+        self.datagrams();
+        self.uni_streams();
+        Ok(Buffer::from(self.messages.recv().await?))
+    }
+
+    /// Bandwidth the congestion controller currently allows, in bits per second. quinn's
+    /// controllers expose no delivery-rate estimate, so this is the congestion window over
+    /// the RTT estimate: an upper bound that tracks the controller as it adapts. `None`
+    /// before the first RTT sample.
+    #[napi]
+    pub fn estimated_bandwidth_bps(&self) -> Option<i64> {
+        let path = self.inner.stats().path;
+        let rtt = path.rtt.as_secs_f64();
+        if path.rtt.is_zero() {
+            return None;
+        }
+        Some((path.cwnd as f64 * 8.0 / rtt) as i64)
+    }
+
+    /// Current `{ rtt_ms, cwnd, bytes_sent, bytes_recv, packets_sent, packets_lost,
+    /// congestion_events, path_mtu }` JSON, cheap enough to poll for metrics export.
+    #[napi]
+    pub fn stats(&self) -> Result<String> {
+        ConnectionStats::to_json(&self.inner)
+    }
+
+    /// quinn's smoothed RTT estimate in milliseconds, without building the `stats` JSON.
+    #[napi]
+    pub fn rtt_ms(&self) -> f64 {
+        self.inner.rtt().as_secs_f64() * 1000.0
+    }
+
+    /// Loss-detection counters as `{ sent_packets, lost_packets, lost_bytes,
+    /// congestion_events, black_holes_detected }` JSON. quinn does not record spurious
+    /// retransmits (packets declared lost that are acknowledged later), so there is no such
+    /// count; on a reordering path, a loss rate that drops when the reordering threshold is
+    /// raised is the nearest signal.
+    #[napi]
+    pub fn loss_stats(&self) -> String {
+        let path = self.inner.stats().path;
+        serde_json::json!({
+            "sent_packets": path.sent_packets,
+            "lost_packets": path.lost_packets,
+            "lost_bytes": path.lost_bytes,
+            "congestion_events": path.congestion_events,
+            "black_holes_detected": path.black_holes_detected,
+        })
+        .to_string()
+    }
+
+    /// Caps the send rate of `BiStream` writes and `send` at `target_bps`, allowing bursts of
+    /// one bandwidth-delay product (`target_bps` × current RTT) after idle periods. Writes
+    /// wait for budget rather than fail; the bucket is described in `rate_limit.rs`. `0`
+    /// removes the limit. Datagram exchanges and flows are not limited.
+    #[napi]
+    pub fn set_adaptive_rate_limit(&self, target_bps: u32) {
+        self.rate_limit.set(target_bps);
+    }
+
+    /// Average bits per second admitted since the limit was set, or `None` without a limit.
+    #[napi]
+    pub fn effective_rate_bps(&self) -> Option<f64> {
+        self.rate_limit.effective_bps()
+    }
+
+    /// Datagrams sent on this connection (exchanges, flows, unreliable messages) that are
+    /// still queued. An estimate: quinn does not expose its queue, so this is datagrams
+    /// submitted minus datagram frames sent, reset to 0 whenever quinn's send buffer is empty.
+    #[napi]
+    pub fn outstanding_datagrams(&self) -> Result<u32> {
+        Ok(self.datagram_queue.outstanding())
+    }
+
+    /// Caps queued datagrams at `limit`; `0` removes the cap. Past it, `overflow` decides:
+    /// `"error"` fails the send, `"drop_newest"` discards the new datagram, and `"drop_oldest"`
+    /// holds the new one back and discards the oldest held-back one. Datagrams quinn already
+    /// has cannot be recalled, so with `"drop_oldest"` quinn gets at most half the limit
+    /// (rounded up) and the rest is held back. Discarded datagrams are not reported; the send
+    /// still resolves.
+    #[napi]
+    pub fn set_datagram_queue_limit(&self, limit: u32, overflow: String) -> Result<()> {
+        let overflow = datagram_queue::Overflow::parse(&overflow)?;
+        self.datagram_queue.set_limit(limit, overflow);
+        Ok(())
+    }
+
+    /// Resolves once roughly `min_bytes` could be sent without queueing behind congestion
+    /// control. quinn exposes neither bytes in flight nor stream send buffers, so this is an
+    /// estimate: the smaller of the congestion window and the free datagram send buffer (which
+    /// only drains as congestion control lets packets out), checked every 10 ms.
+    #[napi]
+    pub async fn await_send_capacity(&self, min_bytes: u32) -> Result<()> {
+        let min_bytes = u64::from(min_bytes);
+        let mut ticker = tokio::time::interval(SEND_CAPACITY_POLL);
+        loop {
+            if let Some(reason) = self.inner.close_reason() {
+                return Err(quic_err(reason));
+            }
+            let cwnd = self.inner.stats().path.cwnd;
+            let buffer = self.inner.datagram_send_buffer_space() as u64;
+            if cwnd.min(buffer) >= min_bytes {
+                return Ok(());
+            }
+            ticker.tick().await;
+        }
+    }
+
+    /// Emits `{ t, cwnd, rtt, congestion_events }` JSON samples every `interval_ms` until
+    /// stopped or the connection closes. `t` is milliseconds since sampling started and `rtt`
+    /// is in milliseconds. quinn does not expose bytes in flight, so it is not sampled.
+    /// Starting again replaces the previous sampler.
+    #[napi]
+    pub fn start_cwnd_sampling(&self, interval_ms: u32, callback: JsCallback<String>) -> Result<()> {
+        if interval_ms == 0 {
+            return Err(error::invalid_argument("interval_ms must be greater than 0"));
+        }
+        let conn = self.inner.clone();
+        let task = spawn(async move {
+            let started = tokio::time::Instant::now();
+            let mut ticker = tokio::time::interval(Duration::from_millis(interval_ms.into()));
+            loop {
+                ticker.tick().await;
+                if conn.close_reason().is_some() {
+                    break;
+                }
+                let path = conn.stats().path;
+                let sample = serde_json::json!({
+                    "t": started.elapsed().as_millis() as u64,
+                    "cwnd": path.cwnd,
+                    "rtt": path.rtt.as_secs_f64() * 1000.0,
+                    "congestion_events": path.congestion_events,
+                });
+                callback.call(sample.to_string(), ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        });
+
+        if let Some(previous) = self.cwnd_sampler.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    #[napi]
+    pub fn stop_cwnd_sampling(&self) {
+        if let Some(task) = self.cwnd_sampler.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+
+    /// Default for what bi streams opened or accepted from now on do when garbage-collected
+    /// without `finish()` or `reset()`; see `BiStream::set_drop_behavior`.
+    #[napi]
+    pub fn set_stream_drop_behavior(&self, behavior: String, error_code: Option<u32>) -> Result<()> {
+        *self.drop_behavior.lock().unwrap() = DropBehavior::parse(&behavior, error_code)?;
+        Ok(())
+    }
+
+    /// Closes the connection with `error_code`/`reason` once no stream data or datagrams have
+    /// been received for `ms`. Unlike the QUIC idle timeout, keepalives and other control
+    /// frames do not count as activity. Activity is sampled from frame counters every quarter
+    /// of the timeout, so the close may come up to that much late. `0` disables; setting it
+    /// again replaces the previous timeout.
+    #[napi]
+    pub fn set_inactivity_timeout(&self, ms: u32, error_code: u32, reason: Option<String>) {
+        let previous = self.inactivity_watch.lock().unwrap().take();
+        if let Some(previous) = previous {
+            previous.abort();
+        }
+        if ms == 0 {
+            return;
+        }
+
+        let conn = self.inner.clone();
+        let events = self.events.clone();
+        let timeout = Duration::from_millis(ms.into());
+        let poll = (timeout / 4).max(Duration::from_millis(10));
+        let reason = reason.unwrap_or_else(|| "inactivity timeout".to_string());
+        let task = spawn(async move {
+            let received = |conn: &quinn::Connection| {
+                let frames = conn.stats().frame_rx;
+                frames.stream + frames.datagram
+            };
+            let mut last = received(&conn);
+            let mut last_activity = tokio::time::Instant::now();
+            loop {
+                tokio::time::sleep(poll).await;
+                if conn.close_reason().is_some() {
+                    break;
+                }
+                let now = received(&conn);
+                if now != last {
+                    last = now;
+                    last_activity = tokio::time::Instant::now();
+                } else if last_activity.elapsed() >= timeout {
+                    if let Some(events) = events.get() {
+                        events.record("inactivity_timeout", format!("code {error_code}: {reason}"));
+                    }
+                    conn.close(error_code.into(), reason.as_bytes());
+                    break;
+                }
+            }
+        });
+        *self.inactivity_watch.lock().unwrap() = Some(task);
+    }
+
+    /// Bounds the connection's lifetime, counted from establishment, for key hygiene. At
+    /// `max_lifetime_ms` `on_expiring` is called with `grace_ms` so the application can
+    /// establish a replacement and move traffic over; the connection is closed with code 0
+    /// ("max lifetime reached") once the grace period ends. Setting it again replaces the
+    /// previous schedule. Migrating traffic itself is left to the application.
+    #[napi]
+    pub fn set_max_lifetime(&self, max_lifetime_ms: u32, grace_ms: u32, on_expiring: JsCallback<u32>) {
+        let conn = self.inner.clone();
+        let events = self.events.clone();
+        let expires_at =
+            tokio::time::Instant::from_std(self.established) + Duration::from_millis(max_lifetime_ms.into());
+        let task = spawn(async move {
+            tokio::time::sleep_until(expires_at).await;
+            if conn.close_reason().is_some() {
+                return;
+            }
+            if let Some(events) = events.get() {
+                events.record("lifetime_expiring", format!("closing in {grace_ms} ms"));
+            }
+            on_expiring.call(grace_ms, ThreadsafeFunctionCallMode::NonBlocking);
+            tokio::time::sleep(Duration::from_millis(grace_ms.into())).await;
+            conn.close(0u32.into(), b"max lifetime reached");
+        });
+        if let Some(previous) = self.lifetime_watch.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+    }
+
+    /// One JSON record for logging at teardown, also valid after close:
+    /// `{ duration_ms, bytes_sent, bytes_received, streams_opened, streams_accepted, rtt_ms,
+    /// lost_packets, migration_count, close_reason }`. Bytes are UDP payload bytes, so they
+    /// include QUIC overhead. quinn keeps no RTT history, so `rtt_ms` is the latest smoothed
+    /// estimate rather than a peak. `close_reason` is `null` while the connection is open.
+    #[napi]
+    pub fn summary(&self) -> Result<String> {
+        let stats = self.inner.stats();
+        let summary = serde_json::json!({
+            "duration_ms": self.established.elapsed().as_millis() as u64,
+            "bytes_sent": stats.udp_tx.bytes,
+            "bytes_received": stats.udp_rx.bytes,
+            "streams_opened": self.streams_opened.load(Ordering::Relaxed),
+            "streams_accepted": self.streams_accepted.load(Ordering::Relaxed),
+            "rtt_ms": stats.path.rtt.as_secs_f64() * 1000.0,
+            "lost_packets": stats.path.lost_packets,
+            "migration_count": self.observe_path().1,
+            "close_reason": self.inner.close_reason().map(|reason| reason.to_string()),
+        });
+        Ok(summary.to_string())
+    }
+
+    /// Starts recording the last `capacity` events (streams opened and accepted, failures,
+    /// close) for postmortem debugging. Can only be enabled once per connection.
+    #[napi]
+    pub fn enable_event_log(&self, capacity: u32) -> Result<()> {
+        if capacity == 0 {
+            return Err(error::invalid_argument("capacity must be greater than 0"));
+        }
+        self.events
+            .set(event_log::EventLog::new(capacity as usize))
+            .map_err(|_| to_napi_err("event log already enabled"))
+    }
+
+    /// Returns the recorded events as a JSON array of `{ t, kind, detail }`, oldest first.
+    /// Remains available after the connection has closed.
+    #[napi]
+    pub fn event_log(&self) -> Result<String> {
+        let events = self.events.get().ok_or_else(|| to_napi_err("event log not enabled"))?;
+        self.record_close_reason();
+        Ok(events.to_json())
+    }
+
+    /// Resolves once the connection is closed, for any reason, with `{ kind, code?, reason? }`.
+    /// `kind` is one of `timed_out` (idle timeout, no application reason), `locally_closed`,
+    /// `application_closed` (the peer's `code` and `reason`), `connection_closed` (a transport
+    /// error the peer reported), `transport_error` (one detected locally), `reset`,
+    /// `version_mismatch` or `cids_exhausted`. Resolves immediately if already closed.
+    #[napi]
+    pub async fn closed(&self) -> Result<String> {
+        let reason = self.inner.closed().await;
+        self.record_close_reason();
+        Ok(close_reason_json(&reason).to_string())
+    }
+
+    /// Calls `cb` once with the same `{ kind, code?, reason? }` as `closed` when the connection
+    /// ends, or right away if it already has. Each registered callback is called.
+    #[napi]
+    pub fn on_close(&self, cb: JsCallback<String>) {
+        let conn = self.inner.clone();
+        spawn(async move {
+            let reason = conn.closed().await;
+            cb.call(
+                close_reason_json(&reason).to_string(),
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        });
+    }
+
+    /// `false` once the connection has closed for any reason, e.g. to evict pooled
+    /// connections. A `true` can be stale by the next call: a dead peer is only noticed at
+    /// the idle timeout.
+    #[napi]
+    pub fn is_alive(&self) -> bool {
+        self.inner.close_reason().is_none()
+    }
+
+    /// The same `{ kind, code?, reason? }` as `closed`, without waiting: `null` while the
+    /// connection is open.
+    #[napi]
+    pub fn close_reason(&self) -> Option<String> {
+        let reason = self.inner.close_reason()?;
+        self.record_close_reason();
+        Some(close_reason_json(&reason).to_string())
+    }
+
+    #[napi]
+    pub fn close(&self, error_code: u32, reason: Option<String>) {
+        let reason = reason.unwrap_or_else(|| "closed".to_string());
+        self.record_close_reason();
+        self.record("close", format!("code {error_code}: {reason}"));
+        self.inner.close(error_code.into(), reason.as_bytes());
+    }
+}
+
+/// Lets a stream opened with a trace id add its lifecycle events to the connection's log.
+struct StreamTrace {
+    events: Arc<OnceLock<event_log::EventLog>>,
+    detail: String,
+}
+
+/// Held by a locally opened stream until both halves close or the stream is dropped.
+struct OpenSlot {
+    _permit: Option<tokio::sync::OwnedSemaphorePermit>,
+    outstanding: Arc<AtomicU32>,
+}
+
+impl Drop for OpenSlot {
+    fn drop(&mut self) {
+        self.outstanding.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The `OpenSlot` of a compressed or encrypted stream, released once both halves are closed
+/// like `BiStream`'s. `None` for accepted streams, which take no slot.
+struct HeldSlot {
+    slot: std::sync::Mutex<Option<OpenSlot>>,
+    send_closed: AtomicBool,
+    recv_closed: AtomicBool,
+}
+
+impl HeldSlot {
+    fn new(slot: Option<OpenSlot>, send_open: bool, recv_open: bool) -> Self {
+        let held = Self {
+            slot: std::sync::Mutex::new(slot),
+            send_closed: AtomicBool::new(false),
+            recv_closed: AtomicBool::new(false),
+        };
+        if !send_open {
+            held.send_closed();
+        }
+        if !recv_open {
+            held.recv_closed();
+        }
+        held
+    }
+
+    fn send_closed(&self) {
+        self.half_closed(&self.send_closed);
+    }
+
+    fn recv_closed(&self) {
+        self.half_closed(&self.recv_closed);
+    }
+
+    fn half_closed(&self, flag: &AtomicBool) {
+        flag.store(true, Ordering::Release);
+        if self.send_closed.load(Ordering::Acquire) && self.recv_closed.load(Ordering::Acquire) {
+            self.slot.lock().unwrap().take();
+        }
+    }
+}
+
+/// Counts a stream in `open_stream_counts` for as long as it is held.
+struct LiveStream(Arc<AtomicU32>);
+
+impl LiveStream {
+    fn new(count: &Arc<AtomicU32>) -> Self {
+        count.fetch_add(1, Ordering::Relaxed);
+        Self(count.clone())
+    }
+}
+
+impl Drop for LiveStream {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Lets JS abandon awaited calls without closing anything: `cancel` makes every call running
+/// under `run` at that moment reject with `CANCELLED`, and later calls are unaffected. Only for
+/// futures quinn documents as cancel-safe, so nothing is lost.
+struct Canceller(tokio::sync::watch::Sender<u64>);
+
+impl Canceller {
+    fn new() -> Self {
+        Self(tokio::sync::watch::Sender::new(0))
+    }
+
+    fn cancel(&self) {
+        self.0.send_modify(|generation| *generation += 1);
+    }
+
+    async fn run<T>(&self, op: impl Future<Output = Result<T>>) -> Result<T> {
+        let mut cancelled = self.0.subscribe();
+        tokio::select! {
+            result = op => result,
+            _ = cancelled.changed() => Err(error::coded(Code::Cancelled, "cancelled")),
+        }
+    }
+}
+
+/// What a `BiStream` does with halves still open when it is garbage-collected.
+#[derive(Clone, Copy, Default)]
+enum DropBehavior {
+    /// Sends any coalesced bytes, then FIN; the peer sees a normal end of stream.
+    #[default]
+    Finish,
+    /// Resets the send half and stops the receive half with the code, so the peer can tell an
+    /// abandoned stream from a finished one.
+    Reset(u32),
+}
+
+impl DropBehavior {
+    fn parse(behavior: &str, error_code: Option<u32>) -> Result<Self> {
+        match (behavior, error_code) {
+            ("finish", _) => Ok(Self::Finish),
+            ("reset", Some(code)) => Ok(Self::Reset(code)),
+            ("reset", None) => Err(error::invalid_argument("reset drop behavior needs an error code")),
+            _ => Err(error::invalid_argument(format!("unknown drop behavior {behavior:?}"))),
+        }
+    }
+}
+
+/// Bytes buffered by write coalescing before they are sent without waiting for the window.
+const COALESCE_MAX_BYTES: usize = 16 * 1024;
+
+const FILE_CHUNK_BYTES: usize = 64 * 1024;
+
+/// How much `write_with_progress` writes between progress callbacks.
+const PROGRESS_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Small writes held back by write coalescing, sent as one write when the window elapses.
+#[derive(Default)]
+struct PendingWrites {
+    window: Option<Duration>,
+    buf: Vec<u8>,
+    armed: bool,
+}
+
+async fn drain_pending(send: &mut quinn::SendStream, pending: &std::sync::Mutex<PendingWrites>) -> Result<()> {
+    let buf = {
+        let mut pending = pending.lock().unwrap();
+        pending.armed = false;
+        std::mem::take(&mut pending.buf)
+    };
+    if !buf.is_empty() {
+        send.write_all(&buf).await.map_err(quic_err)?;
+    }
+    Ok(())
+}
+
+#[napi]
+pub struct BiStream {
+    id: quinn::StreamId,
+    /// Whether this end opened the stream rather than accepted it.
+    opened: bool,
+    send: Arc<tokio::sync::Mutex<Option<quinn::SendStream>>>,
+    pending: Arc<std::sync::Mutex<PendingWrites>>,
+    recv: tokio::sync::Mutex<Option<quinn::RecvStream>>,
+    reading: tokio::sync::watch::Sender<bool>,
+    send_closed: AtomicBool,
+    recv_closed: AtomicBool,
+    /// Set by `reset`, so later writes can say why the send half is gone.
+    send_reset: AtomicBool,
+    slot: std::sync::Mutex<Option<OpenSlot>>,
+    live: std::sync::Mutex<Option<LiveStream>>,
+    trace: Option<StreamTrace>,
+    rate_limit: Arc<rate_limit::RateLimit>,
+    on_writable: std::sync::Mutex<Option<Arc<JsCallback<()>>>>,
+    drop_behavior: std::sync::Mutex<DropBehavior>,
+    reads: Canceller,
+    /// Ciphers for `write_e2ee` / `read_e2ee`, with the key each was created with.
+    send_cipher: std::sync::Mutex<Option<([u8; 32], e2ee_stream::MessageCipher)>>,
+    recv_cipher: std::sync::Mutex<Option<([u8; 32], e2ee_stream::MessageCipher)>>,
+    /// The connection's `maxReadBytes`.
+    max_read_bytes: usize,
+    /// Application bytes handed to and taken from the stream, for `bytes_written` /
+    /// `bytes_read`.
+    bytes_written: AtomicU64,
+    bytes_read: AtomicU64,
+}
+
+impl Drop for BiStream {
+    fn drop(&mut self) {
+        let behavior = *self.drop_behavior.get_mut().unwrap();
+        if let (DropBehavior::Reset(code), Some(mut recv)) = (behavior, self.recv.get_mut().take()) {
+            let _ = recv.stop(code.into());
+        }
+        // A pending coalescing flush may still hold the send lock, so close from a task.
+        let send = self.send.clone();
+        let pending = self.pending.clone();
+        spawn(async move {
+            let Some(mut send) = send.lock().await.take() else {
+                return;
+            };
+            match behavior {
+                DropBehavior::Finish => {
+                    let _ = drain_pending(&mut send, &pending).await;
+                    let _ = send.finish();
+                }
+                DropBehavior::Reset(code) => {
+                    let _ = send.reset(code.into());
+                }
+            }
+        });
+    }
+}
+
+impl BiStream {
+    fn new(
+        send: quinn::SendStream,
+        recv: quinn::RecvStream,
+        opened: bool,
+        rate_limit: Arc<rate_limit::RateLimit>,
+        max_read_bytes: usize,
+    ) -> Self {
+        Self {
+            id: send.id(),
+            opened,
+            send: Arc::new(tokio::sync::Mutex::new(Some(send))),
+            pending: Arc::new(std::sync::Mutex::new(PendingWrites::default())),
+            recv: tokio::sync::Mutex::new(Some(recv)),
+            reading: tokio::sync::watch::Sender::new(true),
+            send_closed: AtomicBool::new(false),
+            recv_closed: AtomicBool::new(false),
+            send_reset: AtomicBool::new(false),
+            slot: std::sync::Mutex::new(None),
+            live: std::sync::Mutex::new(None),
+            trace: None,
+            rate_limit,
+            on_writable: std::sync::Mutex::new(None),
+            drop_behavior: std::sync::Mutex::new(DropBehavior::default()),
+            reads: Canceller::new(),
+            send_cipher: std::sync::Mutex::new(None),
+            recv_cipher: std::sync::Mutex::new(None),
+            max_read_bytes,
+            bytes_written: AtomicU64::new(0),
+            bytes_read: AtomicU64::new(0),
+        }
+    }
+
+    fn e2ee_role(&self) -> e2ee_stream::Role {
+        e2ee_stream::Role::of(self.opened)
+    }
+
+    fn count_written(&self, n: usize) {
+        self.bytes_written.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    fn count_read(&self, n: usize) {
+        self.bytes_read.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    fn with_drop_behavior(self, behavior: DropBehavior) -> Self {
+        *self.drop_behavior.lock().unwrap() = behavior;
+        self
+    }
+
+    fn with_live(self, live: LiveStream) -> Self {
+        *self.live.lock().unwrap() = Some(live);
+        self
+    }
+
+    fn with_slot(self, slot: OpenSlot) -> Self {
+        *self.slot.lock().unwrap() = Some(slot);
+        self
+    }
+
+    fn with_trace(mut self, trace: StreamTrace) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    fn trace(&self, kind: &str) {
+        if let Some(trace) = &self.trace {
+            if let Some(events) = trace.events.get() {
+                events.record(kind, trace.detail.clone());
+            }
+        }
+    }
+
+    /// Why a write found the send half gone.
+    fn send_gone(&self) -> Error {
+        if self.send_reset.load(Ordering::Acquire) {
+            error::coded(Code::StreamReset, "stream reset")
+        } else {
+            error::coded(Code::StreamClosed, "send stream closed")
+        }
+    }
+
+    /// Records that one half is done; the open slot is released once both are.
+    fn half_closed(&self, flag: &AtomicBool) {
+        flag.store(true, Ordering::Release);
+        if self.send_closed.load(Ordering::Acquire) && self.recv_closed.load(Ordering::Acquire) {
+            self.slot.lock().unwrap().take();
+            self.live.lock().unwrap().take();
+        }
+    }
+
+    /// Sends whatever is buffered once `window` elapses. A failure here is not reported, but
+    /// it leaves the stream broken, so the next write or flush fails.
+    fn arm_flush(&self, window: Duration) {
+        let send = self.send.clone();
+        let pending = self.pending.clone();
+        spawn(async move {
+            tokio::time::sleep(window).await;
+            if let Some(send) = send.lock().await.as_mut() {
+                let _ = drain_pending(send, &pending).await;
+            }
+        });
+    }
+
+    /// `write_all` that notices when the stream is blocked and fires `on_writable` once the
+    /// blocked write goes through.
+    async fn write_all_notify(&self, send: &mut quinn::SendStream, mut data: &[u8]) -> Result<()> {
+        while !data.is_empty() {
+            let written = match poll_once(send.write(data)).await {
+                Some(written) => written.map_err(quic_err)?,
+                None => {
+                    let written = send.write(data).await.map_err(quic_err)?;
+                    let cb = self.on_writable.lock().unwrap().clone();
+                    if let Some(cb) = cb {
+                        cb.call((), ThreadsafeFunctionCallMode::NonBlocking);
+                    }
+                    written
+                }
+            };
+            data = &data[written..];
+        }
+        Ok(())
+    }
+
+    /// Waits while reading is paused. Not consuming data keeps quinn from extending the peer's
+    /// flow-control window, which is what applies backpressure.
+    async fn wait_reading(&self) {
+        let mut reading = self.reading.subscribe();
+        let _ = reading.wait_for(|reading| *reading).await;
+    }
+
+    async fn write_chunks(&self, chunks: &[&[u8]]) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some(send) = guard.as_mut() else {
+            return Err(self.send_gone());
+        };
+        let len = chunks.iter().map(|chunk| chunk.len()).sum::<usize>();
+        if len == 0 {
+            return Ok(());
+        }
+        self.rate_limit.acquire(len).await;
+
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if let Some(window) = pending.window {
+                for chunk in chunks {
+                    pending.buf.extend_from_slice(chunk);
+                }
+                if pending.buf.len() < COALESCE_MAX_BYTES {
+                    if !pending.armed {
+                        pending.armed = true;
+                        self.arm_flush(window);
+                    }
+                    self.count_written(len);
+                    return Ok(());
+                }
+            }
+        }
+
+        // Full coalescing buffer, or leftovers from before coalescing was turned off.
+        drain_pending(send, &self.pending).await?;
+        if self.pending.lock().unwrap().window.is_none() {
+            for chunk in chunks {
+                self.write_all_notify(send, chunk).await?;
+            }
+        }
+        self.count_written(len);
+        Ok(())
+    }
+
+    /// `read` without cancellation. Cancel-safe: dropped while waiting, it consumes nothing.
+    async fn read_chunk(&self, max_bytes: Option<u32>) -> Result<Option<Buffer>> {
+        self.wait_reading().await;
+        let mut guard = self.recv.lock().await;
+        let Some(recv) = guard.as_mut() else {
+            return Ok(None);
+        };
+
+        let max = (max_bytes.unwrap_or(64 * 1024) as usize).min(self.max_read_bytes);
+        if max == 0 {
+            return Ok(Some(Buffer::from(Vec::new())));
+        }
+        let chunk = recv.read_chunk(max, true).await.map_err(quic_err)?;
+
+        let Some(chunk) = chunk else {
+            // FIN
+            *guard = None;
+            self.half_closed(&self.recv_closed);
+            self.trace("stream_recv_closed");
+            return Ok(None);
+        };
+
+        self.count_read(chunk.bytes.len());
+        Ok(Some(Buffer::from(chunk.bytes.to_vec())))
+    }
+
+    async fn read_available(&self, max_total: u32) -> Result<Option<Buffer>> {
+        self.wait_reading().await;
+        let mut guard = self.recv.lock().await;
+        let Some(recv) = guard.as_mut() else {
+            return Ok(None);
+        };
+
+        let max = (max_total as usize).min(self.max_read_bytes);
+        if max == 0 {
+            return Ok(Some(Buffer::from(Vec::new())));
+        }
+        let Some(first) = recv.read_chunk(max, true).await.map_err(quic_err)? else {
+            // FIN
+            *guard = None;
+            self.half_closed(&self.recv_closed);
+            self.trace("stream_recv_closed");
+            return Ok(None);
+        };
+        let mut out = first.bytes.to_vec();
+        // Only what is already buffered; FIN or an error surfaces on the next read.
+        while out.len() < max {
+            match poll_once(recv.read_chunk(max - out.len(), true)).await {
+                Some(Ok(Some(chunk))) => out.extend_from_slice(&chunk.bytes),
+                _ => break,
+            }
+        }
+        self.count_read(out.len());
+        Ok(Some(Buffer::from(out)))
+    }
+}
+
+#[napi]
+impl BiStream {
+    /// An empty `data` is a no-op: nothing is sent, but writing to a closed stream still errors.
+    ///
+    /// Writes are applied in the order they acquire the send lock, which is FIFO among waiters.
+    /// Concurrent calls reach the lock in task scheduling order rather than JS call order, so
+    /// callers needing call order must serialize (the TS `QuicBiStream` wrapper does).
+    #[napi]
+    pub async fn write(&self, data: Buffer) -> Result<()> {
+        self.write_chunks(&[&data[..]]).await
+    }
+
+    /// Writes `chunks` back to back under one hold of the send lock, so no other write lands
+    /// between them, and with one call across the napi boundary. Same contract as `write` for
+    /// the concatenation; the chunks are not copied.
+    #[napi]
+    pub async fn write_all(&self, chunks: Vec<Buffer>) -> Result<()> {
+        let chunks: Vec<&[u8]> = chunks.iter().map(|chunk| &chunk[..]).collect();
+        self.write_chunks(&chunks).await
+    }
+
+    /// `write` in 64 KiB pieces, calling `on_progress` with the cumulative bytes written after
+    /// each one. The send lock is held throughout, so other writes queue behind the whole of
+    /// `data`. "Written" means handed to quinn's send buffer, not acknowledged by the peer.
+    #[napi]
+    pub async fn write_with_progress(&self, data: Buffer, on_progress: JsCallback<f64>) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some(send) = guard.as_mut() else {
+            return Err(self.send_gone());
+        };
+        drain_pending(send, &self.pending).await?;
+        let mut written = 0;
+        for chunk in data.chunks(PROGRESS_CHUNK_BYTES) {
+            self.rate_limit.acquire(chunk.len()).await;
+            self.write_all_notify(send, chunk).await?;
+            self.count_written(chunk.len());
+            written += chunk.len();
+            on_progress.call(written as f64, ThreadsafeFunctionCallMode::NonBlocking);
+        }
+        Ok(())
+    }
+
+    /// `write` that gives up after `timeout_ms`, e.g. when the peer stops reading and flow
+    /// control never reopens. On expiry the send half is reset with `error_code` (default `0`),
+    /// since part of `data` may already be sent, and the call rejects with `TIMED_OUT`; later
+    /// writes fail with "stream reset". The timeout includes waiting for a write in progress.
+    #[napi]
+    pub async fn write_timeout(&self, data: Buffer, timeout_ms: u32, error_code: Option<u32>) -> Result<()> {
+        let timeout = Duration::from_millis(timeout_ms.into());
+        if let Ok(result) = tokio::time::timeout(timeout, self.write(data)).await {
+            return result;
+        }
+        self.reset(error_code.unwrap_or(0)).await?;
+        Err(error::coded(
+            Code::TimedOut,
+            format!("write timed out after {timeout_ms} ms"),
+        ))
+    }
+
+    /// Calls `cb` whenever a write that was blocked on flow control or congestion makes
+    /// progress again, so a scheduler can resume producers without polling. Applies to
+    /// `write` and `write_file`; replaces any earlier callback.
+    #[napi]
+    pub fn on_writable(&self, cb: JsCallback<()>) {
+        *self.on_writable.lock().unwrap() = Some(Arc::new(cb));
+    }
+
+    /// Batches small writes: they are buffered for up to `coalesce_ms` (or until 16 KiB are
+    /// pending) and sent together, trading latency for fewer packets. `0` turns coalescing off; anything
+    /// still buffered goes out with the next write, `flush` or `finish`.
+    #[napi]
+    pub fn set_write_coalescing(&self, coalesce_ms: u32) {
+        self.pending.lock().unwrap().window = match coalesce_ms {
+            0 => None,
+            ms => Some(Duration::from_millis(ms.into())),
+        };
+    }
+
+    /// Sends buffered coalesced writes now, for messages that should not wait out the window.
+    #[napi]
+    pub async fn flush(&self) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some(send) = guard.as_mut() else {
+            return Err(self.send_gone());
+        };
+        drain_pending(send, &self.pending).await
+    }
+
+    #[napi]
+    pub async fn finish(&self) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some(mut send) = guard.take() else {
+            return Ok(());
+        };
+        self.half_closed(&self.send_closed);
+        self.trace("stream_send_closed");
+        drain_pending(&mut send, &self.pending).await?;
+        send.finish().map_err(quic_err)?;
+        Ok(())
+    }
+
+    /// Abandons the send half: the peer's reads fail with `error_code`, and coalesced or
+    /// unacknowledged bytes are discarded. Later writes fail with "stream reset". A no-op once
+    /// the send half is closed.
+    #[napi]
+    pub async fn reset(&self, error_code: u32) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some(mut send) = guard.take() else {
+            return Ok(());
+        };
+        self.pending.lock().unwrap().buf.clear();
+        self.send_reset.store(true, Ordering::Release);
+        self.half_closed(&self.send_closed);
+        self.trace("stream_reset");
+        send.reset(error_code.into()).map_err(quic_err)
+    }
+
+    /// Abandons the receive half, asking the peer to stop sending with `error_code`; its
+    /// writes then fail. Unread data is discarded and later reads resolve `null`. Waits for a
+    /// read in progress. A no-op once the receive half is closed.
+    #[napi]
+    pub async fn stop(&self, error_code: u32) -> Result<()> {
+        let mut guard = self.recv.lock().await;
+        let Some(mut recv) = guard.take() else {
+            return Ok(());
+        };
+        self.half_closed(&self.recv_closed);
+        self.trace("stream_stopped");
+        recv.stop(error_code.into()).map_err(quic_err)
+    }
+
+    /// Send priority relative to the connection's other streams: data of higher-priority
+    /// streams is sent first, and equal priorities share in round robin. Defaults to `0`.
+    /// Waits for a write in progress; errors once the send half is closed.
+    #[napi]
+    pub async fn set_priority(&self, priority: i32) -> Result<()> {
+        let guard = self.send.lock().await;
+        let Some(send) = guard.as_ref() else {
+            return Err(self.send_gone());
+        };
+        send.set_priority(priority).map_err(quic_err)
+    }
+
+    /// Whether this is the first bi stream its initiator opened on the connection, so for an
+    /// accepted stream, the peer's first one. Based on the stream id rather than arrival
+    /// order, so it holds even when several streams are accepted concurrently.
+    #[napi]
+    pub fn is_first_stream(&self) -> bool {
+        self.id.index() == 0
+    }
+
+    /// The QUIC stream id, shared by both halves and the same on both endpoints, for tagging
+    /// logs. A string because ids are 62-bit.
+    #[napi]
+    pub fn id(&self) -> String {
+        u64::from(self.id).to_string()
+    }
+
+    /// Sets what happens if this handle is garbage-collected with a half still open.
+    /// `"finish"` (the default) sends buffered coalesced bytes and then FIN, and stops the
+    /// receive half with code 0. `"reset"` resets the send half and stops the receive half
+    /// with `error_code`. Halves already closed, and halves moved out by `into_encrypted`,
+    /// are unaffected. GC timing is up to the JS engine, so close streams explicitly when the
+    /// peer needs to know promptly.
+    #[napi]
+    pub fn set_drop_behavior(&self, behavior: String, error_code: Option<u32>) -> Result<()> {
+        *self.drop_behavior.lock().unwrap() = DropBehavior::parse(&behavior, error_code)?;
+        Ok(())
+    }
+
+    /// `max_bytes: 0` resolves immediately with an empty buffer (or `null` after FIN) without
+    /// consuming anything from the stream; `max_bytes` above `maxReadBytes` is clamped.
+    /// Rejects with `CANCELLED` if `cancel_reads` is called first; no data is lost then.
+    #[napi]
+    pub async fn read(&self, max_bytes: Option<u32>) -> Result<Option<Buffer>> {
+        self.reads.run(self.read_chunk(max_bytes)).await
     }
 
+    /// Waits for data like `read`, then also takes every chunk already buffered, up to
+    /// `max_total` bytes, in one buffer: one call across the napi boundary instead of one per
+    /// chunk. quinn's `read_chunks` has no byte bound, so chunks are taken one at a time
+    /// without waiting. `null` on FIN; cancellable with `cancel_reads` like `read`.
     #[napi]
-    pub async fn accept(&self) -> Result<PeerConnection> {
-        let conn = self.inner.accept().await.map_err(to_napi_err)?;
-        Ok(PeerConnection { inner: conn })
+    pub async fn read_all_available(&self, max_total: u32) -> Result<Option<Buffer>> {
+        self.reads.run(self.read_available(max_total)).await
     }
 
+    /// Makes every `read` waiting right now, including ones queued behind another, reject
+    /// with `CANCELLED`. The stream stays usable.
     #[napi]
-    pub fn close(&self, error_code: u32, reason: Option<String>) {
-        let reason = reason.unwrap_or_else(|| "closed".to_string());
-        self.inner.close(error_code, reason.as_bytes());
+    pub fn cancel_reads(&self) {
+        self.reads.cancel();
     }
-}
 
-#[napi]
-pub struct PeerClient {
-    endpoint: tokio::sync::Mutex<quinn::Endpoint>,
-}
+    /// Reads exactly `n` bytes, for length-prefixed framing. Resolves `None` if the stream
+    /// ends before any byte of this read, and rejects with "stream finished mid-read" if it
+    /// ends after some. `n: 0` resolves with an empty buffer.
+    #[napi]
+    pub async fn read_exact(&self, n: u32) -> Result<Option<Buffer>> {
+        self.wait_reading().await;
+        let mut guard = self.recv.lock().await;
+        let Some(recv) = guard.as_mut() else {
+            return Ok(None);
+        };
 
-#[napi]
-impl PeerClient {
-    #[napi(factory)]
-    pub fn create(bind_addr: String) -> Result<Self> {
-        let bind_addr = parse_socket_addr(bind_addr)?;
-        let endpoint = block_on(async move { rust::create_quic_peer_client(bind_addr) }).map_err(to_napi_err)?;
-        Ok(Self {
-            endpoint: tokio::sync::Mutex::new(endpoint),
-        })
+        if n as usize > self.max_read_bytes {
+            return Err(error::invalid_argument(format!(
+                "read_exact of {n} bytes exceeds maxReadBytes ({})",
+                self.max_read_bytes
+            )));
+        }
+        let mut buf = vec![0u8; n as usize];
+        let mut filled = 0;
+        while filled < buf.len() {
+            let Some(read) = recv.read(&mut buf[filled..]).await.map_err(quic_err)? else {
+                // FIN
+                *guard = None;
+                self.half_closed(&self.recv_closed);
+                self.trace("stream_recv_closed");
+                self.count_read(filled);
+                if filled == 0 {
+                    return Ok(None);
+                }
+                return Err(error::coded(
+                    Code::StreamClosed,
+                    format!("stream finished mid-read ({filled} of {n} bytes)"),
+                ));
+            };
+            filled += read;
+        }
+        self.count_read(filled);
+        Ok(Some(Buffer::from(buf)))
     }
 
+    /// Scatter-read into `buffers` in order. Waits for at least one byte, then only takes data
+    /// that is already buffered, so buffers may be partially filled; the total is returned.
+    /// Returns `None` at EOF when nothing was read.
     #[napi]
-    pub async fn connect(&self, offer_json: String) -> Result<PeerConnection> {
-        let offer: rust::QuicOffer = serde_json::from_str(&offer_json).map_err(to_napi_err)?;
-        let mut endpoint = self.endpoint.lock().await;
-        let conn = rust::connect_quic_peer(&mut endpoint, &offer)
-            .await
-            .map_err(to_napi_err)?;
-        Ok(PeerConnection { inner: conn })
-    }
-}
+    pub async fn read_vectored(&self, mut buffers: Vec<Buffer>) -> Result<Option<u32>> {
+        self.wait_reading().await;
+        let mut guard = self.recv.lock().await;
+        let Some(recv) = guard.as_mut() else {
+            return Ok(None);
+        };
 
-#[napi]
-pub struct PeerConnection {
-    inner: rust::QuicPeerConnection,
-}
+        let mut total = 0usize;
+        let mut fin = false;
+        'fill: for buffer in buffers.iter_mut() {
+            let buf: &mut [u8] = buffer;
+            let mut filled = 0;
+            while filled < buf.len() {
+                let read = if total == 0 {
+                    recv.read(&mut buf[filled..]).await.map_err(quic_err)?
+                } else {
+                    match poll_once(recv.read(&mut buf[filled..])).await {
+                        Some(read) => read.map_err(quic_err)?,
+                        None => break 'fill,
+                    }
+                };
+                let Some(n) = read else {
+                    fin = true;
+                    break 'fill;
+                };
+                filled += n;
+                total += n;
+            }
+        }
 
-#[napi]
-impl PeerConnection {
-    #[napi]
-    pub fn remote_address(&self) -> Result<String> {
-        Ok(self.inner.remote_address().to_string())
-    }
+        self.count_read(total);
+        if fin {
+            // FIN
+            *guard = None;
+            self.half_closed(&self.recv_closed);
+            self.trace("stream_recv_closed");
+            if total == 0 {
+                return Ok(None);
+            }
+        }
 
-    #[napi]
-    pub async fn open_bi(&self) -> Result<BiStream> {
-        let (send, recv) = self.inner.open_bi().await.map_err(to_napi_err)?;
-        Ok(BiStream::new(send, recv))
+        Ok(Some(total as u32))
     }
 
+    /// Streams the file at `path` into the stream without passing through JS, waiting on flow
+    /// control as it goes. Does not finish the stream. Resolves with the bytes sent.
     #[napi]
-    pub async fn accept_bi(&self) -> Result<BiStream> {
-        let (send, recv) = self.inner.accept_bi().await.map_err(to_napi_err)?;
-        Ok(BiStream::new(send, recv))
+    pub async fn write_file(&self, path: String) -> Result<i64> {
+        use tokio::io::AsyncReadExt;
+
+        let mut file = tokio::fs::File::open(&path).await.map_err(to_napi_err)?;
+        let mut guard = self.send.lock().await;
+        let Some(send) = guard.as_mut() else {
+            return Err(self.send_gone());
+        };
+        drain_pending(send, &self.pending).await?;
+
+        let mut buf = vec![0u8; FILE_CHUNK_BYTES];
+        let mut sent = 0u64;
+        loop {
+            let n = file.read(&mut buf).await.map_err(to_napi_err)?;
+            if n == 0 {
+                break;
+            }
+            self.rate_limit.acquire(n).await;
+            self.write_all_notify(send, &buf[..n]).await?;
+            self.count_written(n);
+            sent += n as u64;
+        }
+        Ok(sent as i64)
     }
 
+    /// Writes everything received until the peer finishes the stream to a new file at `path`
+    /// (truncating an existing one). Resolves with the bytes written.
     #[napi]
-    pub fn close(&self, error_code: u32, reason: Option<String>) {
-        let reason = reason.unwrap_or_else(|| "closed".to_string());
-        self.inner.close(error_code, reason.as_bytes());
-    }
-}
+    pub async fn recv_to_file(&self, path: String) -> Result<i64> {
+        use tokio::io::AsyncWriteExt;
 
-#[napi]
-pub struct BiStream {
-    send: tokio::sync::Mutex<Option<quinn::SendStream>>,
-    recv: tokio::sync::Mutex<Option<quinn::RecvStream>>,
-}
+        let mut file = tokio::fs::File::create(&path).await.map_err(to_napi_err)?;
+        let mut guard = self.recv.lock().await;
+        let Some(recv) = guard.as_mut() else {
+            return Err(error::coded(Code::StreamClosed, "recv stream closed"));
+        };
 
-impl BiStream {
-    fn new(send: quinn::SendStream, recv: quinn::RecvStream) -> Self {
-        Self {
-            send: tokio::sync::Mutex::new(Some(send)),
-            recv: tokio::sync::Mutex::new(Some(recv)),
+        let mut received = 0u64;
+        loop {
+            self.wait_reading().await;
+            let Some(chunk) = recv.read_chunk(FILE_CHUNK_BYTES, true).await.map_err(quic_err)? else {
+                break;
+            };
+            file.write_all(&chunk.bytes).await.map_err(to_napi_err)?;
+            self.count_read(chunk.bytes.len());
+            received += chunk.bytes.len() as u64;
         }
+        file.flush().await.map_err(to_napi_err)?;
+
+        // FIN
+        *guard = None;
+        self.half_closed(&self.recv_closed);
+        self.trace("stream_recv_closed");
+        Ok(received as i64)
     }
-}
 
-#[napi]
-impl BiStream {
+    /// Holds back reads until `resume_reading`. A read already waiting on the network when this
+    /// is called still completes; later reads wait.
     #[napi]
-    pub async fn write(&self, data: Buffer) -> Result<()> {
+    pub fn pause_reading(&self) {
+        self.reading.send_replace(false);
+    }
+
+    /// Sends `plaintext` as one encrypted message in the `EncryptedStream` frame format (see
+    /// `e2ee_stream.rs`), without giving up the plain `write`s. The peer reads it with
+    /// `read_e2ee` and the same pre-shared key; both sides must interleave plain and encrypted
+    /// data in the same order. Every call on a stream must pass the same `shared_key`. Frames
+    /// carry this end's role (opener or acceptor), so they cannot be reflected back at it.
+    /// This is stream framing only: relay payload decryption cannot read these frames.
+    #[napi]
+    pub async fn write_e2ee(&self, shared_key: Buffer, plaintext: Buffer) -> Result<()> {
+        let key = e2ee_stream::shared_key(&shared_key)?;
         let mut guard = self.send.lock().await;
         let Some(send) = guard.as_mut() else {
-            return Err(to_napi_err("send stream closed"));
+            return Err(self.send_gone());
         };
-        send.write_all(&data).await.map_err(to_napi_err)?;
+        let frame = {
+            let mut slot = self.send_cipher.lock().unwrap();
+            e2ee_stream::keyed_cipher(&mut slot, &key, self.e2ee_role())?.seal(&plaintext)?
+        };
+        self.rate_limit.acquire(frame.len()).await;
+        drain_pending(send, &self.pending).await?;
+        self.write_all_notify(send, &frame).await?;
+        self.count_written(plaintext.len());
         Ok(())
     }
 
+    /// Reads and decrypts the next message sent with `write_e2ee`, or `null` once the peer
+    /// finishes the stream. Rejects frames that were replayed, reordered or tampered with.
     #[napi]
-    pub async fn finish(&self) -> Result<()> {
+    pub async fn read_e2ee(&self, shared_key: Buffer) -> Result<Option<Buffer>> {
+        let key = e2ee_stream::shared_key(&shared_key)?;
+        self.wait_reading().await;
+        let mut guard = self.recv.lock().await;
+        let Some(recv) = guard.as_mut() else {
+            return Ok(None);
+        };
+        let Some(body) = framing::read_frame(recv, e2ee_stream::MAX_FRAME_BYTES).await? else {
+            // FIN
+            *guard = None;
+            self.half_closed(&self.recv_closed);
+            self.trace("stream_recv_closed");
+            return Ok(None);
+        };
+        let mut slot = self.recv_cipher.lock().unwrap();
+        let plaintext = e2ee_stream::keyed_cipher(&mut slot, &key, self.e2ee_role())?.open(&body)?;
+        self.count_read(plaintext.len());
+        Ok(Some(Buffer::from(plaintext)))
+    }
+
+    /// Writes `data` as one length-prefixed message (see `framing.rs`) for the peer's
+    /// `recv_message`. Plain writes can be mixed in if the peer reads in the same order.
+    #[napi]
+    pub async fn send_message(&self, data: Buffer) -> Result<()> {
+        if u32::try_from(data.len()).is_err() {
+            return Err(error::invalid_argument("message longer than 4 GiB"));
+        }
+        let frame = framing::encode(&data);
         let mut guard = self.send.lock().await;
-        let Some(mut send) = guard.take() else {
-            return Ok(());
+        let Some(send) = guard.as_mut() else {
+            return Err(self.send_gone());
         };
-        send.finish().map_err(to_napi_err)?;
+        self.rate_limit.acquire(frame.len()).await;
+        drain_pending(send, &self.pending).await?;
+        self.write_all_notify(send, &frame).await?;
+        self.count_written(data.len());
         Ok(())
     }
 
+    /// Reads one message sent with `send_message`, or `null` when the peer finishes the stream
+    /// between messages. Rejects with `STREAM_CLOSED` ("truncated frame") if it finishes
+    /// mid-message, and rejects a length above `max_bytes` (default and ceiling `maxReadBytes`)
+    /// before allocating; the stream is out of sync after either and should be stopped. Not
+    /// cancellable with `cancel_reads`, since a message may be half consumed.
     #[napi]
-    pub async fn read(&self, max_bytes: Option<u32>) -> Result<Option<Buffer>> {
+    pub async fn recv_message(&self, max_bytes: Option<u32>) -> Result<Option<Buffer>> {
+        self.wait_reading().await;
         let mut guard = self.recv.lock().await;
         let Some(recv) = guard.as_mut() else {
             return Ok(None);
         };
-
-        let max = max_bytes.unwrap_or(64 * 1024) as usize;
-        let chunk = recv
-            .read_chunk(max, true)
-            .await
-            .map_err(to_napi_err)?;
-
-        let Some(chunk) = chunk else {
+        let max = max_bytes.map_or(self.max_read_bytes, |max| (max as usize).min(self.max_read_bytes));
+        let Some(body) = framing::read_frame(recv, max).await? else {
             // FIN
             *guard = None;
+            self.half_closed(&self.recv_closed);
+            self.trace("stream_recv_closed");
             return Ok(None);
         };
+        self.count_read(body.len());
+        Ok(Some(Buffer::from(body)))
+    }
 
-        Ok(Some(Buffer::from(chunk.bytes.to_vec())))
+    /// Moves both halves, and the open stream slot, into an `EncryptedStream`; this handle
+    /// behaves as closed afterwards.
+    #[napi]
+    pub async fn into_encrypted(&self, shared_key: Buffer) -> Result<e2ee_stream::EncryptedStream> {
+        let key = e2ee_stream::shared_key(&shared_key)?;
+        let mut send = self.send.lock().await.take();
+        if let Some(send) = send.as_mut() {
+            drain_pending(send, &self.pending).await?;
+        }
+        let recv = self.recv.lock().await.take();
+        let slot = self.slot.lock().unwrap().take();
+        Ok(e2ee_stream::EncryptedStream::new(
+            send,
+            recv,
+            &key,
+            self.e2ee_role(),
+            slot,
+        ))
+    }
+
+    /// Application bytes written so far: what callers passed to the write methods
+    /// (plaintext for `write_e2ee`), excluding QUIC and frame overhead. Counted once handed to
+    /// the stream (or its coalescing buffer), not once acknowledged; failed writes are left out.
+    #[napi]
+    pub fn bytes_written(&self) -> i64 {
+        self.bytes_written.load(Ordering::Relaxed) as i64
+    }
+
+    /// Application bytes read so far, by any read method, on the same terms as `bytes_written`.
+    #[napi]
+    pub fn bytes_read(&self) -> i64 {
+        self.bytes_read.load(Ordering::Relaxed) as i64
+    }
+
+    #[napi]
+    pub fn resume_reading(&self) {
+        self.reading.send_replace(true);
+    }
+
+    #[napi]
+    pub fn is_reading_paused(&self) -> bool {
+        !*self.reading.borrow()
     }
 }
 
 #[napi]
 pub struct RelayClient {
     endpoint: tokio::sync::Mutex<quinn::Endpoint>,
+    /// Handle to the same endpoint for queries that must not wait behind a `connect_transport`.
+    handle: quinn::Endpoint,
 }
 
 #[napi]
@@ -192,53 +2548,412 @@ impl RelayClient {
     #[napi(factory)]
     pub fn create(bind_addr: String) -> Result<Self> {
         let bind_addr = parse_socket_addr(bind_addr)?;
+        crypto::provider()?;
         let endpoint = block_on(async move { rust::create_quic_relay_client(bind_addr) }).map_err(to_napi_err)?;
         Ok(Self {
+            handle: endpoint.clone(),
             endpoint: tokio::sync::Mutex::new(endpoint),
         })
     }
 
+    /// The address the endpoint is bound to, with the OS-assigned port when bound to port 0.
+    #[napi]
+    pub fn local_address(&self) -> Result<String> {
+        Ok(self.handle.local_addr().map_err(to_napi_err)?.to_string())
+    }
+
     #[napi]
-    pub async fn connect_transport(&self, relay_info_json: String) -> Result<RelayTransport> {
+    pub async fn connect_transport(
+        &self,
+        relay_info_json: String,
+        options_json: Option<String>,
+    ) -> Result<RelayTransport> {
         let relay: rust::QuicRelayInfo = serde_json::from_str(&relay_info_json).map_err(to_napi_err)?;
+        let options: RelayTransportOptions = parse_options(options_json)?;
         let mut endpoint = self.endpoint.lock().await;
-        let conn = rust::connect_quic_relay_transport(&mut endpoint, &relay)
-            .await
-            .map_err(to_napi_err)?;
-        Ok(RelayTransport { inner: conn })
+        let connect = async {
+            rust::connect_quic_relay_transport(&mut endpoint, &relay)
+                .await
+                .map_err(to_napi_err)
+        };
+        let conn = with_handshake_timeout(options.handshake_timeout_ms, connect).await?;
+        Ok(RelayTransport {
+            inner: conn,
+            max_sessions: options.max_sessions,
+            bound: std::sync::Mutex::new(HashSet::new()),
+            receiving: tokio::sync::watch::Sender::new(true),
+        })
+    }
+}
+
+/// One session's entry in a `relay_bind_multi` answer, or `None` when it is missing or malformed
+/// and the session has to be bound on its own.
+fn bind_multi_outcome(
+    results: &serde_json::Value,
+    session_id: &uuid::Uuid,
+) -> Option<std::result::Result<bool, String>> {
+    let result = results.get(session_id.to_string())?;
+    if let Some(peer_ready) = result.get("peer_ready").and_then(|ready| ready.as_bool()) {
+        return Some(Ok(peer_ready));
     }
+    let error = result.get("error").and_then(|error| error.as_str())?;
+    Some(Err(error.to_string()))
+}
+
+const BIND_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+const BIND_RETRY_BACKOFF_MAX: Duration = Duration::from_secs(2);
+
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RelayBindOptions {
+    timeout_ms: Option<u32>,
+    /// Further attempts after a timed-out one; needs `timeout_ms`.
+    #[serde(default)]
+    retries: u32,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RelayTransportOptions {
+    max_sessions: Option<u32>,
+    handshake_timeout_ms: Option<u32>,
 }
 
 #[napi]
 pub struct RelayTransport {
     inner: quinn::Connection,
+    max_sessions: Option<u32>,
+    bound: std::sync::Mutex<HashSet<uuid::Uuid>>,
+    receiving: tokio::sync::watch::Sender<bool>,
+}
+
+impl RelayTransport {
+    /// Reserves the slot for `session_id` before the bind round trip, so concurrent binds
+    /// cannot overshoot the cap. `false` if it was already bound.
+    fn reserve(&self, session_id: uuid::Uuid) -> Result<bool> {
+        let mut bound = self.bound.lock().unwrap();
+        if bound.contains(&session_id) {
+            return Ok(false);
+        }
+        if let Some(max) = self.max_sessions {
+            if bound.len() >= max as usize {
+                return Err(error::coded(
+                    Code::SessionLimit,
+                    format!("session limit reached ({max})"),
+                ));
+            }
+        }
+        Ok(bound.insert(session_id))
+    }
+
+    /// `relay_bind` under `options`: each attempt is bounded by `timeout_ms`, and timed-out
+    /// ones are retried after 100 ms, doubling up to 2 s. Errors from the relay fail at once.
+    async fn bind_with_retries(&self, token: &str, session_id: uuid::Uuid, options: &RelayBindOptions) -> Result<bool> {
+        let Some(timeout_ms) = options.timeout_ms else {
+            return rust::relay_bind(&self.inner, token, session_id)
+                .await
+                .map_err(to_napi_err);
+        };
+        let timeout = Duration::from_millis(timeout_ms.into());
+        let mut backoff = BIND_RETRY_BACKOFF;
+        for attempt in 0..=options.retries {
+            if attempt > 0 {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(BIND_RETRY_BACKOFF_MAX);
+            }
+            if let Ok(result) = tokio::time::timeout(timeout, rust::relay_bind(&self.inner, token, session_id)).await {
+                return result.map_err(to_napi_err);
+            }
+        }
+        Err(error::coded(
+            Code::TimedOut,
+            format!(
+                "relay_bind timed out ({} attempts of {timeout_ms} ms)",
+                u64::from(options.retries) + 1
+            ),
+        ))
+    }
+
+    /// Runs a receive unless `stop_receiving` was called, in which case it resolves `None`.
+    async fn receive<T>(&self, recv: impl Future<Output = Result<T>>) -> Result<Option<T>> {
+        let mut receiving = self.receiving.subscribe();
+        if !*receiving.borrow_and_update() {
+            return Ok(None);
+        }
+        tokio::select! {
+            frame = recv => frame.map(Some),
+            _ = receiving.wait_for(|receiving| !*receiving) => Ok(None),
+        }
+    }
 }
 
 #[napi]
 impl RelayTransport {
+    /// Options: `{ timeoutMs?, retries? }`. With `timeoutMs`, an attempt that gets no answer
+    /// in time is abandoned and, up to `retries` times, tried again with backoff; the last
+    /// timeout rejects with `TIMED_OUT`. An abandoned attempt may still have reached the
+    /// relay, which then sees the bind more than once.
+    #[napi]
+    pub async fn relay_bind(&self, token: String, session_id: String, options_json: Option<String>) -> Result<bool> {
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        let options: RelayBindOptions = parse_options(options_json)?;
+        let reserved = self.reserve(session_id)?;
+        let result = self.bind_with_retries(&token, session_id, &options).await;
+        if result.is_err() && reserved {
+            self.bound.lock().unwrap().remove(&session_id);
+        }
+        result
+    }
+
+    /// Binds several sessions in one round trip with a `relay_bind_multi` control request, or
+    /// one `relay_bind` after another when the relay does not answer it successfully: on an
+    /// error frame, no answer, a failed request, or a malformed answer. A session missing from
+    /// an otherwise good answer is bound on its own as well. Resolves a JSON array in input
+    /// order of `{ session_id, peer_ready }` or `{ session_id, error }`; one session failing
+    /// does not fail the others.
+    ///
+    /// Request op `relay_bind_multi` with payload `{ "token", "session_ids": [..] }`. Response
+    /// data: `{ "results": { "<session_id>": { "peer_ready": bool } | { "error": string } } }`.
     #[napi]
-    pub async fn relay_bind(&self, token: String, session_id: String) -> Result<bool> {
-        let session_id = uuid::Uuid::parse_str(&session_id).map_err(|_| to_napi_err("invalid session_id"))?;
-        rust::relay_bind(&self.inner, &token, session_id)
+    pub async fn relay_bind_multi(&self, token: String, session_ids: Vec<String>) -> Result<String> {
+        let session_ids = session_ids
+            .iter()
+            .map(|id| {
+                uuid::Uuid::parse_str(id).map_err(|_| error::invalid_argument(format!("invalid session_id {id:?}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut outcomes: Vec<Option<std::result::Result<bool, String>>> = vec![None; session_ids.len()];
+        let mut reserved = HashSet::new();
+        for (outcome, session_id) in outcomes.iter_mut().zip(&session_ids) {
+            match self.reserve(*session_id) {
+                Ok(true) => {
+                    reserved.insert(*session_id);
+                }
+                Ok(false) => {}
+                Err(err) => *outcome = Some(Err(err.reason.clone())),
+            }
+        }
+
+        let to_bind: Vec<String> = session_ids
+            .iter()
+            .zip(&outcomes)
+            .filter(|(_, outcome)| outcome.is_none())
+            .map(|(session_id, _)| session_id.to_string())
+            .collect();
+        let payload = serde_json::json!({ "token": token, "session_ids": to_bind });
+        let batched = relay_control::request(&self.inner, "relay_bind_multi", payload)
             .await
-            .map_err(to_napi_err)
+            .ok()
+            .and_then(relay_control::Reply::data)
+            .and_then(|mut data| data.get_mut("results").map(serde_json::Value::take))
+            .filter(|results| results.is_object());
+
+        for (outcome, session_id) in outcomes.iter_mut().zip(&session_ids) {
+            if outcome.is_some() {
+                continue;
+            }
+            let answered = batched
+                .as_ref()
+                .and_then(|results| bind_multi_outcome(results, session_id));
+            let result = match answered {
+                Some(result) => result,
+                None => rust::relay_bind(&self.inner, &token, *session_id)
+                    .await
+                    .map_err(|err| err.to_string()),
+            };
+            if result.is_err() && reserved.contains(session_id) {
+                self.bound.lock().unwrap().remove(session_id);
+            }
+            *outcome = Some(result);
+        }
+
+        let report: Vec<serde_json::Value> = session_ids
+            .iter()
+            .zip(outcomes)
+            .map(
+                |(session_id, outcome)| match outcome.expect("every session has an outcome") {
+                    Ok(peer_ready) => serde_json::json!({ "session_id": session_id, "peer_ready": peer_ready }),
+                    Err(error) => serde_json::json!({ "session_id": session_id, "error": error }),
+                },
+            )
+            .collect();
+        serde_json::to_string(&report).map_err(to_napi_err)
     }
 
+    /// Releases `session_id` on the relay while keeping the transport, so one connection can
+    /// carry many short sessions without leaking relay state; it also frees the slot counted
+    /// against `max_sessions`. Resolves whether the relay had it bound. Rejects with the relay's
+    /// message when it declines, which includes relays that cannot unbind, leaving the local
+    /// state as it was.
+    ///
+    /// Request op `relay_unbind` with payload `{ "token", "session_id" }`. Response data:
+    /// `{ "unbound": bool }`.
     #[napi]
-    pub async fn relay_send(&self, token: String, session_id: String, payload_json: String) -> Result<()> {
-        let session_id = uuid::Uuid::parse_str(&session_id).map_err(|_| to_napi_err("invalid session_id"))?;
-        let payload: serde_json::Value = serde_json::from_str(&payload_json).map_err(to_napi_err)?;
+    pub async fn relay_unbind(&self, token: String, session_id: String) -> Result<bool> {
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        let payload = serde_json::json!({ "token": token, "session_id": session_id.to_string() });
+        let response = match relay_control::request(&self.inner, "relay_unbind", payload).await? {
+            relay_control::Reply::Data(response) => response,
+            relay_control::Reply::Declined(message) => {
+                return Err(to_napi_err(format!("relay declined relay_unbind: {message}")));
+            }
+        };
+        let unbound = response
+            .get("unbound")
+            .and_then(|unbound| unbound.as_bool())
+            .ok_or_else(|| to_napi_err("invalid relay_unbind response"))?;
+        self.bound.lock().unwrap().remove(&session_id);
+        Ok(unbound)
+    }
+
+    /// Same JSON as `PeerConnection::stats`, for the connection to the relay.
+    #[napi]
+    pub fn stats(&self) -> Result<String> {
+        ConnectionStats::to_json(&self.inner)
+    }
+
+    #[napi]
+    pub fn bound_session_count(&self) -> u32 {
+        self.bound.lock().unwrap().len() as u32
+    }
+
+    /// Whether this transport has bound `session_id`, from local state only (no round trip).
+    /// May be stale if the relay dropped the binding on its side.
+    #[napi]
+    pub fn is_bound(&self, session_id: String) -> Result<bool> {
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        Ok(self.bound.lock().unwrap().contains(&session_id))
+    }
+
+    /// Asks the relay which sessions are currently bound under `token`, by us or by the far
+    /// peer, so callers can wait for the peer to bind before sending. Resolves `None` when the
+    /// relay declines the query with an error frame or does not answer, which is how relays
+    /// without it respond; a refused token looks the same.
+    ///
+    /// Request op `relay_list_sessions` with payload `{ "token" }`. Response data:
+    /// `{ "session_ids": [..] }`.
+    #[napi]
+    pub async fn relay_list_sessions(&self, token: String) -> Result<Option<Vec<String>>> {
+        let payload = serde_json::json!({ "token": token });
+        let Some(response) = relay_control::request(&self.inner, "relay_list_sessions", payload)
+            .await?
+            .data()
+        else {
+            return Ok(None);
+        };
+        let invalid = || to_napi_err("invalid relay_list_sessions response");
+        let session_ids = response
+            .get("session_ids")
+            .and_then(|ids| ids.as_array())
+            .ok_or_else(invalid)?;
+        session_ids
+            .iter()
+            .map(|id| {
+                let id = id
+                    .as_str()
+                    .and_then(|id| uuid::Uuid::parse_str(id).ok())
+                    .ok_or_else(invalid)?;
+                Ok(id.to_string())
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Some)
+    }
+
+    /// Asks the relay which source address our packets arrive from (STUN-style). Resolves
+    /// `None` when the relay declines the query, which is how relays without it answer.
+    ///
+    /// Request op `reflexive_address` with an empty payload. Response data: `{ "address" }`.
+    #[napi]
+    pub async fn reflexive_address(&self) -> Result<Option<String>> {
+        let request = relay_control::request(&self.inner, "reflexive_address", serde_json::json!({}));
+        let Some(response) = request.await?.data() else {
+            return Ok(None);
+        };
+        let address = response
+            .get("address")
+            .and_then(|address| address.as_str())
+            .ok_or_else(|| to_napi_err("invalid reflexive_address response"))?;
+        let address = parse_socket_addr(address.to_string())?;
+        Ok(Some(address.to_string()))
+    }
+
+    /// With `compress`, large payloads are zstd-compressed and expanded again by
+    /// `recv_relay_data`; see `relay_compress.rs`. Resolves once the frame is written to the
+    /// relay: the relay protocol has no delivery acknowledgement, so a payload for a session
+    /// with no bound peer is dropped without notice.
+    #[napi]
+    pub async fn relay_send(
+        &self,
+        token: String,
+        session_id: String,
+        payload_json: String,
+        compress: Option<bool>,
+    ) -> Result<()> {
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        let mut payload: serde_json::Value = serde_json::from_str(&payload_json).map_err(to_napi_err)?;
+        if compress.unwrap_or(false) {
+            payload = relay_compress::wrap(payload)?;
+        }
         rust::relay_send(&self.inner, &token, session_id, payload)
             .await
             .map_err(to_napi_err)
     }
 
+    /// Sends `payload` as raw bytes rather than JSON; the receiver must use
+    /// `recv_relay_data_bytes`. The framing is described in `relay_bytes.rs`.
+    #[napi]
+    pub async fn relay_send_bytes(&self, token: String, session_id: String, payload: Buffer) -> Result<()> {
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        rust::relay_send(&self.inner, &token, session_id, relay_bytes::wrap(&payload))
+            .await
+            .map_err(to_napi_err)
+    }
+
+    /// Resolves `None` once `stop_receiving` has been called. Rejects frames sent with
+    /// `relay_send_bytes` or `relay_send_e2ee`; the frame is consumed either way. Adds
+    /// `encrypted`, `true` for forward-secret and sequenced payloads (see `relay_payload.rs`).
+    #[napi]
+    pub async fn recv_relay_data(&self) -> Result<Option<String>> {
+        let recv = async { rust::recv_relay_data(&self.inner).await.map_err(to_napi_err) };
+        let Some(frame) = self.receive(recv).await? else {
+            return Ok(None);
+        };
+        let mut frame = serde_json::to_value(&frame).map_err(to_napi_err)?;
+        relay_bytes::expect_json(&frame)?;
+        relay_payload::mark_plain_frame(&mut frame)?;
+        relay_compress::unwrap_frame(&mut frame)?;
+        serde_json::to_string(&frame).map(Some).map_err(to_napi_err)
+    }
+
+    /// The binary counterpart of `recv_relay_data`, for frames sent with `relay_send_bytes`;
+    /// rejects JSON payloads, consuming the frame.
+    #[napi]
+    pub async fn recv_relay_data_bytes(&self) -> Result<Option<relay_bytes::RelayBytesFrame>> {
+        let recv = async { rust::recv_relay_data(&self.inner).await.map_err(to_napi_err) };
+        let Some(frame) = self.receive(recv).await? else {
+            return Ok(None);
+        };
+        let frame = serde_json::to_value(&frame).map_err(to_napi_err)?;
+        relay_bytes::RelayBytesFrame::parse(&frame).map(Some)
+    }
+
+    /// Ends relay data consumption without closing the connection: pending and later
+    /// `recv_relay_data`/`recv_relay_data_bytes`/`recv_relay_data_e2ee` calls resolve `None`,
+    /// while sends keep working. A frame being read when this is called is discarded.
     #[napi]
-    pub async fn recv_relay_data(&self) -> Result<String> {
-        let frame = rust::recv_relay_data(&self.inner).await.map_err(to_napi_err)?;
-        serde_json::to_string(&frame).map_err(to_napi_err)
+    pub fn stop_receiving(&self) {
+        self.receiving.send_replace(false);
     }
 
+    /// `aad` is authenticated but not sent; the receiver must pass the same bytes to
+    /// `recv_relay_data_e2ee`. See `relay_aad.rs`. `compress` works as for `relay_send`,
+    /// compressing before encryption.
     #[napi]
     pub async fn relay_send_e2ee(
         &self,
@@ -246,32 +2961,74 @@ impl RelayTransport {
         session_id: String,
         shared_key: Buffer,
         plaintext_json: String,
+        aad: Option<Buffer>,
+        compress: Option<bool>,
     ) -> Result<()> {
-        if shared_key.len() != 32 {
-            return Err(to_napi_err("shared_key must be 32 bytes"));
+        let key = relay_aad::relay_key(&shared_key, aad.as_deref())?;
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        let mut plaintext: serde_json::Value = serde_json::from_str(&plaintext_json).map_err(to_napi_err)?;
+        if compress.unwrap_or(false) {
+            plaintext = relay_compress::wrap(plaintext)?;
         }
-        let session_id = uuid::Uuid::parse_str(&session_id).map_err(|_| to_napi_err("invalid session_id"))?;
-        let plaintext: serde_json::Value = serde_json::from_str(&plaintext_json).map_err(to_napi_err)?;
 
-        let mut key = [0u8; 32];
-        key.copy_from_slice(&shared_key);
         rust::relay_send_e2ee(&self.inner, &token, session_id, &key, &plaintext)
             .await
             .map_err(to_napi_err)
     }
 
+    /// Like `relay_send_e2ee`, but with a fresh ephemeral key per message instead of a
+    /// shared session key, for sender forward secrecy; see `forward_secret.rs` for the format
+    /// and cost. The recipient reads it with `recv_relay_data` and opens the payload with
+    /// `E2eeKeyPairHandle::open_forward_secret`.
+    #[napi]
+    pub async fn relay_send_fs(
+        &self,
+        token: String,
+        session_id: String,
+        peer_public_b64: String,
+        plaintext_json: String,
+    ) -> Result<()> {
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        let plaintext: serde_json::Value = serde_json::from_str(&plaintext_json).map_err(to_napi_err)?;
+        let payload = forward_secret::seal(&peer_public_b64, session_id, &plaintext)?;
+        rust::relay_send(&self.inner, &token, session_id, payload)
+            .await
+            .map_err(to_napi_err)
+    }
+
+    /// Fails to decrypt frames sent with different `aad`. Rejects plaintext frames with
+    /// `NOT_ENCRYPTED` rather than passing them through as the SDK does, consuming the frame.
     #[napi]
-    pub async fn recv_relay_data_e2ee(&self, shared_key: Buffer) -> Result<String> {
-        if shared_key.len() != 32 {
-            return Err(to_napi_err("shared_key must be 32 bytes"));
+    pub async fn recv_relay_data_e2ee(&self, shared_key: Buffer, aad: Option<Buffer>) -> Result<Option<String>> {
+        let key = relay_aad::relay_key(&shared_key, aad.as_deref())?;
+
+        let recv = async { rust::recv_relay_data(&self.inner).await.map_err(to_napi_err) };
+        let Some(mut frame) = self.receive(recv).await? else {
+            return Ok(None);
+        };
+        relay_payload::expect_sdk_sealed(&frame.payload)?;
+        frame.payload = rust::decrypt_relay_payload(&key, frame.session_id, &frame.payload).map_err(to_napi_err)?;
+        let mut frame = serde_json::to_value(&frame).map_err(to_napi_err)?;
+        relay_compress::unwrap_frame(&mut frame)?;
+        if let Some(frame) = frame.as_object_mut() {
+            frame.insert("encrypted".to_string(), true.into());
         }
-        let mut key = [0u8; 32];
-        key.copy_from_slice(&shared_key);
+        serde_json::to_string(&frame).map(Some).map_err(to_napi_err)
+    }
 
-        let frame = rust::recv_relay_data_e2ee(&self.inner, &key)
-            .await
-            .map_err(to_napi_err)?;
-        serde_json::to_string(&frame).map_err(to_napi_err)
+    /// Resolves once the connection to the relay is closed, with the same JSON as
+    /// `PeerConnection::closed`.
+    #[napi]
+    pub async fn closed(&self) -> String {
+        close_reason_json(&self.inner.closed().await).to_string()
+    }
+
+    /// `closed` without waiting: `null` while the connection is open.
+    #[napi]
+    pub fn close_reason(&self) -> Option<String> {
+        Some(close_reason_json(&self.inner.close_reason()?).to_string())
     }
 
     #[napi]
@@ -300,43 +3057,188 @@ impl E2eeKeyPairHandle {
         self.inner.public_key_b64()
     }
 
+    /// Rejects a peer key that is not base64, not 32 bytes, or a low-order point, each with its
+    /// own `INVALID_ARGUMENT` message (see `public_key.rs`).
     #[napi]
     pub fn derive_relay_shared_key(&self, peer_public_b64: String, session_id: String) -> Result<Buffer> {
-        let session_id = uuid::Uuid::parse_str(&session_id).map_err(|_| to_napi_err("invalid session_id"))?;
+        public_key::validate_peer(&peer_public_b64)?;
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
         let key = self
             .inner
             .derive_relay_shared_key(&peer_public_b64, session_id)
             .map_err(to_napi_err)?;
         Ok(Buffer::from(key.to_vec()))
     }
+
+    /// Advances a key from `derive_relay_shared_key` (or an earlier rotation) one ratchet
+    /// step; see `relay_ratchet.rs`. Deterministic, so peers rotating in lockstep stay in
+    /// sync, and one-way, so the new key does not reveal `current_key`. Discard the old key
+    /// once no payloads sealed under it are outstanding.
+    #[napi]
+    pub fn rotate_relay_shared_key(&self, current_key: Buffer, session_id: String) -> Result<Buffer> {
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        Ok(Buffer::from(
+            relay_ratchet::next_key(&current_key, session_id)?.to_vec(),
+        ))
+    }
+
+    /// Decrypts the payload of a frame sent with `relay_send_fs` to this key pair.
+    #[napi]
+    pub fn open_forward_secret(&self, session_id: String, payload_json: String) -> Result<String> {
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        let payload: serde_json::Value = serde_json::from_str(&payload_json).map_err(to_napi_err)?;
+        let plaintext = forward_secret::open(&self.inner, session_id, &payload)?;
+        serde_json::to_string(&plaintext).map_err(to_napi_err)
+    }
 }
 
+/// Derives the session id both peers compute independently from their public keys.
+///
+/// Both base64 keys are decoded and ordered bytewise so the result is symmetric. The id is
+/// the first 16 bytes of `SHA-256("gann-session-id-v1" || 0x00 || lower || higher)` with
+/// the UUID version set to 8 and the RFC 4122 variant bits applied, formatted as a
+/// lowercase hyphenated UUID.
 #[napi]
-pub fn encrypt_relay_payload(shared_key: Buffer, session_id: String, plaintext_json: String) -> Result<String> {
-    if shared_key.len() != 32 {
-        return Err(to_napi_err("shared_key must be 32 bytes"));
-    }
-    let session_id = uuid::Uuid::parse_str(&session_id).map_err(|_| to_napi_err("invalid session_id"))?;
-    let plaintext: serde_json::Value = serde_json::from_str(&plaintext_json).map_err(to_napi_err)?;
+pub fn derive_session_id(my_public_b64: String, peer_public_b64: String) -> Result<String> {
+    use base64::Engine as _;
+    use sha2::Digest as _;
+
+    let decode = |key: &str| {
+        base64::engine::general_purpose::STANDARD
+            .decode(key)
+            .map_err(|_| error::invalid_argument("invalid public key"))
+    };
+    let (mine, peer) = (decode(&my_public_b64)?, decode(&peer_public_b64)?);
+    let (lower, higher) = if mine <= peer { (mine, peer) } else { (peer, mine) };
+
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(b"gann-session-id-v1\0");
+    hasher.update(&lower);
+    hasher.update(&higher);
+    let digest = hasher.finalize();
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x80;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    Ok(uuid::Uuid::from_bytes(bytes).to_string())
+}
+
+/// `aad` is authenticated but not included in the output; see `relay_aad.rs`. The nonce is
+/// drawn at random by the Rust SDK on every call and travels inside the payload, since
+/// `decrypt_relay_payload` gets nothing else; the SDK offers no way to supply one.
+///
+/// With `seq`, the output carries that sequence number and `sender`, this side's id, both
+/// authenticated, for `RelayReplayGuard` to check; `sender` is required with `seq`. See
+/// `relay_seq.rs`.
+#[napi]
+pub fn encrypt_relay_payload(
+    shared_key: Buffer,
+    session_id: String,
+    plaintext_json: String,
+    aad: Option<Buffer>,
+    seq: Option<i64>,
+    sender: Option<String>,
+) -> Result<String> {
+    encrypt_payload(&shared_key, &session_id, &plaintext_json, aad.as_deref(), seq, sender)
+}
+
+/// `encrypt_relay_payload` on a blocking-pool thread, so JSON and AEAD work on large payloads
+/// stays off the JS thread. The inputs are copied first; for small payloads the round trip
+/// costs more than it saves.
+#[napi]
+pub async fn encrypt_relay_payload_async(
+    shared_key: Buffer,
+    session_id: String,
+    plaintext_json: String,
+    aad: Option<Buffer>,
+    seq: Option<i64>,
+    sender: Option<String>,
+) -> Result<String> {
+    let shared_key = shared_key.to_vec();
+    let aad = aad.map(|aad| aad.to_vec());
+    spawn_blocking(move || encrypt_payload(&shared_key, &session_id, &plaintext_json, aad.as_deref(), seq, sender))
+        .await
+        .map_err(to_napi_err)?
+}
 
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&shared_key);
+fn encrypt_payload(
+    shared_key: &[u8],
+    session_id: &str,
+    plaintext_json: &str,
+    aad: Option<&[u8]>,
+    seq: Option<i64>,
+    sender: Option<String>,
+) -> Result<String> {
+    let key = relay_aad::relay_key(shared_key, aad)?;
+    let session_id = uuid::Uuid::parse_str(session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+    let plaintext: serde_json::Value = serde_json::from_str(plaintext_json).map_err(to_napi_err)?;
 
+    if let Some(seq) = seq {
+        let sender = relay_seq::parse_sender(sender)?;
+        let sealed = relay_seq::seal(&key, session_id, &sender, relay_seq::parse_seq(seq)?, &plaintext)?;
+        return serde_json::to_string(&sealed).map_err(to_napi_err);
+    }
     let encrypted = rust::encrypt_relay_payload(&key, session_id, &plaintext).map_err(to_napi_err)?;
     serde_json::to_string(&encrypted).map_err(to_napi_err)
 }
 
+/// Fails unless `aad` matches what the payload was encrypted with. Payloads with a `seq` are
+/// authenticated but not checked for replay; use `RelayReplayGuard` for that.
 #[napi]
-pub fn decrypt_relay_payload(shared_key: Buffer, session_id: String, payload_json: String) -> Result<String> {
-    if shared_key.len() != 32 {
-        return Err(to_napi_err("shared_key must be 32 bytes"));
-    }
-    let session_id = uuid::Uuid::parse_str(&session_id).map_err(|_| to_napi_err("invalid session_id"))?;
-    let payload: serde_json::Value = serde_json::from_str(&payload_json).map_err(to_napi_err)?;
+pub fn decrypt_relay_payload(
+    shared_key: Buffer,
+    session_id: String,
+    payload_json: String,
+    aad: Option<Buffer>,
+) -> Result<String> {
+    decrypt_payload(&shared_key, &session_id, &payload_json, aad.as_deref())
+}
 
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&shared_key);
+/// `decrypt_relay_payload` on a blocking-pool thread, like `encrypt_relay_payload_async`.
+#[napi]
+pub async fn decrypt_relay_payload_async(
+    shared_key: Buffer,
+    session_id: String,
+    payload_json: String,
+    aad: Option<Buffer>,
+) -> Result<String> {
+    let shared_key = shared_key.to_vec();
+    let aad = aad.map(|aad| aad.to_vec());
+    spawn_blocking(move || decrypt_payload(&shared_key, &session_id, &payload_json, aad.as_deref()))
+        .await
+        .map_err(to_napi_err)?
+}
 
-    let plaintext = rust::decrypt_relay_payload(&key, session_id, &payload).map_err(to_napi_err)?;
+fn decrypt_payload(shared_key: &[u8], session_id: &str, payload_json: &str, aad: Option<&[u8]>) -> Result<String> {
+    let key = relay_aad::relay_key(shared_key, aad)?;
+    let session_id = uuid::Uuid::parse_str(session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+    let payload: serde_json::Value = serde_json::from_str(payload_json).map_err(to_napi_err)?;
+
+    let (_, plaintext) = relay_seq::open(&key, session_id, &payload)?;
     serde_json::to_string(&plaintext).map_err(to_napi_err)
 }
+
+/// Packs an offer JSON into the compact binary form described on `QuicOffer::to_bytes`.
+#[napi]
+pub fn offer_to_bytes(offer_json: String) -> Result<Buffer> {
+    let offer: peer::QuicOffer = serde_json::from_str(&offer_json).map_err(to_napi_err)?;
+    Ok(Buffer::from(offer.to_bytes()?))
+}
+
+/// Unpacks `offer_to_bytes` output back into offer JSON.
+#[napi]
+pub fn offer_from_bytes(bytes: Buffer) -> Result<String> {
+    let offer = peer::QuicOffer::from_bytes(&bytes)?;
+    serde_json::to_string(&offer).map_err(to_napi_err)
+}
+
+/// Checks that `proof` is a signature over `e2ee_public_b64` by the key of `cert_der` (e.g.
+/// the certificate from a peer's offer), tying the peer's E2EE key to its TLS identity.
+#[napi]
+pub fn verify_key_binding(cert_der: Buffer, e2ee_public_b64: String, proof: Buffer) -> Result<bool> {
+    key_binding::verify(&cert_der, &e2ee_public_b64, &proof)
+}
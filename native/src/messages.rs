@@ -0,0 +1,94 @@
+//! Message send with a per-message reliability choice, over one connection.
+//!
+//! Unreliable messages are datagrams starting with the kind byte `0xE3` (alongside the
+//! exchange kinds in `datagram.rs`). Reliable messages, and unreliable ones too large for a
+//! datagram, are length-prefixed frames on a single uni stream per direction, opened on first
+//! use and announced by a `0xE3` first byte (the uni stream kinds are in `uni_stream.rs`).
+//! Reliable messages keep their order; unreliable ones may be lost or reordered relative to
+//! everything else.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use napi::bindgen_prelude::*;
+use tokio::sync::mpsc;
+
+use crate::datagram_queue::{self, DatagramQueue};
+use crate::error::quic_err;
+use crate::{framing, OpenSlot};
+
+pub(crate) const KIND_MESSAGE: u8 = 0xE3;
+const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+pub(crate) struct Messages {
+    conn: quinn::Connection,
+    datagrams: Arc<DatagramQueue>,
+    /// The message stream with the open stream slot it holds for the connection's lifetime.
+    stream: tokio::sync::Mutex<Option<(quinn::SendStream, OpenSlot)>>,
+    incoming_tx: mpsc::UnboundedSender<Vec<u8>>,
+    incoming: tokio::sync::Mutex<mpsc::UnboundedReceiver<Vec<u8>>>,
+}
+
+impl Messages {
+    pub fn new(conn: quinn::Connection, datagrams: Arc<DatagramQueue>) -> Self {
+        let (incoming_tx, incoming) = mpsc::unbounded_channel();
+        Self {
+            conn,
+            datagrams,
+            stream: tokio::sync::Mutex::new(None),
+            incoming_tx,
+            incoming: tokio::sync::Mutex::new(incoming),
+        }
+    }
+
+    /// Where the datagram demux delivers `0xE3` datagram payloads, and `forward_stream` the
+    /// frames of the peer's message stream.
+    pub fn incoming_sender(&self) -> mpsc::UnboundedSender<Vec<u8>> {
+        self.incoming_tx.clone()
+    }
+
+    /// `slot` is only awaited when the message stream still has to be opened.
+    pub async fn send(&self, data: &[u8], reliable: bool, slot: impl Future<Output = Result<OpenSlot>>) -> Result<()> {
+        if !reliable && self.conn.max_datagram_size().is_some_and(|max| data.len() < max) {
+            let mut datagram = Vec::with_capacity(1 + data.len());
+            datagram.push(KIND_MESSAGE);
+            datagram.extend_from_slice(data);
+            match self.datagrams.send(datagram) {
+                Ok(()) => return Ok(()),
+                // The path MTU shrank since the size check; fall back to the stream.
+                Err(datagram_queue::SendError::Quinn(quinn::SendDatagramError::TooLarge)) => {}
+                Err(err) => return Err(quic_err(err)),
+            }
+        }
+
+        let mut guard = self.stream.lock().await;
+        if guard.is_none() {
+            let slot = slot.await?;
+            let mut send = self.conn.open_uni().await.map_err(quic_err)?;
+            send.write_all(&[KIND_MESSAGE]).await.map_err(quic_err)?;
+            *guard = Some((send, slot));
+        }
+        let (send, _) = guard.as_mut().expect("message stream opened above");
+        send.write_all(&framing::encode(data)).await.map_err(quic_err)
+    }
+
+    /// Resolves with the next message from either path. Both demuxes must already route here
+    /// via `incoming_sender`.
+    pub async fn recv(&self) -> Result<Vec<u8>> {
+        let mut incoming = self.incoming.lock().await;
+        tokio::select! {
+            biased;
+            Some(message) = incoming.recv() => Ok(message),
+            reason = self.conn.closed() => Err(quic_err(reason)),
+        }
+    }
+}
+
+/// Delivers the frames of the peer's message stream, whose kind byte was already read.
+pub(crate) async fn forward_stream(mut recv: quinn::RecvStream, incoming_tx: mpsc::UnboundedSender<Vec<u8>>) {
+    while let Ok(Some(frame)) = framing::read_frame(&mut recv, MAX_FRAME_BYTES).await {
+        if incoming_tx.send(frame).is_err() {
+            break;
+        }
+    }
+}
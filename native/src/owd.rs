@@ -0,0 +1,90 @@
+//! One-way delay probes over datagrams.
+//!
+//! Probes use the exchange framing from `datagram.rs` with their own kinds (integers
+//! big-endian; timestamps are wall-clock microseconds since the Unix epoch):
+//! - `0xE5` probe: u64 send time `t1`
+//! - `0xE6` echo, sent by a peer with the responder enabled: `t1` echoed, then u64 receive
+//!   time `t2`
+//!
+//! The prober stamps its own receive time `t3` on the echo. `t2 - t1` is the forward delay
+//! and `t3 - t2` the reverse delay, both offset by the difference between the two clocks;
+//! they are only absolute with synchronized clocks. The offset is estimated NTP-style from
+//! the sample with the lowest RTT, assuming that sample's path was symmetric.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(crate) const KIND_PROBE: u8 = 0xE5;
+pub(crate) const KIND_ECHO: u8 = 0xE6;
+
+pub(crate) fn now_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_micros() as u64)
+}
+
+pub(crate) fn echo_payload(probe: &[u8], received: u64) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(16);
+    payload.extend_from_slice(&probe[..probe.len().min(8)]);
+    payload.extend_from_slice(&received.to_be_bytes());
+    payload
+}
+
+pub(crate) struct Sample {
+    pub sent: u64,
+    pub peer_received: u64,
+    pub received: u64,
+}
+
+impl Sample {
+    /// Parses an echo payload with the local receive time appended.
+    pub fn parse(payload: &[u8]) -> Option<Self> {
+        let field = |i: usize| Some(u64::from_be_bytes(payload.get(i * 8..i * 8 + 8)?.try_into().ok()?));
+        Some(Self {
+            sent: field(0)?,
+            peer_received: field(1)?,
+            received: field(2)?,
+        })
+    }
+
+    fn forward_us(&self) -> i64 {
+        self.peer_received as i64 - self.sent as i64
+    }
+
+    fn reverse_us(&self) -> i64 {
+        self.received as i64 - self.peer_received as i64
+    }
+
+    fn rtt_us(&self) -> i64 {
+        self.received as i64 - self.sent as i64
+    }
+}
+
+fn millis_stats(values: impl Iterator<Item = i64> + Clone) -> serde_json::Value {
+    let count = values.clone().count();
+    if count == 0 {
+        return serde_json::Value::Null;
+    }
+    let ms = |us: i64| us as f64 / 1000.0;
+    serde_json::json!({
+        "min": ms(values.clone().min().unwrap_or(0)),
+        "avg": ms(values.clone().sum::<i64>() / count as i64),
+        "max": ms(values.max().unwrap_or(0)),
+    })
+}
+
+/// `{ sent, received, rtt_ms, forward_ms, reverse_ms, clock_offset_ms }`; the delay fields
+/// are `{ min, avg, max }`, and they and `clock_offset_ms` are `null` without samples.
+pub(crate) fn summarize(sent: u32, samples: &[Sample]) -> serde_json::Value {
+    let clock_offset_ms = samples
+        .iter()
+        .min_by_key(|sample| sample.rtt_us())
+        .map(|sample| (sample.forward_us() - sample.reverse_us()) as f64 / 2000.0);
+    serde_json::json!({
+        "sent": sent,
+        "received": samples.len(),
+        "rtt_ms": millis_stats(samples.iter().map(Sample::rtt_us)),
+        "forward_ms": millis_stats(samples.iter().map(Sample::forward_us)),
+        "reverse_ms": millis_stats(samples.iter().map(Sample::reverse_us)),
+        "clock_offset_ms": clock_offset_ms,
+    })
+}
@@ -0,0 +1,744 @@
+//! Direct peer endpoints built on quinn, mirroring the offer format of the Rust SDK's
+//! `QuicPeerServer`/`connect_quic_peer` so offers stay interoperable. Owning the TLS and quinn
+//! configuration here is what lets the bindings report verifier failures in detail.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use base64::Engine as _;
+use napi::bindgen_prelude::*;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+
+use crate::error::{self, quic_err, Code};
+use crate::{cidr, key_binding, to_napi_err};
+
+/// The Rust SDK's peer defaults, so offers from either side connect to the other.
+const DEFAULT_ALPN: &str = "gann-quic-p2p/1";
+const DEFAULT_SERVER_NAME: &str = "gann-peer";
+const SESSION_CACHE_SIZE: usize = 256;
+/// TLS alert a server sends when it shares no ALPN protocol with the client.
+const ALERT_NO_APPLICATION_PROTOCOL: u8 = 120;
+
+const OFFER_BYTES_VERSION: u8 = 1;
+const OFFER_TAG_E2EE_PUBKEY: u8 = 1;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct QuicOffer {
+    pub candidates: Vec<String>,
+    pub cert_der_b64: String,
+    pub fingerprint_sha256: String,
+    #[serde(default = "default_alpn")]
+    pub alpn: String,
+    #[serde(default = "default_server_name")]
+    pub server_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub e2ee_pubkey_b64: Option<String>,
+}
+
+fn default_alpn() -> String {
+    DEFAULT_ALPN.to_string()
+}
+
+fn default_server_name() -> String {
+    DEFAULT_SERVER_NAME.to_string()
+}
+
+impl QuicOffer {
+    /// The candidates as numeric addresses only, for the binary form.
+    pub fn candidate_addrs(&self) -> Result<Vec<SocketAddr>> {
+        self.candidates
+            .iter()
+            .map(|raw| {
+                raw.parse::<SocketAddr>()
+                    .map_err(|_| error::invalid_argument("invalid candidate"))
+            })
+            .collect()
+    }
+
+    /// The candidates for connecting: numeric addresses as they are, and `host:port` entries
+    /// resolved now to every address the name has, in resolver order and without duplicates.
+    /// Names that fail to resolve are skipped unless nothing resolves at all.
+    pub async fn resolve_candidates(&self) -> Result<Vec<SocketAddr>> {
+        let mut addrs = Vec::new();
+        let mut last_err = None;
+        for raw in &self.candidates {
+            if let Ok(addr) = raw.parse::<SocketAddr>() {
+                addrs.push(addr);
+                continue;
+            }
+            if raw
+                .rsplit_once(':')
+                .is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err())
+            {
+                return Err(error::invalid_argument(format!("invalid candidate {raw:?}")));
+            }
+            match tokio::net::lookup_host(raw.as_str()).await {
+                Ok(resolved) => addrs.extend(resolved),
+                Err(err) => last_err = Some(to_napi_err(format!("failed to resolve candidate {raw:?}: {err}"))),
+            }
+        }
+        let mut seen = std::collections::HashSet::new();
+        addrs.retain(|addr| seen.insert(*addr));
+        match (addrs.is_empty(), last_err) {
+            (true, Some(err)) => Err(err),
+            _ => Ok(addrs),
+        }
+    }
+
+    /// Packed binary form, roughly half the size of the JSON. Layout (integers big-endian):
+    /// - u8 version (`1`); decoders reject other versions
+    /// - u8 candidate count, then per candidate u8 family (`4` or `6`), the address bytes and
+    ///   a u16 port
+    /// - u16 length + raw certificate DER (the fingerprint is recomputed from it on decode)
+    /// - u8 length + ALPN, u8 length + server name
+    /// - optional fields until the end, each u8 tag + u16 length + value; decoders skip tags
+    ///   they do not know, so fields can be added without a version bump. Tag `1` is the raw
+    ///   E2EE public key.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let b64 = &base64::engine::general_purpose::STANDARD;
+        let cert_der = b64
+            .decode(&self.cert_der_b64)
+            .map_err(|_| to_napi_err("invalid cert_der_b64"))?;
+        let candidates = self.candidate_addrs()?;
+
+        let mut out = vec![OFFER_BYTES_VERSION];
+        out.push(u8::try_from(candidates.len()).map_err(|_| to_napi_err("too many candidates"))?);
+        for addr in candidates {
+            match addr.ip() {
+                std::net::IpAddr::V4(ip) => {
+                    out.push(4);
+                    out.extend_from_slice(&ip.octets());
+                }
+                std::net::IpAddr::V6(ip) => {
+                    out.push(6);
+                    out.extend_from_slice(&ip.octets());
+                }
+            }
+            out.extend_from_slice(&addr.port().to_be_bytes());
+        }
+        let cert_len = u16::try_from(cert_der.len()).map_err(|_| to_napi_err("certificate too large"))?;
+        out.extend_from_slice(&cert_len.to_be_bytes());
+        out.extend_from_slice(&cert_der);
+        for field in [&self.alpn, &self.server_name] {
+            out.push(u8::try_from(field.len()).map_err(|_| to_napi_err("alpn or server name too long"))?);
+            out.extend_from_slice(field.as_bytes());
+        }
+        if let Some(pubkey) = &self.e2ee_pubkey_b64 {
+            let pubkey = b64.decode(pubkey).map_err(|_| to_napi_err("invalid e2ee_pubkey_b64"))?;
+            out.push(OFFER_TAG_E2EE_PUBKEY);
+            out.extend_from_slice(&(pubkey.len() as u16).to_be_bytes());
+            out.extend_from_slice(&pubkey);
+        }
+        Ok(out)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let b64 = &base64::engine::general_purpose::STANDARD;
+        let mut reader = ByteReader(bytes);
+        let version = reader.u8()?;
+        if version != OFFER_BYTES_VERSION {
+            return Err(to_napi_err(format!("unsupported offer bytes version {version}")));
+        }
+
+        let mut candidates = Vec::new();
+        for _ in 0..reader.u8()? {
+            let ip: std::net::IpAddr = match reader.u8()? {
+                4 => <[u8; 4]>::try_from(reader.take(4)?).expect("4 bytes").into(),
+                6 => <[u8; 16]>::try_from(reader.take(16)?).expect("16 bytes").into(),
+                family => return Err(to_napi_err(format!("invalid candidate family {family}"))),
+            };
+            candidates.push(SocketAddr::new(ip, reader.u16()?).to_string());
+        }
+        let cert_len = reader.u16()?;
+        let cert_der = reader.take(cert_len.into())?;
+        let alpn_len = reader.u8()?;
+        let alpn = reader.string(alpn_len.into())?;
+        let server_name_len = reader.u8()?;
+        let server_name = reader.string(server_name_len.into())?;
+
+        let mut e2ee_pubkey_b64 = None;
+        while !reader.0.is_empty() {
+            let tag = reader.u8()?;
+            let len = reader.u16()?;
+            let value = reader.take(len.into())?;
+            if tag == OFFER_TAG_E2EE_PUBKEY {
+                e2ee_pubkey_b64 = Some(b64.encode(value));
+            }
+        }
+
+        Ok(Self {
+            candidates,
+            cert_der_b64: b64.encode(cert_der),
+            fingerprint_sha256: fingerprint_sha256(cert_der),
+            alpn,
+            server_name,
+            e2ee_pubkey_b64,
+        })
+    }
+}
+
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(to_napi_err("truncated offer bytes"));
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn string(&mut self, len: usize) -> Result<String> {
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| to_napi_err("invalid utf-8 in offer bytes"))
+    }
+}
+
+pub(crate) struct PeerIdentity {
+    pub cert_der: Vec<u8>,
+    pub alpn: String,
+    pub server_name: String,
+    pub signing_key: Arc<dyn rustls::sign::SigningKey>,
+}
+
+impl PeerIdentity {
+    pub fn offer(&self, candidates: Vec<SocketAddr>) -> QuicOffer {
+        QuicOffer {
+            candidates: candidates.into_iter().map(|addr| addr.to_string()).collect(),
+            cert_der_b64: base64::engine::general_purpose::STANDARD.encode(&self.cert_der),
+            fingerprint_sha256: fingerprint_sha256(&self.cert_der),
+            alpn: self.alpn.clone(),
+            server_name: self.server_name.clone(),
+            e2ee_pubkey_b64: None,
+        }
+    }
+}
+
+pub(crate) fn fingerprint_sha256(der: &[u8]) -> String {
+    Sha256::digest(der).iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Candidates for an endpoint bound to `addr`, as the Rust SDK advertises them when none are
+/// given: an unspecified address is not dialable, so it becomes loopback (both families for
+/// `[::]`, which usually accepts IPv4 too).
+pub(crate) fn default_candidates(addr: SocketAddr) -> Vec<SocketAddr> {
+    match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => vec![SocketAddr::new(Ipv4Addr::LOCALHOST.into(), addr.port())],
+        IpAddr::V6(ip) if ip.is_unspecified() => vec![
+            SocketAddr::new(Ipv6Addr::LOCALHOST.into(), addr.port()),
+            SocketAddr::new(Ipv4Addr::LOCALHOST.into(), addr.port()),
+        ],
+        _ => vec![addr],
+    }
+}
+
+/// Checks an offer's `fingerprint_sha256` against the certificate it carries. Like the Rust
+/// SDK, the fingerprint is required and must be 64 hex digits.
+fn check_fingerprint(fingerprint: &str, cert_der: &[u8]) -> Result<()> {
+    let fingerprint = fingerprint.trim();
+    if fingerprint.len() != 64 || !fingerprint.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(error::invalid_argument("fingerprint_sha256 must be 64 hex digits"));
+    }
+    if !fingerprint.eq_ignore_ascii_case(&fingerprint_sha256(cert_der)) {
+        return Err(error::invalid_argument("certificate fingerprint mismatch"));
+    }
+    Ok(())
+}
+
+/// Generates a fresh self-signed identity and binds a server endpoint with it, accepting the
+/// `alpn` protocols (default `gann-quic-p2p/1`). Resuming clients may send 0-RTT data only
+/// with `accept_early_data`. Must run inside the tokio runtime.
+pub(crate) fn create_server(
+    bind_addr: SocketAddr,
+    transport: Arc<quinn::TransportConfig>,
+    alpn: Option<Vec<String>>,
+    allow_migration: bool,
+    accept_early_data: bool,
+) -> Result<(quinn::Endpoint, PeerIdentity)> {
+    let alpn = alpn.unwrap_or_else(|| vec![DEFAULT_ALPN.to_string()]);
+    let server_name = DEFAULT_SERVER_NAME.to_string();
+    let cert = rcgen::generate_simple_self_signed(vec![server_name.clone()]).map_err(to_napi_err)?;
+    let cert_der = cert.serialize_der().map_err(to_napi_err)?;
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.serialize_private_key_der()));
+    let signing_key = key_binding::load_signing_key(&key)?;
+
+    let mut tls = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![CertificateDer::from(cert_der.clone())], key)
+        .map_err(to_napi_err)?;
+    tls.alpn_protocols = alpn.iter().map(|id| id.as_bytes().to_vec()).collect();
+    if accept_early_data {
+        // quinn requires either 0 or the maximum here.
+        tls.max_early_data_size = u32::MAX;
+    }
+
+    let crypto = quinn::crypto::rustls::QuicServerConfig::try_from(tls).map_err(to_napi_err)?;
+    let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+    server_config.transport_config(transport);
+    server_config.migration(allow_migration);
+    let endpoint = quinn::Endpoint::server(server_config, bind_addr).map_err(to_napi_err)?;
+
+    Ok((
+        endpoint,
+        PeerIdentity {
+            cert_der,
+            alpn: alpn[0].clone(),
+            server_name,
+            signing_key,
+        },
+    ))
+}
+
+/// Caps the handshakes a server runs at once, since each costs a round of public-key crypto.
+/// Independent of how many established connections the endpoint holds.
+pub(crate) struct Handshakes {
+    limit: Option<Arc<tokio::sync::Semaphore>>,
+    in_progress: Arc<AtomicU32>,
+}
+
+impl Handshakes {
+    /// `0` means unlimited.
+    pub fn new(max_concurrent: u32) -> Arc<Self> {
+        Arc::new(Self {
+            limit: (max_concurrent > 0).then(|| Arc::new(tokio::sync::Semaphore::new(max_concurrent as usize))),
+            in_progress: Arc::new(AtomicU32::new(0)),
+        })
+    }
+
+    pub fn in_progress(&self) -> u32 {
+        self.in_progress.load(Ordering::Relaxed)
+    }
+
+    /// Waits for a free slot, then starts the handshake for `incoming`. The slot is held
+    /// until the returned `Handshake` finishes or is dropped.
+    pub async fn start(&self, incoming: quinn::Incoming) -> std::result::Result<Handshake, quinn::ConnectionError> {
+        let permit = match &self.limit {
+            Some(limit) => Some(
+                limit
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("handshake semaphore is never closed"),
+            ),
+            None => None,
+        };
+        self.in_progress.fetch_add(1, Ordering::Relaxed);
+        let slot = HandshakeSlot {
+            _permit: permit,
+            in_progress: self.in_progress.clone(),
+        };
+        Ok(Handshake {
+            connecting: incoming.accept()?,
+            _slot: slot,
+        })
+    }
+
+    /// Waits for a free slot, then runs the handshake for `incoming`.
+    pub async fn complete(
+        &self,
+        incoming: quinn::Incoming,
+    ) -> std::result::Result<quinn::Connection, quinn::ConnectionError> {
+        self.start(incoming).await?.finish().await
+    }
+}
+
+struct HandshakeSlot {
+    _permit: Option<tokio::sync::OwnedSemaphorePermit>,
+    in_progress: Arc<AtomicU32>,
+}
+
+impl Drop for HandshakeSlot {
+    fn drop(&mut self) {
+        self.in_progress.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A server handshake in progress, counted by its `Handshakes`.
+pub(crate) struct Handshake {
+    connecting: quinn::Connecting,
+    _slot: HandshakeSlot,
+}
+
+impl Handshake {
+    /// The client's SNI and the ALPN protocol chosen from its offer, available as soon as the
+    /// ClientHello has been processed, before the handshake completes.
+    pub async fn client_hello(
+        &mut self,
+    ) -> std::result::Result<Option<quinn::crypto::rustls::HandshakeData>, quinn::ConnectionError> {
+        let data = self.connecting.handshake_data().await?;
+        Ok(data
+            .downcast::<quinn::crypto::rustls::HandshakeData>()
+            .ok()
+            .map(|data| *data))
+    }
+
+    pub async fn finish(self) -> std::result::Result<quinn::Connection, quinn::ConnectionError> {
+        self.connecting.await
+    }
+
+    /// Gives up the slot, e.g. to close the connection right away.
+    pub fn into_connecting(self) -> quinn::Connecting {
+        self.connecting
+    }
+}
+
+/// Accepts the next connection that completes its handshake; failed handshakes are skipped.
+/// `None` once the endpoint is closed, or once `deadline` passes without a client arriving.
+/// The deadline only bounds the wait for clients: a handshake that has started runs to the
+/// end, since dropping it would abort the client's connection.
+pub(crate) async fn accept(
+    endpoint: &quinn::Endpoint,
+    handshakes: &Handshakes,
+    allowlist: Option<&cidr::Allowlist>,
+    deadline: Option<tokio::time::Instant>,
+) -> Option<quinn::Connection> {
+    loop {
+        let next = next_allowed(endpoint, allowlist);
+        let incoming = match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, next).await.ok()??,
+            None => next.await?,
+        };
+        if let Ok(conn) = handshakes.complete(incoming).await {
+            return Some(conn);
+        }
+    }
+}
+
+/// The next incoming connection from an allowed address, refusing the others.
+pub(crate) async fn next_allowed(
+    endpoint: &quinn::Endpoint,
+    allowlist: Option<&cidr::Allowlist>,
+) -> Option<quinn::Incoming> {
+    loop {
+        let incoming = endpoint.accept().await?;
+        match allowlist {
+            Some(allowlist) if !allowlist.allows(incoming.remote_address().ip()) => incoming.refuse(),
+            _ => return Some(incoming),
+        }
+    }
+}
+
+/// Client TLS settings from the `PeerClient` options.
+pub(crate) struct ClientTls {
+    /// Advertised instead of the offer's protocol when set.
+    pub alpn: Option<Vec<String>>,
+    /// Lowercase hex SHA-256 the server's leaf certificate must match.
+    pub pinned_sha256: Option<String>,
+    /// Session tickets from earlier connections, shared so reconnects can resume (and send
+    /// 0-RTT data). Keyed by certificate fingerprint through `PinnedSessions`, since peers
+    /// share the default server name.
+    sessions: Arc<rustls::client::ClientSessionMemoryCache>,
+    /// TLS configs by certificate fingerprint and ALPN list. rustls only resumes a session
+    /// through the verifier that stored it, so connects to one server must reuse its config.
+    configs: std::sync::Mutex<HashMap<ServerTlsKey, ServerTls>>,
+}
+
+/// Certificate fingerprint and ALPN protocol ids.
+type ServerTlsKey = (String, Vec<Vec<u8>>);
+
+/// Client TLS configuration for one server certificate.
+#[derive(Clone)]
+struct ServerTls {
+    crypto: Arc<quinn::crypto::rustls::QuicClientConfig>,
+    verifier: Arc<CapturingVerifier>,
+}
+
+impl ClientTls {
+    pub fn new(alpn: Option<Vec<String>>, pinned_sha256: Option<String>) -> Self {
+        Self {
+            alpn,
+            pinned_sha256,
+            sessions: Arc::new(rustls::client::ClientSessionMemoryCache::new(SESSION_CACHE_SIZE)),
+            configs: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The config for servers presenting `cert_der`, which is its only trust anchor, built on
+    /// first use.
+    fn server_tls(&self, cert_der: Vec<u8>, alpn: Vec<Vec<u8>>) -> Result<ServerTls> {
+        let key = (fingerprint_sha256(&cert_der), alpn);
+        let mut configs = self.configs.lock().unwrap();
+        if let Some(tls) = configs.get(&key) {
+            return Ok(tls.clone());
+        }
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(CertificateDer::from(cert_der)).map_err(to_napi_err)?;
+        let inner = rustls::client::WebPkiServerVerifier::builder(Arc::new(roots))
+            .build()
+            .map_err(to_napi_err)?;
+        let verifier = Arc::new(CapturingVerifier {
+            inner,
+            pinned_sha256: self.pinned_sha256.clone(),
+            failure: std::sync::Mutex::new(None),
+        });
+
+        let mut tls = rustls::ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(verifier.clone())
+            .with_no_client_auth();
+        tls.alpn_protocols = key.1.clone();
+        let sessions = PinnedSessions::new(self.sessions.clone(), &key.0)?;
+        tls.resumption = rustls::client::Resumption::store(Arc::new(sessions));
+        tls.enable_early_data = true;
+        let crypto = quinn::crypto::rustls::QuicClientConfig::try_from(tls).map_err(to_napi_err)?;
+
+        let tls = ServerTls {
+            crypto: Arc::new(crypto),
+            verifier,
+        };
+        if configs.len() >= SESSION_CACHE_SIZE {
+            // Forgets every server (and its resumption) at once rather than tracking recency.
+            configs.clear();
+        }
+        configs.insert(key, tls.clone());
+        Ok(tls)
+    }
+}
+
+/// The client session cache as seen by connections to one certificate: every entry is stored
+/// under a name derived from the fingerprint instead of the server name, so servers sharing
+/// a name (every peer using the default `gann-peer`) keep separate tickets.
+#[derive(Debug)]
+struct PinnedSessions {
+    inner: Arc<rustls::client::ClientSessionMemoryCache>,
+    key: ServerName<'static>,
+}
+
+impl PinnedSessions {
+    fn new(inner: Arc<rustls::client::ClientSessionMemoryCache>, fingerprint: &str) -> Result<Self> {
+        // A DNS label holds at most 63 characters, so the 64 hex digits take two.
+        let (head, tail) = fingerprint.split_at(32);
+        let key = ServerName::try_from(format!("{head}.{tail}.sessions.invalid")).map_err(to_napi_err)?;
+        Ok(Self { inner, key })
+    }
+}
+
+impl rustls::client::ClientSessionStore for PinnedSessions {
+    fn set_kx_hint(&self, _: ServerName<'static>, group: rustls::NamedGroup) {
+        self.inner.set_kx_hint(self.key.clone(), group)
+    }
+
+    fn kx_hint(&self, _: &ServerName<'_>) -> Option<rustls::NamedGroup> {
+        self.inner.kx_hint(&self.key)
+    }
+
+    fn set_tls12_session(&self, _: ServerName<'static>, value: rustls::client::Tls12ClientSessionValue) {
+        self.inner.set_tls12_session(self.key.clone(), value)
+    }
+
+    fn tls12_session(&self, _: &ServerName<'_>) -> Option<rustls::client::Tls12ClientSessionValue> {
+        self.inner.tls12_session(&self.key)
+    }
+
+    fn remove_tls12_session(&self, _: &ServerName<'static>) {
+        self.inner.remove_tls12_session(&self.key)
+    }
+
+    fn insert_tls13_ticket(&self, _: ServerName<'static>, value: rustls::client::Tls13ClientSessionValue) {
+        self.inner.insert_tls13_ticket(self.key.clone(), value)
+    }
+
+    fn take_tls13_ticket(&self, _: &ServerName<'static>) -> Option<rustls::client::Tls13ClientSessionValue> {
+        self.inner.take_tls13_ticket(&self.key)
+    }
+}
+
+/// Wraps the webpki verifier so the specific rejection survives the handshake; quinn only
+/// reports a TLS alert number once verification fails. Also enforces the certificate pin.
+#[derive(Debug)]
+struct CapturingVerifier {
+    inner: Arc<rustls::client::WebPkiServerVerifier>,
+    pinned_sha256: Option<String>,
+    failure: std::sync::Mutex<Option<rustls::Error>>,
+}
+
+impl ServerCertVerifier for CapturingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        let pin_mismatch = self
+            .pinned_sha256
+            .as_ref()
+            .is_some_and(|pin| *pin != fingerprint_sha256(end_entity));
+        if pin_mismatch {
+            let err = rustls::Error::InvalidCertificate(CertificateError::ApplicationVerificationFailure);
+            *self.failure.lock().unwrap() = Some(err.clone());
+            return Err(err);
+        }
+        self.inner
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+            .inspect_err(|err| *self.failure.lock().unwrap() = Some(err.clone()))
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+fn describe_verification_failure(err: &rustls::Error) -> String {
+    if let rustls::Error::InvalidCertificate(CertificateError::ApplicationVerificationFailure) = err {
+        return "certificate verification failed (pin_mismatch): certificate does not match pinnedCertSha256".into();
+    }
+    let kind = match err {
+        rustls::Error::InvalidCertificate(cert_err) => match cert_err {
+            CertificateError::Expired | CertificateError::ExpiredContext { .. } => "expired",
+            CertificateError::NotValidYet | CertificateError::NotValidYetContext { .. } => "not_yet_valid",
+            CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. } => "name_mismatch",
+            CertificateError::UnknownIssuer => "unknown_issuer",
+            CertificateError::Revoked => "revoked",
+            _ => "invalid_certificate",
+        },
+        _ => "invalid_certificate",
+    };
+    format!("certificate verification failed ({kind}): {err}")
+}
+
+/// Client-side view of an offer: the offered certificate is the only trust anchor, so the
+/// standard webpki checks (validity period, name, issuer) apply to it.
+pub(crate) struct OfferTarget {
+    config: quinn::ClientConfig,
+    verifier: Arc<CapturingVerifier>,
+    server_name: String,
+}
+
+impl OfferTarget {
+    pub fn new(offer: &QuicOffer, transport: Arc<quinn::TransportConfig>, client: &ClientTls) -> Result<Self> {
+        let cert_der = base64::engine::general_purpose::STANDARD
+            .decode(&offer.cert_der_b64)
+            .map_err(|_| to_napi_err("invalid cert_der_b64"))?;
+        check_fingerprint(&offer.fingerprint_sha256, &cert_der)?;
+        let alpn = match &client.alpn {
+            Some(alpn) => alpn.iter().map(|id| id.as_bytes().to_vec()).collect(),
+            None => vec![offer.alpn.as_bytes().to_vec()],
+        };
+        let tls = client.server_tls(cert_der, alpn)?;
+
+        let mut config = quinn::ClientConfig::new(tls.crypto);
+        config.transport_config(transport);
+
+        Ok(Self {
+            config,
+            verifier: tls.verifier,
+            server_name: offer.server_name.clone(),
+        })
+    }
+
+    /// Starts a handshake to `addr` and, if a session ticket for the server is cached, hands
+    /// out the connection right away for 0-RTT use. `None` without a ticket, in which case
+    /// the attempt is dropped and the caller should `connect` normally.
+    pub fn connect_0rtt(
+        &self,
+        endpoint: &quinn::Endpoint,
+        addr: SocketAddr,
+    ) -> Result<Option<(quinn::Connection, quinn::ZeroRttAccepted)>> {
+        let connecting = endpoint
+            .connect_with(self.config.clone(), addr, &self.server_name)
+            .map_err(error::invalid_argument)?;
+        Ok(connecting.into_0rtt().ok())
+    }
+
+    /// Races a handshake to every candidate at once and keeps the first to complete, with the
+    /// candidate it was dialed on. The losers are aborted, and any that completed meanwhile
+    /// closed with code 0 ("lost candidate race"), so no half-open connection lingers.
+    pub async fn connect(
+        &self,
+        endpoint: &quinn::Endpoint,
+        candidates: &[SocketAddr],
+    ) -> Result<(quinn::Connection, SocketAddr)> {
+        // The verifier is shared with earlier connects to this server; forget their failure.
+        *self.verifier.failure.lock().unwrap() = None;
+        let mut attempts = tokio::task::JoinSet::new();
+        let mut last_err = None;
+        for addr in candidates {
+            match endpoint.connect_with(self.config.clone(), *addr, &self.server_name) {
+                Ok(connecting) => {
+                    let addr = *addr;
+                    attempts.spawn(async move { (addr, connecting.await) });
+                }
+                Err(err) => last_err = Some(error::invalid_argument(err)),
+            }
+        }
+
+        while let Some(joined) = attempts.join_next().await {
+            match joined {
+                Ok((addr, Ok(conn))) => {
+                    close_losers(attempts);
+                    return Ok((conn, addr));
+                }
+                Ok((_, Err(quinn::ConnectionError::ConnectionClosed(close))))
+                    if close.error_code == quinn::TransportErrorCode::crypto(ALERT_NO_APPLICATION_PROTOCOL) =>
+                {
+                    last_err = Some(error::coded(
+                        Code::HandshakeFailed,
+                        "the server accepts none of the offered ALPN protocols",
+                    ));
+                }
+                Ok((_, Err(err))) => last_err = Some(quic_err(err)),
+                Err(err) => last_err = Some(to_napi_err(err)),
+            }
+        }
+
+        if let Some(failure) = self.verifier.failure.lock().unwrap().as_ref() {
+            return Err(error::coded(
+                Code::HandshakeFailed,
+                describe_verification_failure(failure),
+            ));
+        }
+        Err(last_err.unwrap_or_else(|| error::invalid_argument("no candidates")))
+    }
+}
+
+type Attempts = tokio::task::JoinSet<(
+    SocketAddr,
+    std::result::Result<quinn::Connection, quinn::ConnectionError>,
+)>;
+
+/// Aborts the attempts still running and closes those that finished after the winner.
+fn close_losers(mut attempts: Attempts) {
+    attempts.abort_all();
+    tokio::spawn(async move {
+        while let Some(joined) = attempts.join_next().await {
+            if let Ok((_, Ok(conn))) = joined {
+                conn.close(0u32.into(), b"lost candidate race");
+            }
+        }
+    });
+}
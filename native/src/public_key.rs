@@ -0,0 +1,63 @@
+//! Checks on peer X25519 public keys before they reach the Rust SDK's key derivation.
+//!
+//! Each failure is an `INVALID_ARGUMENT` error with its own message:
+//! - `"peer public key is not valid base64"`: not standard, padded base64
+//! - `"peer public key must be 32 bytes, got N"`: decodes to the wrong length
+//! - `"peer public key is a low-order point"`: one of the points X25519 maps every private
+//!   key to the same (all-zero) shared secret with, so the derived key would be public
+//!
+//! The low-order list is libsodium's, compared with the top bit cleared since X25519 ignores
+//! it: 0, 1, the two points of order 8, and p - 1, p, p + 1 (p = 2^255 - 19).
+
+use base64::Engine as _;
+use napi::bindgen_prelude::*;
+
+use crate::error;
+
+const KEY_BYTES: usize = 32;
+
+const LOW_ORDER: [[u8; KEY_BYTES]; 7] = [
+    [0x00; KEY_BYTES],
+    [
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    [
+        0xe0, 0xeb, 0x7a, 0x7c, 0x3b, 0x41, 0xb8, 0xae, 0x16, 0x56, 0xe3, 0xfa, 0xf1, 0x9f, 0xc4, 0x6a, 0xda, 0x09,
+        0x8d, 0xeb, 0x9c, 0x32, 0xb1, 0xfd, 0x86, 0x62, 0x05, 0x16, 0x5f, 0x49, 0xb8, 0x00,
+    ],
+    [
+        0x5f, 0x9c, 0x95, 0xbc, 0xa3, 0x50, 0x8c, 0x24, 0xb1, 0xd0, 0xb1, 0x55, 0x9c, 0x83, 0xef, 0x5b, 0x04, 0x44,
+        0x5c, 0xc4, 0x58, 0x1c, 0x8e, 0x86, 0xd8, 0x22, 0x4e, 0xdd, 0xd0, 0x9f, 0x11, 0x57,
+    ],
+    [
+        0xec, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+    ],
+    [
+        0xed, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+    ],
+    [
+        0xee, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+    ],
+];
+
+/// Validates a base64 peer public key, failing as described in the module docs.
+pub(crate) fn validate_peer(peer_public_b64: &str) -> Result<()> {
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(peer_public_b64)
+        .map_err(|_| error::invalid_argument("peer public key is not valid base64"))?;
+    let mut key: [u8; KEY_BYTES] = decoded.as_slice().try_into().map_err(|_| {
+        error::invalid_argument(format!(
+            "peer public key must be {KEY_BYTES} bytes, got {}",
+            decoded.len()
+        ))
+    })?;
+    key[KEY_BYTES - 1] &= 0x7f;
+    if LOW_ORDER.contains(&key) {
+        return Err(error::invalid_argument("peer public key is a low-order point"));
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! RTT-aware send rate ceiling shared by a connection's streams.
+//!
+//! A token bucket refills at the target rate and holds at most one bandwidth-delay product
+//! (target rate × current RTT), so an idle sender may burst one BDP before being paced. A
+//! single write larger than the bucket is let through once the bucket is full and drives it
+//! into debt, which later writes then wait out.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Lower bound on the bucket so tiny RTTs (e.g. loopback) still admit a full packet.
+const MIN_BURST_BYTES: f64 = 1500.0;
+
+struct Bucket {
+    target_bps: f64,
+    tokens: f64,
+    refilled: Instant,
+    set_at: Instant,
+    admitted: u64,
+}
+
+pub(crate) struct RateLimit {
+    conn: quinn::Connection,
+    bucket: Mutex<Option<Bucket>>,
+}
+
+impl RateLimit {
+    pub fn new(conn: quinn::Connection) -> Self {
+        Self {
+            conn,
+            bucket: Mutex::new(None),
+        }
+    }
+
+    /// `0` removes the limit.
+    pub fn set(&self, target_bps: u32) {
+        let now = Instant::now();
+        *self.bucket.lock().unwrap() = (target_bps > 0).then(|| Bucket {
+            target_bps: target_bps.into(),
+            tokens: 0.0,
+            refilled: now,
+            set_at: now,
+            admitted: 0,
+        });
+    }
+
+    /// Waits until `bytes` may be sent under the limit.
+    pub async fn acquire(&self, bytes: usize) {
+        loop {
+            let wait = {
+                let mut guard = self.bucket.lock().unwrap();
+                let Some(bucket) = guard.as_mut() else {
+                    return;
+                };
+                let rate = bucket.target_bps / 8.0;
+                let burst = (rate * self.conn.stats().path.rtt.as_secs_f64()).max(MIN_BURST_BYTES);
+                let now = Instant::now();
+                bucket.tokens = (bucket.tokens + rate * (now - bucket.refilled).as_secs_f64()).min(burst);
+                bucket.refilled = now;
+
+                let needed = (bytes as f64).min(burst);
+                if bucket.tokens >= needed {
+                    bucket.tokens -= bytes as f64;
+                    bucket.admitted += bytes as u64;
+                    return;
+                }
+                Duration::from_secs_f64((needed - bucket.tokens) / rate)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Average rate admitted since the limit was set, in bits per second.
+    pub fn effective_bps(&self) -> Option<f64> {
+        let guard = self.bucket.lock().unwrap();
+        let bucket = guard.as_ref()?;
+        let elapsed = bucket.set_at.elapsed().as_secs_f64();
+        (elapsed > 0.0).then(|| bucket.admitted as f64 * 8.0 / elapsed)
+    }
+}
@@ -0,0 +1,32 @@
+//! Associated data for relay payload encryption.
+//!
+//! The Rust SDK's relay AEAD only authenticates the session id, so other associated data is
+//! bound through the key instead: with `aad` present, payloads are sealed under
+//! `SHA-256("gann-relay-aad-v1" || shared_key || u32 BE len(aad) || aad)` rather than the
+//! shared key itself. Opening with different (or missing) associated data then uses the
+//! wrong key and fails authentication. Without `aad` the shared key is used as-is, so the
+//! wire format is unchanged for callers that do not opt in.
+
+use napi::bindgen_prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::error;
+
+const LABEL: &[u8] = b"gann-relay-aad-v1";
+
+/// The key to hand to the SDK's relay encryption, after checking `shared_key`'s length.
+pub(crate) fn relay_key(shared_key: &[u8], aad: Option<&[u8]>) -> Result<[u8; 32]> {
+    let key: [u8; 32] = shared_key
+        .try_into()
+        .map_err(|_| error::invalid_argument("shared_key must be 32 bytes"))?;
+    let Some(aad) = aad else {
+        return Ok(key);
+    };
+    let aad_len = u32::try_from(aad.len()).map_err(|_| error::invalid_argument("aad too large"))?;
+    let mut hasher = Sha256::new();
+    hasher.update(LABEL);
+    hasher.update(key);
+    hasher.update(aad_len.to_be_bytes());
+    hasher.update(aad);
+    Ok(hasher.finalize().into())
+}
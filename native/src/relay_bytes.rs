@@ -0,0 +1,101 @@
+//! Binary payloads over relay frames, whose payload the Rust SDK types as JSON.
+//!
+//! Bytes travel as a JSON object `{ "bytes_v": 1, "b64": <standard base64> }`, so they survive
+//! the SDK's JSON coercion intact; the framing costs a third on top of the raw size. The
+//! JSON and binary receive calls each reject the other kind of payload instead of handing
+//! it over misread.
+
+use base64::Engine as _;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::to_napi_err;
+
+const VERSION: u64 = 1;
+
+pub(crate) fn wrap(payload: &[u8]) -> serde_json::Value {
+    serde_json::json!({
+        "bytes_v": VERSION,
+        "b64": base64::engine::general_purpose::STANDARD.encode(payload),
+    })
+}
+
+pub(crate) fn is_wrapped(payload: &serde_json::Value) -> bool {
+    payload.get("bytes_v").is_some()
+}
+
+/// Checks a frame received as JSON; errors if it carries bytes.
+pub(crate) fn expect_json(frame: &serde_json::Value) -> Result<()> {
+    match frame.get("payload") {
+        Some(payload) if is_wrapped(payload) => Err(to_napi_err(
+            "relay payload is binary (sent with relay_send_bytes); read it with recv_relay_data_bytes",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// A relay frame whose payload was sent with `RelayTransport::relay_send_bytes`.
+#[napi]
+pub struct RelayBytesFrame {
+    session_id: String,
+    from: String,
+    to: String,
+    payload: Vec<u8>,
+}
+
+impl RelayBytesFrame {
+    pub(crate) fn parse(frame: &serde_json::Value) -> Result<Self> {
+        let field = |name: &str| {
+            frame
+                .get(name)
+                .and_then(|value| value.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let payload = frame.get("payload").unwrap_or(&serde_json::Value::Null);
+        if !is_wrapped(payload) {
+            return Err(to_napi_err(
+                "relay payload is JSON, not binary; read it with recv_relay_data",
+            ));
+        }
+        if payload.get("bytes_v").and_then(|version| version.as_u64()) != Some(VERSION) {
+            return Err(to_napi_err("unsupported binary relay payload version"));
+        }
+        let b64 = payload
+            .get("b64")
+            .and_then(|b64| b64.as_str())
+            .ok_or_else(|| to_napi_err("binary relay payload without b64"))?;
+        let payload = base64::engine::general_purpose::STANDARD
+            .decode(b64)
+            .map_err(|err| to_napi_err(format!("invalid binary relay payload: {err}")))?;
+        Ok(Self {
+            session_id: field("session_id"),
+            from: field("from"),
+            to: field("to"),
+            payload,
+        })
+    }
+}
+
+#[napi]
+impl RelayBytesFrame {
+    #[napi]
+    pub fn session_id(&self) -> String {
+        self.session_id.clone()
+    }
+
+    #[napi]
+    pub fn from(&self) -> String {
+        self.from.clone()
+    }
+
+    #[napi]
+    pub fn to(&self) -> String {
+        self.to.clone()
+    }
+
+    #[napi]
+    pub fn payload(&self) -> Buffer {
+        Buffer::from(self.payload.clone())
+    }
+}
@@ -0,0 +1,58 @@
+//! Optional zstd compression of relay JSON payloads.
+//!
+//! A compressed payload travels as `{ "zstd_v": 1, "b64": <standard base64> }` holding the
+//! zstd-compressed JSON text of the original payload. Payloads whose JSON is under
+//! `MIN_COMPRESS_BYTES`, or that do not shrink, are sent unchanged, so the flag only costs
+//! something where it saves more. Receivers expand compressed payloads transparently.
+//!
+//! For `relay_send_e2ee` the wrapper is built before encryption and becomes the plaintext,
+//! so the AEAD authenticates the flag along with the data; the relay cannot toggle it.
+
+use base64::Engine as _;
+use napi::bindgen_prelude::*;
+
+use crate::compress::Codec;
+use crate::to_napi_err;
+
+const VERSION: u64 = 1;
+const MIN_COMPRESS_BYTES: usize = 1024;
+
+/// `payload` compressed and wrapped, or unchanged when compression would not pay off.
+pub(crate) fn wrap(payload: serde_json::Value) -> Result<serde_json::Value> {
+    let json = serde_json::to_vec(&payload).map_err(to_napi_err)?;
+    if json.len() < MIN_COMPRESS_BYTES {
+        return Ok(payload);
+    }
+    let compressed = Codec::Zstd.compress(&json)?;
+    // The wrapper adds base64 and about 25 bytes of JSON.
+    if compressed.len() * 4 / 3 + 32 >= json.len() {
+        return Ok(payload);
+    }
+    Ok(serde_json::json!({
+        "zstd_v": VERSION,
+        "b64": base64::engine::general_purpose::STANDARD.encode(compressed),
+    }))
+}
+
+/// Expands `frame.payload` in place if it was compressed by `wrap`.
+pub(crate) fn unwrap_frame(frame: &mut serde_json::Value) -> Result<()> {
+    let Some(payload) = frame.get_mut("payload") else {
+        return Ok(());
+    };
+    let Some(version) = payload.get("zstd_v") else {
+        return Ok(());
+    };
+    if version.as_u64() != Some(VERSION) {
+        return Err(to_napi_err("unsupported compressed relay payload version"));
+    }
+    let b64 = payload
+        .get("b64")
+        .and_then(|b64| b64.as_str())
+        .ok_or_else(|| to_napi_err("compressed relay payload without b64"))?;
+    let compressed = base64::engine::general_purpose::STANDARD
+        .decode(b64)
+        .map_err(|err| to_napi_err(format!("invalid compressed relay payload: {err}")))?;
+    let json = Codec::Zstd.decompress(&compressed)?;
+    *payload = serde_json::from_slice(&json).map_err(to_napi_err)?;
+    Ok(())
+}
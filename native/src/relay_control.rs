@@ -0,0 +1,75 @@
+//! Relay control requests the Rust SDK's relay protocol has no frame for.
+//!
+//! Requests use the SDK's relay framing: one `{ "op", "payload" }` object written to a fresh bi
+//! stream that is then finished. The relay answers with one `{ "op", "data" }` object carrying
+//! the request's op, or with the SDK's error frame `{ "op": "error", "data": { "message" } }`,
+//! and finishes its side. A relay that predates an op answers it with an error frame, resets
+//! or stops the stream, or stays silent; all of these yield `Reply::Declined` so callers can
+//! degrade gracefully.
+
+use std::time::Duration;
+
+use napi::bindgen_prelude::*;
+
+use crate::error::quic_err;
+use crate::to_napi_err;
+
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+
+/// How the relay answered a control request.
+pub(crate) enum Reply {
+    /// The `data` of a frame with the request's op.
+    Data(serde_json::Value),
+    /// An error frame's message, or why there was no answer. An error frame does not say
+    /// whether the op is unknown to the relay or was refused, so callers treat both alike.
+    Declined(String),
+}
+
+impl Reply {
+    pub(crate) fn data(self) -> Option<serde_json::Value> {
+        match self {
+            Self::Data(data) => Some(data),
+            Self::Declined(_) => None,
+        }
+    }
+}
+
+pub(crate) async fn request(conn: &quinn::Connection, op: &str, payload: serde_json::Value) -> Result<Reply> {
+    let (mut send, mut recv) = conn.open_bi().await.map_err(quic_err)?;
+    let frame = serde_json::json!({ "op": op, "payload": payload });
+    let body = serde_json::to_vec(&frame).map_err(to_napi_err)?;
+    match send.write_all(&body).await {
+        Ok(()) => {}
+        Err(quinn::WriteError::Stopped(_)) => return Ok(Reply::Declined(format!("relay stopped the {op} request"))),
+        Err(err) => return Err(quic_err(err)),
+    }
+    send.finish().map_err(quic_err)?;
+
+    let response = match tokio::time::timeout(CONTROL_TIMEOUT, recv.read_to_end(MAX_RESPONSE_BYTES)).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(quinn::ReadToEndError::Read(quinn::ReadError::Reset(_)))) => {
+            return Ok(Reply::Declined(format!("relay reset the {op} request")));
+        }
+        Err(_) => return Ok(Reply::Declined(format!("relay did not answer the {op} request"))),
+        Ok(Err(quinn::ReadToEndError::Read(err))) => return Err(quic_err(err)),
+        Ok(Err(err)) => return Err(to_napi_err(err)),
+    };
+    if response.is_empty() {
+        return Ok(Reply::Declined(format!("relay did not answer the {op} request")));
+    }
+
+    let mut response: serde_json::Value = serde_json::from_slice(&response).map_err(to_napi_err)?;
+    let data = response
+        .get_mut("data")
+        .map(serde_json::Value::take)
+        .unwrap_or_default();
+    match response.get("op").and_then(|op| op.as_str()) {
+        Some("error") => {
+            let message = data.get("message").and_then(|message| message.as_str());
+            Ok(Reply::Declined(message.unwrap_or("relay error").to_string()))
+        }
+        Some(answered) if answered == op => Ok(Reply::Data(data)),
+        _ => Err(to_napi_err(format!("unexpected {op} response"))),
+    }
+}
@@ -0,0 +1,72 @@
+//! Telling encrypted relay payloads apart from plain JSON on receive.
+//!
+//! The Rust SDK does not mark E2EE payloads, so they are recognized by shape: an object with
+//! exactly the top-level fields of the SDK's encrypted payload, taken once from a throwaway
+//! encryption. Forward-secret (`forward_secret.rs`) and sequenced (`relay_seq.rs`) payloads
+//! carry their own markers. A plain payload that happens to have the same fields as the SDK's
+//! is misclassified; application JSON rarely looks like that.
+
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+use gann_sdk as rust;
+use napi::bindgen_prelude::*;
+
+use crate::error::{self, Code};
+use crate::to_napi_err;
+
+fn keys(value: &serde_json::Value) -> Option<BTreeSet<String>> {
+    Some(value.as_object()?.keys().cloned().collect())
+}
+
+fn sdk_sealed_keys() -> &'static Option<BTreeSet<String>> {
+    static KEYS: OnceLock<Option<BTreeSet<String>>> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let sealed = rust::encrypt_relay_payload(&[0u8; 32], uuid::Uuid::nil(), &serde_json::Value::Null).ok()?;
+        keys(&serde_json::to_value(&sealed).ok()?)
+    })
+}
+
+/// Whether `payload` is what `relay_send_e2ee` (or `encrypt_relay_payload` without `seq`)
+/// produces.
+fn is_sdk_sealed(payload: &serde_json::Value) -> bool {
+    sdk_sealed_keys()
+        .as_ref()
+        .is_some_and(|sealed| keys(payload).as_ref() == Some(sealed))
+}
+
+/// Whether `payload` is encrypted in any of the forms this crate produces.
+fn is_encrypted(payload: &serde_json::Value) -> bool {
+    let sequenced = payload.get("seq").is_some() && payload.get("sealed").is_some();
+    is_sdk_sealed(payload) || payload.get("fs_v").is_some() || sequenced
+}
+
+/// Refuses a payload for `recv_relay_data_e2ee` that is not SDK-sealed. The SDK's decryption
+/// passes such payloads through as plaintext, so a relay could otherwise inject plain JSON
+/// that reads as having been encrypted by the peer.
+pub(crate) fn expect_sdk_sealed(payload: &serde_json::Value) -> Result<()> {
+    if is_sdk_sealed(payload) {
+        return Ok(());
+    }
+    Err(error::coded(
+        Code::NotEncrypted,
+        "relay payload is not end-to-end encrypted (sent without relay_send_e2ee); read it with recv_relay_data",
+    ))
+}
+
+/// Adds `encrypted` to a frame for `recv_relay_data`, refusing E2EE payloads, which only
+/// `recv_relay_data_e2ee` can decrypt. Forward-secret and sequenced payloads pass with
+/// `encrypted: true`, since they are opened with their own calls.
+pub(crate) fn mark_plain_frame(frame: &mut serde_json::Value) -> Result<()> {
+    let payload = frame.get("payload").unwrap_or(&serde_json::Value::Null);
+    if is_sdk_sealed(payload) {
+        return Err(to_napi_err(
+            "relay payload is end-to-end encrypted (sent with relay_send_e2ee); read it with recv_relay_data_e2ee",
+        ));
+    }
+    let encrypted = is_encrypted(payload);
+    if let Some(frame) = frame.as_object_mut() {
+        frame.insert("encrypted".to_string(), encrypted.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,25 @@
+//! Symmetric ratchet for long-lived relay sessions.
+//!
+//! Each step replaces the shared key with
+//! `SHA-256("gann-relay-ratchet-v1" || session_id (16 bytes) || current_key)`. Both peers
+//! applying the same number of steps arrive at the same key without another key exchange,
+//! and since SHA-256 is one-way, a leaked key does not reveal the ones before it. Keys after
+//! it are exposed, as with any ratchet that has no fresh DH input.
+
+use napi::bindgen_prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::error;
+
+const LABEL: &[u8] = b"gann-relay-ratchet-v1";
+
+pub(crate) fn next_key(current_key: &[u8], session_id: uuid::Uuid) -> Result<[u8; 32]> {
+    let key: [u8; 32] = current_key
+        .try_into()
+        .map_err(|_| error::invalid_argument("current_key must be 32 bytes"))?;
+    let mut hasher = Sha256::new();
+    hasher.update(LABEL);
+    hasher.update(session_id.as_bytes());
+    hasher.update(key);
+    Ok(hasher.finalize().into())
+}
@@ -0,0 +1,170 @@
+//! Sequence numbers for relay payloads, for replay detection.
+//!
+//! With `seq` given, `encrypt_relay_payload` also takes the sealing peer's `sender` id and
+//! outputs `{ "seq": n, "sender": <id>, "sealed": <payload> }`, where `sealed` is the usual
+//! encrypted payload under the key
+//! `SHA-256("gann-relay-seq-v2" || key || u32 BE sender length || sender || u64 BE seq)` and
+//! `key` is the shared key after `relay_aad.rs`. Changing `seq` or `sender` then breaks
+//! authentication. `decrypt_relay_payload` accepts both forms without checking order;
+//! `RelayReplayGuard` additionally requires sequence numbers to strictly increase per session
+//! and sender, so replayed and reordered payloads are rejected.
+//!
+//! Both peers of a session share the key, so the sender id is what tells their payloads apart:
+//! a guard knows its owner's id and rejects payloads sealed under it, which is what a payload
+//! reflected back at its sender looks like. Any id both peers agree on works, such as each
+//! peer's E2EE public key.
+
+use std::collections::HashMap;
+
+use gann_sdk as rust;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use sha2::{Digest, Sha256};
+
+use crate::{error, relay_aad, to_napi_err};
+
+const LABEL: &[u8] = b"gann-relay-seq-v2";
+
+fn seq_key(key: &[u8; 32], sender: &str, seq: u64) -> Result<[u8; 32]> {
+    let sender_len = u32::try_from(sender.len()).map_err(|_| error::invalid_argument("sender too long"))?;
+    let mut hasher = Sha256::new();
+    hasher.update(LABEL);
+    hasher.update(key);
+    hasher.update(sender_len.to_be_bytes());
+    hasher.update(sender.as_bytes());
+    hasher.update(seq.to_be_bytes());
+    Ok(hasher.finalize().into())
+}
+
+/// The caller's `seq`, checked to be non-negative.
+pub(crate) fn parse_seq(seq: i64) -> Result<u64> {
+    u64::try_from(seq).map_err(|_| error::invalid_argument("seq must not be negative"))
+}
+
+/// The caller's `sender`, required alongside `seq`.
+pub(crate) fn parse_sender(sender: Option<String>) -> Result<String> {
+    sender.ok_or_else(|| error::invalid_argument("seq needs a sender"))
+}
+
+pub(crate) fn seal(
+    key: &[u8; 32],
+    session_id: uuid::Uuid,
+    sender: &str,
+    seq: u64,
+    plaintext: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    let sealed =
+        rust::encrypt_relay_payload(&seq_key(key, sender, seq)?, session_id, plaintext).map_err(to_napi_err)?;
+    Ok(serde_json::json!({
+        "seq": seq,
+        "sender": sender,
+        "sealed": serde_json::to_value(&sealed).map_err(to_napi_err)?,
+    }))
+}
+
+/// The sender and sequence number of a sequenced payload, or `None` for a plain one.
+fn sequence(payload: &serde_json::Value) -> Result<Option<(String, u64)>> {
+    let Some(seq) = payload.get("seq") else {
+        return Ok(None);
+    };
+    let seq = seq
+        .as_u64()
+        .ok_or_else(|| error::invalid_argument("seq must be a non-negative integer"))?;
+    let sender = payload
+        .get("sender")
+        .and_then(|sender| sender.as_str())
+        .ok_or_else(|| error::invalid_argument("sequenced payload has no sender"))?;
+    Ok(Some((sender.to_string(), seq)))
+}
+
+/// Decrypts either form, returning the sender and sequence number alongside the plaintext.
+pub(crate) fn open(
+    key: &[u8; 32],
+    session_id: uuid::Uuid,
+    payload: &serde_json::Value,
+) -> Result<(Option<(String, u64)>, serde_json::Value)> {
+    let Some((sender, seq)) = sequence(payload)? else {
+        let plaintext = rust::decrypt_relay_payload(key, session_id, payload).map_err(to_napi_err)?;
+        return Ok((None, serde_json::to_value(&plaintext).map_err(to_napi_err)?));
+    };
+    let sealed = payload
+        .get("sealed")
+        .ok_or_else(|| error::invalid_argument("missing sealed payload"))?;
+    let plaintext =
+        rust::decrypt_relay_payload(&seq_key(key, &sender, seq)?, session_id, sealed).map_err(to_napi_err)?;
+    Ok((
+        Some((sender, seq)),
+        serde_json::to_value(&plaintext).map_err(to_napi_err)?,
+    ))
+}
+
+/// Remembers the last sequence number accepted per session and sender. Keep one per receiver,
+/// created with the receiver's own sender id; state is in memory only, so a restarted receiver
+/// accepts old payloads again.
+#[napi]
+pub struct RelayReplayGuard {
+    own_sender: String,
+    last: std::sync::Mutex<HashMap<(uuid::Uuid, String), u64>>,
+}
+
+#[napi]
+impl RelayReplayGuard {
+    /// `own_sender` is the id this side seals its own sequenced payloads with.
+    #[napi(factory)]
+    pub fn create(own_sender: String) -> Self {
+        Self {
+            own_sender,
+            last: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `decrypt_relay_payload` for sequenced payloads that also rejects, with
+    /// `INVALID_ARGUMENT`, a `seq` not above the last one accepted for `session_id` and the
+    /// payload's sender, payloads sealed with this guard's own sender id (reflected back at
+    /// it), and payloads without a `seq`. Only authentic payloads advance a sequence.
+    #[napi]
+    pub fn decrypt(
+        &self,
+        shared_key: Buffer,
+        session_id: String,
+        payload_json: String,
+        aad: Option<Buffer>,
+    ) -> Result<String> {
+        let key = relay_aad::relay_key(&shared_key, aad.as_deref())?;
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        let payload: serde_json::Value = serde_json::from_str(&payload_json).map_err(to_napi_err)?;
+        let (sequence, plaintext) = open(&key, session_id, &payload)?;
+        let (sender, seq) = sequence.ok_or_else(|| error::invalid_argument("payload has no seq"))?;
+        if sender == self.own_sender {
+            return Err(error::invalid_argument(
+                "reflected payload: sealed with this guard's own sender",
+            ));
+        }
+
+        let mut last = self.last.lock().unwrap();
+        let key = (session_id, sender);
+        if let Some(&previous) = last.get(&key) {
+            if seq <= previous {
+                return Err(error::invalid_argument(format!(
+                    "replayed payload: seq {seq} is not above {previous}"
+                )));
+            }
+        }
+        last.insert(key, seq);
+        drop(last);
+        serde_json::to_string(&plaintext).map_err(to_napi_err)
+    }
+
+    /// Forgets `session_id`'s sequences, e.g. once the session ends.
+    #[napi]
+    pub fn forget(&self, session_id: String) -> Result<()> {
+        let session_id =
+            uuid::Uuid::parse_str(&session_id).map_err(|_| error::invalid_argument("invalid session_id"))?;
+        self.last
+            .lock()
+            .unwrap()
+            .retain(|(session, _), _| *session != session_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+//! Transport tuning accepted by the endpoint `create` factories.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use napi::bindgen_prelude::*;
+
+use crate::to_napi_err;
+use crate::{cidr, error};
+
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+const MIN_MTU: u16 = 1200;
+/// Default for `max_read_bytes`.
+pub(crate) const DEFAULT_MAX_READ_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TransportOptions {
+    /// Opt-in to the QUIC ACK frequency extension (draft-ietf-quic-ack-frequency). Only takes
+    /// effect when the peer supports it as well.
+    pub ack_frequency: Option<AckFrequencyOptions>,
+    /// Closes connections after this long without any packet from the peer; `0` disables the
+    /// timeout. Defaults to quinn's 30 s, raised to twice `keep_alive_interval_ms` if needed.
+    pub idle_timeout_ms: Option<u32>,
+    /// Sends a keep-alive when nothing else was sent for this long, keeping NAT bindings warm.
+    /// Must be below the idle timeout.
+    pub keep_alive_interval_ms: Option<u32>,
+    /// Drives the endpoint's UDP I/O and its connections' protocol state on one dedicated
+    /// thread instead of the shared multi-threaded runtime, for cache locality. Best-effort:
+    /// it covers quinn's internal tasks only; JS-facing calls still run on the shared runtime
+    /// and hand off to that thread. One thread per endpoint, shared by all its connections.
+    #[serde(default)]
+    pub dedicated_thread: bool,
+    /// `PeerServer` only: how many TLS handshakes may run at once. Further clients wait in
+    /// the endpoint's incoming queue, retransmitting their Initial, until a slot frees up.
+    /// Unlimited by default or with `0`.
+    pub max_concurrent_handshakes: Option<u32>,
+    /// ALPN protocol ids in preference order. A `PeerServer` accepts only these and offers the
+    /// first (default `gann-quic-p2p/1`); a `PeerClient` advertises these instead of the offer's
+    /// `alpn`. Without a common protocol the handshake fails with `HANDSHAKE_FAILED`.
+    pub alpn: Option<Vec<String>>,
+    /// `PeerClient` only: SHA-256 of the certificate servers must present, as hex (colons
+    /// allowed). Any other certificate fails the handshake, even one matching its offer.
+    pub pinned_cert_sha256: Option<String>,
+    /// Congestion controller for the endpoint's connections; quinn's default is Cubic.
+    pub congestion_controller: Option<CongestionController>,
+    /// UDP payload size to start with, at least 1200 (QUIC's minimum, and quinn's default).
+    pub initial_mtu: Option<u16>,
+    /// Largest UDP payload size MTU discovery probes for; quinn's default is 1452.
+    pub max_mtu: Option<u16>,
+    /// `false` stops probing for larger packets, for paths that black-hole them; packets then
+    /// stay at `initial_mtu`. On by default.
+    pub mtu_discovery: Option<bool>,
+    /// Bytes the peer may send on one stream ahead of our reads. The window, not the link,
+    /// caps a single stream's throughput at window / RTT, so high-BDP paths need it raised.
+    pub stream_receive_window: Option<u64>,
+    /// Same as `stream_receive_window`, across all streams of a connection.
+    pub receive_window: Option<u64>,
+    /// Bytes we keep unacknowledged across all streams of a connection.
+    pub send_window: Option<u64>,
+    /// Bidi streams the peer may have open at once on a connection; quinn's default is 100.
+    /// Beyond it the peer's `open_bi` waits for one to close, as QUIC flow control has it.
+    pub max_concurrent_bidi_streams: Option<u32>,
+    /// Same for uni streams. The shared reliable message stream counts as one, so `0` also
+    /// stops the peer's reliable `send`.
+    pub max_concurrent_uni_streams: Option<u32>,
+    /// `PeerServer` only: remote address ranges allowed to connect (see `cidr.rs`). Others
+    /// are refused before any handshake work and never reach `accept`. An empty list refuses
+    /// everyone; unset allows everyone.
+    pub allowed_cidrs: Option<Vec<String>>,
+    /// `PeerServer` only, default `true`. With `false`, packets from a client's new address
+    /// are dropped instead of migrating the connection, which then times out on the idle
+    /// timeout unless the client returns to its old address.
+    pub allow_migration: Option<bool>,
+    /// `PeerServer` only, default `false`. Lets resuming clients send 0-RTT data, which an
+    /// attacker can replay, so enable it only when every early request is idempotent. Without
+    /// it the session tickets handed out do not permit early data, so `connect_0rtt` does a
+    /// full handshake.
+    #[serde(default)]
+    pub accept_early_data: bool,
+    /// Upper bound on the `max_bytes` a stream read honors, default 16 MiB, so a caller
+    /// cannot be driven into one giant allocation. `read` and `read_all_available` clamp
+    /// larger requests to it; `read_exact` rejects them.
+    pub max_read_bytes: Option<u32>,
+    /// Bytes of incoming datagrams quinn buffers before dropping the oldest; quinn's default
+    /// is 1.25 MB. `0` refuses datagrams from the peer altogether.
+    pub datagram_receive_buffer_size: Option<u32>,
+}
+
+#[derive(Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CongestionController {
+    Cubic,
+    /// Model-based rather than loss-based, so it keeps long fat pipes full where Cubic backs
+    /// off on sporadic loss. quinn marks its implementation experimental.
+    Bbr,
+    NewReno,
+}
+
+/// Fewer, larger ACKs cut per-packet CPU at high packet rates, at the cost of slower loss
+/// detection and congestion feedback (so latency suffers under loss). There is no knob for
+/// the number of ranges per ACK: quinn fixes it at 64 internally and exposes no setting.
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AckFrequencyOptions {
+    /// Ack-eliciting packets the peer may receive before it must send an ACK.
+    pub ack_eliciting_threshold: Option<u32>,
+    /// Upper bound on how long the peer may delay an ACK.
+    pub max_ack_delay_ms: Option<u32>,
+    /// Packet reordering tolerated before the peer ACKs immediately.
+    pub reordering_threshold: Option<u32>,
+}
+
+impl TransportOptions {
+    pub fn transport_config(&self) -> Result<Arc<quinn::TransportConfig>> {
+        let mut config = quinn::TransportConfig::default();
+
+        if let Some(size) = self.datagram_receive_buffer_size {
+            config.datagram_receive_buffer_size((size > 0).then_some(size as usize));
+        }
+
+        if let Some(ack) = &self.ack_frequency {
+            let mut ack_config = quinn::AckFrequencyConfig::default();
+            if let Some(threshold) = ack.ack_eliciting_threshold {
+                ack_config.ack_eliciting_threshold(threshold.into());
+            }
+            if let Some(delay_ms) = ack.max_ack_delay_ms {
+                ack_config.max_ack_delay(Some(Duration::from_millis(delay_ms.into())));
+            }
+            if let Some(threshold) = ack.reordering_threshold {
+                ack_config.reordering_threshold(threshold.into());
+            }
+            config.ack_frequency_config(Some(ack_config));
+        }
+
+        let keep_alive = self
+            .keep_alive_interval_ms
+            .filter(|ms| *ms > 0)
+            .map(|ms| Duration::from_millis(ms.into()));
+        let idle_timeout = match (self.idle_timeout_ms, keep_alive) {
+            (Some(0), _) => None,
+            (Some(ms), _) => Some(Duration::from_millis(ms.into())),
+            (None, Some(keep_alive)) => Some(DEFAULT_IDLE_TIMEOUT.max(keep_alive * 2)),
+            (None, None) => Some(DEFAULT_IDLE_TIMEOUT),
+        };
+        if let (Some(keep_alive), Some(idle_timeout)) = (keep_alive, idle_timeout) {
+            if keep_alive >= idle_timeout {
+                return Err(error::invalid_argument(format!(
+                    "keepAliveIntervalMs ({}) must be below idleTimeoutMs ({})",
+                    keep_alive.as_millis(),
+                    idle_timeout.as_millis()
+                )));
+            }
+        }
+        let idle_timeout = idle_timeout
+            .map(quinn::IdleTimeout::try_from)
+            .transpose()
+            .map_err(to_napi_err)?;
+        config.max_idle_timeout(idle_timeout);
+        config.keep_alive_interval(keep_alive);
+
+        let var_int = |bytes: u64, name: &str| {
+            quinn::VarInt::from_u64(bytes).map_err(|_| error::invalid_argument(format!("{name} is too large")))
+        };
+        if let Some(bytes) = self.stream_receive_window {
+            config.stream_receive_window(var_int(bytes, "streamReceiveWindow")?);
+        }
+        if let Some(bytes) = self.receive_window {
+            config.receive_window(var_int(bytes, "receiveWindow")?);
+        }
+        if let Some(bytes) = self.send_window {
+            config.send_window(bytes);
+        }
+        if let Some(count) = self.max_concurrent_bidi_streams {
+            config.max_concurrent_bidi_streams(count.into());
+        }
+        if let Some(count) = self.max_concurrent_uni_streams {
+            config.max_concurrent_uni_streams(count.into());
+        }
+
+        let initial_mtu = self.initial_mtu.unwrap_or(MIN_MTU);
+        if initial_mtu < MIN_MTU {
+            return Err(error::invalid_argument(format!(
+                "initialMtu must be at least {MIN_MTU}"
+            )));
+        }
+        config.initial_mtu(initial_mtu);
+        if self.mtu_discovery == Some(false) {
+            config.mtu_discovery_config(None);
+        } else if let Some(max_mtu) = self.max_mtu {
+            if max_mtu < initial_mtu {
+                return Err(error::invalid_argument(format!(
+                    "maxMtu ({max_mtu}) must be at least initialMtu ({initial_mtu})"
+                )));
+            }
+            let mut discovery = quinn::MtuDiscoveryConfig::default();
+            discovery.upper_bound(max_mtu);
+            config.mtu_discovery_config(Some(discovery));
+        }
+
+        match self.congestion_controller {
+            None | Some(CongestionController::Cubic) => {}
+            Some(CongestionController::Bbr) => {
+                config.congestion_controller_factory(Arc::new(quinn::congestion::BbrConfig::default()));
+            }
+            Some(CongestionController::NewReno) => {
+                config.congestion_controller_factory(Arc::new(quinn::congestion::NewRenoConfig::default()));
+            }
+        }
+
+        Ok(Arc::new(config))
+    }
+
+    /// The `alpn` option, checked against the TLS limits: at least one id, each 1-255 bytes.
+    pub fn alpn(&self) -> Result<Option<Vec<String>>> {
+        let Some(alpn) = &self.alpn else {
+            return Ok(None);
+        };
+        if alpn.is_empty() {
+            return Err(error::invalid_argument("alpn must list at least one protocol"));
+        }
+        if let Some(id) = alpn.iter().find(|id| id.is_empty() || id.len() > 255) {
+            return Err(error::invalid_argument(format!(
+                "alpn protocol {id:?} must be 1-255 bytes"
+            )));
+        }
+        Ok(Some(alpn.clone()))
+    }
+
+    pub fn max_read_bytes(&self) -> Result<usize> {
+        match self.max_read_bytes {
+            Some(0) => Err(error::invalid_argument("maxReadBytes must be at least 1")),
+            Some(max) => Ok(max as usize),
+            None => Ok(DEFAULT_MAX_READ_BYTES),
+        }
+    }
+
+    pub fn allowlist(&self) -> Result<Option<cidr::Allowlist>> {
+        self.allowed_cidrs.as_deref().map(cidr::Allowlist::parse).transpose()
+    }
+
+    /// The `pinned_cert_sha256` option as 64 lowercase hex digits.
+    pub fn pinned_cert_sha256(&self) -> Result<Option<String>> {
+        let Some(pin) = &self.pinned_cert_sha256 else {
+            return Ok(None);
+        };
+        let pin = pin.replace(':', "").to_ascii_lowercase();
+        if pin.len() != 64 || !pin.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(error::invalid_argument("pinnedCertSha256 must be a hex SHA-256 digest"));
+        }
+        Ok(Some(pin))
+    }
+
+    pub fn runtime(&self) -> Result<Option<Arc<DedicatedRuntime>>> {
+        if !self.dedicated_thread {
+            return Ok(None);
+        }
+        DedicatedRuntime::start().map(|runtime| Some(Arc::new(runtime)))
+    }
+}
+
+/// A current-thread tokio runtime on its own OS thread. Endpoints created while it is entered
+/// spawn their driver tasks there. The thread exits once the last handle is dropped, so the
+/// endpoint and each of its connections hold one.
+pub(crate) struct DedicatedRuntime {
+    handle: tokio::runtime::Handle,
+    _stop: tokio::sync::oneshot::Sender<()>,
+}
+
+impl DedicatedRuntime {
+    fn start() -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(to_napi_err)?;
+        let handle = runtime.handle().clone();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        std::thread::Builder::new()
+            .name("gann-quic-endpoint".to_string())
+            .spawn(move || {
+                let _ = runtime.block_on(stopped);
+            })
+            .map_err(to_napi_err)?;
+        Ok(Self { handle, _stop: stop })
+    }
+
+    pub fn enter(&self) -> tokio::runtime::EnterGuard<'_> {
+        self.handle.enter()
+    }
+}
@@ -0,0 +1,152 @@
+//! Unidirectional streams.
+//!
+//! Every uni stream starts with a kind byte so the connection's uni streams can be shared:
+//! `0xE0` for application streams from `PeerConnection::open_uni`, `0xE3` for the message
+//! stream (see `messages.rs`). Streams of any other kind are dropped.
+
+use std::sync::Arc;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use tokio::sync::mpsc;
+
+use crate::error::{self, quic_err, Code};
+use crate::messages::{self, KIND_MESSAGE};
+use crate::{rate_limit, LiveStream, OpenSlot};
+
+pub(crate) const KIND_UNI: u8 = 0xE0;
+
+/// Routes the peer's uni streams by kind. Started lazily by the first `accept_uni` or `recv`
+/// and stops once the connection stops yielding streams.
+pub(crate) struct UniDemux {
+    app: tokio::sync::Mutex<mpsc::UnboundedReceiver<quinn::RecvStream>>,
+}
+
+impl UniDemux {
+    pub fn start(conn: quinn::Connection, messages_tx: mpsc::UnboundedSender<Vec<u8>>) -> Arc<Self> {
+        let (app_tx, app_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok(mut recv) = conn.accept_uni().await {
+                let app_tx = app_tx.clone();
+                let messages_tx = messages_tx.clone();
+                tokio::spawn(async move {
+                    let mut kind = [0u8; 1];
+                    if recv.read_exact(&mut kind).await.is_err() {
+                        return;
+                    }
+                    match kind[0] {
+                        KIND_UNI => {
+                            let _ = app_tx.send(recv);
+                        }
+                        KIND_MESSAGE => messages::forward_stream(recv, messages_tx).await,
+                        _ => {}
+                    }
+                });
+            }
+        });
+        Arc::new(Self {
+            app: tokio::sync::Mutex::new(app_rx),
+        })
+    }
+
+    /// The next application uni stream, or `None` once the connection is closed.
+    pub async fn accept(&self) -> Option<quinn::RecvStream> {
+        self.app.lock().await.recv().await
+    }
+}
+
+/// The write half of a uni stream, mirroring `BiStream`'s send side.
+#[napi]
+pub struct SendStream {
+    send: tokio::sync::Mutex<Option<quinn::SendStream>>,
+    rate_limit: Arc<rate_limit::RateLimit>,
+    slot: std::sync::Mutex<Option<OpenSlot>>,
+    live: std::sync::Mutex<Option<LiveStream>>,
+}
+
+impl SendStream {
+    pub(crate) fn new(
+        send: quinn::SendStream,
+        rate_limit: Arc<rate_limit::RateLimit>,
+        slot: OpenSlot,
+        live: LiveStream,
+    ) -> Self {
+        Self {
+            send: tokio::sync::Mutex::new(Some(send)),
+            rate_limit,
+            slot: std::sync::Mutex::new(Some(slot)),
+            live: std::sync::Mutex::new(Some(live)),
+        }
+    }
+}
+
+#[napi]
+impl SendStream {
+    /// An empty `data` is a no-op; writing to a finished stream errors.
+    #[napi]
+    pub async fn write(&self, data: Buffer) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some(send) = guard.as_mut() else {
+            return Err(error::coded(Code::StreamClosed, "send stream closed"));
+        };
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.rate_limit.acquire(data.len()).await;
+        send.write_all(&data).await.map_err(quic_err)
+    }
+
+    #[napi]
+    pub async fn finish(&self) -> Result<()> {
+        let mut guard = self.send.lock().await;
+        let Some(mut send) = guard.take() else {
+            return Ok(());
+        };
+        self.slot.lock().unwrap().take();
+        self.live.lock().unwrap().take();
+        send.finish().map_err(quic_err)
+    }
+}
+
+/// The read half of a uni stream, mirroring `BiStream`'s receive side.
+#[napi]
+pub struct RecvStream {
+    recv: tokio::sync::Mutex<Option<quinn::RecvStream>>,
+    max_read_bytes: usize,
+    live: std::sync::Mutex<Option<LiveStream>>,
+}
+
+impl RecvStream {
+    pub(crate) fn new(recv: quinn::RecvStream, max_read_bytes: usize, live: LiveStream) -> Self {
+        Self {
+            recv: tokio::sync::Mutex::new(Some(recv)),
+            max_read_bytes,
+            live: std::sync::Mutex::new(Some(live)),
+        }
+    }
+}
+
+#[napi]
+impl RecvStream {
+    /// Same contract as `BiStream::read`: `max_bytes: 0` resolves with an empty buffer, and
+    /// `null` means the peer finished the stream.
+    #[napi]
+    pub async fn read(&self, max_bytes: Option<u32>) -> Result<Option<Buffer>> {
+        let mut guard = self.recv.lock().await;
+        let Some(recv) = guard.as_mut() else {
+            return Ok(None);
+        };
+
+        let max = (max_bytes.unwrap_or(64 * 1024) as usize).min(self.max_read_bytes);
+        if max == 0 {
+            return Ok(Some(Buffer::from(Vec::new())));
+        }
+        let Some(chunk) = recv.read_chunk(max, true).await.map_err(quic_err)? else {
+            // FIN
+            *guard = None;
+            self.live.lock().unwrap().take();
+            return Ok(None);
+        };
+        Ok(Some(Buffer::from(chunk.bytes.to_vec())))
+    }
+}
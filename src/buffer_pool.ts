@@ -0,0 +1,98 @@
+/**
+ * Fixed set of reusable receive buffers, so a hot read path does not allocate per read.
+ * Buffers are handed out as `PooledBuffer` handles that must be released back.
+ */
+export class BufferPool {
+  readonly bufferSize: number;
+  private readonly free: Buffer[] = [];
+  private readonly generations = new Map<Buffer, number>();
+  private readonly waiters: Array<(buffer: Buffer) => void> = [];
+
+  private constructor(bufferSize: number, count: number) {
+    this.bufferSize = bufferSize;
+    for (let i = 0; i < count; i++) {
+      const buffer = Buffer.allocUnsafe(bufferSize);
+      this.free.push(buffer);
+      this.generations.set(buffer, 0);
+    }
+  }
+
+  static create(bufferSize: number, count: number): BufferPool {
+    if (!Number.isInteger(bufferSize) || bufferSize <= 0) {
+      throw new Error("bufferSize must be a positive integer");
+    }
+    if (!Number.isInteger(count) || count <= 0) {
+      throw new Error("count must be a positive integer");
+    }
+    return new BufferPool(bufferSize, count);
+  }
+
+  available(): number {
+    return this.free.length;
+  }
+
+  /** Resolves with a free buffer, waiting for a release when all are in use. */
+  async acquire(): Promise<PooledBuffer> {
+    const buffer = this.free.pop() ?? (await new Promise<Buffer>((resolve) => this.waiters.push(resolve)));
+    return new PooledBuffer(this, buffer, this.generations.get(buffer)!);
+  }
+
+  /** @internal */
+  isCurrent(buffer: Buffer, generation: number): boolean {
+    return this.generations.get(buffer) === generation;
+  }
+
+  /** @internal Bumps the generation so stale handles fail instead of seeing reused data. */
+  release(buffer: Buffer, generation: number): void {
+    if (!this.isCurrent(buffer, generation)) {
+      throw new Error("pooled buffer already released");
+    }
+    this.generations.set(buffer, generation + 1);
+    const waiter = this.waiters.shift();
+    if (waiter) {
+      waiter(buffer);
+    } else {
+      this.free.push(buffer);
+    }
+  }
+}
+
+/**
+ * A buffer on loan from a `BufferPool`. `data` holds the valid bytes; using it after
+ * `release()` throws, because the pool may already have handed the memory to another read.
+ */
+export class PooledBuffer {
+  private readonly pool: BufferPool;
+  private readonly buffer: Buffer;
+  private readonly generation: number;
+  private length: number;
+
+  /** @internal */
+  constructor(pool: BufferPool, buffer: Buffer, generation: number) {
+    this.pool = pool;
+    this.buffer = buffer;
+    this.generation = generation;
+    this.length = buffer.length;
+  }
+
+  get data(): Buffer {
+    if (!this.pool.isCurrent(this.buffer, this.generation)) {
+      throw new Error("pooled buffer used after release");
+    }
+    return this.buffer.subarray(0, this.length);
+  }
+
+  /** @internal Full backing buffer, for filling before `data` is trimmed. */
+  get backing(): Buffer {
+    return this.buffer;
+  }
+
+  /** @internal */
+  setLength(length: number): void {
+    this.length = length;
+  }
+
+  release(): void {
+    this.pool.release(this.buffer, this.generation);
+  }
+}
@@ -14,8 +14,12 @@ import {
   type QuicDirectFirstResult,
 } from "./quic_session.js";
 
+export * from "./buffer_pool.js";
+export * from "./node_stream.js";
 export * from "./quic.js";
 export * from "./quic_session.js";
+export * from "./relay_reconnect.js";
+export * from "./rpc.js";
 
 export type CapabilityDescriptor = {
   name: string;
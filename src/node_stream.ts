@@ -0,0 +1,77 @@
+import { Readable, Writable } from "node:stream";
+
+/** Receive half accepted by `toReadable`: a `QuicBiStream` or `QuicRecvStream`. */
+export type QuicReadableSource = {
+  read(maxBytes?: number): Promise<Buffer | null>;
+  stop?(errorCode: number): Promise<void>;
+};
+
+/** Send half accepted by `toWritable`: a `QuicBiStream` or `QuicSendStream`. */
+export type QuicWritableSink = {
+  write(data: Buffer): Promise<void>;
+  finish(): Promise<void>;
+  reset?(errorCode: number): Promise<void>;
+};
+
+export type QuicNodeStreamOptions = {
+  /** Bytes Node buffers before applying backpressure; Node's default when omitted. */
+  highWaterMark?: number;
+  /** Code for `stop()`/`reset()` when the Node stream is destroyed with an error. Default `0`. */
+  errorCode?: number;
+};
+
+/**
+ * Wraps a receive half as a `stream.Readable`. A QUIC read is only issued when Node asks for
+ * more data, so a slow consumer leaves bytes in QUIC's flow-control window and the peer stalls.
+ * FIN ends the Readable; destroying it with an error stops the QUIC stream where supported.
+ */
+export function toReadable(source: QuicReadableSource, options: QuicNodeStreamOptions = {}): Readable {
+  let reading = false;
+  return new Readable({
+    highWaterMark: options.highWaterMark,
+    read(size) {
+      if (reading) {
+        return;
+      }
+      reading = true;
+      source.read(size).then(
+        (chunk) => {
+          reading = false;
+          this.push(chunk);
+        },
+        (err) => this.destroy(err instanceof Error ? err : new Error(String(err))),
+      );
+    },
+    destroy(err, callback) {
+      if (err && source.stop) {
+        source.stop(options.errorCode ?? 0).then(() => callback(err), () => callback(err));
+        return;
+      }
+      callback(err);
+    },
+  });
+}
+
+/**
+ * Wraps a send half as a `stream.Writable`. Each chunk's callback waits for the QUIC write to
+ * resolve, so Node's buffering (and `write()` returning `false`) tracks QUIC flow control.
+ * `end()` finishes the stream; destroying it with an error resets it where supported.
+ */
+export function toWritable(sink: QuicWritableSink, options: QuicNodeStreamOptions = {}): Writable {
+  return new Writable({
+    highWaterMark: options.highWaterMark,
+    write(chunk: Buffer, _encoding, callback) {
+      sink.write(chunk).then(() => callback(), callback);
+    },
+    final(callback) {
+      sink.finish().then(() => callback(), callback);
+    },
+    destroy(err, callback) {
+      if (err && sink.reset) {
+        sink.reset(options.errorCode ?? 0).then(() => callback(err), () => callback(err));
+        return;
+      }
+      callback(err);
+    },
+  });
+}
@@ -1,8 +1,13 @@
 import { createRequire } from "node:module";
 import { fileURLToPath } from "node:url";
 import path from "node:path";
+import type { BufferPool, PooledBuffer } from "./buffer_pool.js";
 
 export type QuicOffer = {
+  /**
+   * `ip:port` or `host:port`. Hostnames are resolved when connecting and every address they
+   * resolve to is tried; `offerToBytes` only accepts numeric addresses.
+   */
   candidates: string[];
   cert_der_b64: string;
   fingerprint_sha256: string;
@@ -24,21 +29,376 @@ export type QuicRelayInfo = {
   server_name?: string | null;
 };
 
+export type QuicRelayBindOptions = {
+  /** Bound on each bind attempt; without it a bind waits as long as the relay takes. */
+  timeoutMs?: number;
+  /**
+   * Attempts after a timed-out one (default 0), spaced 100 ms apart and doubling up to 2 s.
+   * Only used with `timeoutMs`; relay errors are never retried.
+   */
+  retries?: number;
+};
+
+/**
+ * `peer_ready` is what `relayBind()` would resolve with; `error` if this session failed to bind,
+ * with `code` when the cause is known, e.g. `"SESSION_LIMIT"`.
+ */
+export type QuicRelayBindResult =
+  | { session_id: string; peer_ready: boolean }
+  | { session_id: string; error: string; code?: QuicErrorCode };
+
 export type QuicRelayDataFrame = {
   session_id: string;
   from: string;
   to: string;
   payload: unknown;
+  /**
+   * `true` from `recvRelayDataE2ee`, which rejects plaintext frames, and from `recvRelayData`
+   * for payloads meant for `E2eeKeyPair.openForwardSecret` or `RelayReplayGuard`.
+   */
+  encrypted: boolean;
+};
+
+export type QuicRelayBytesFrame = {
+  session_id: string;
+  from: string;
+  to: string;
+  payload: Buffer;
+};
+
+/**
+ * QUIC ACK frequency extension tuning. Fewer, larger ACKs save CPU at very high packet rates but
+ * delay loss detection and congestion feedback. The extension is still a draft and only applies
+ * when both endpoints support it, so it stays off unless configured.
+ */
+export type QuicAckFrequencyOptions = {
+  /** Ack-eliciting packets the peer may receive before it must send an ACK. */
+  ackElicitingThreshold?: number;
+  /** Upper bound on how long the peer may delay an ACK. */
+  maxAckDelayMs?: number;
+  /** Packet reordering tolerated before the peer ACKs immediately. */
+  reorderingThreshold?: number;
+};
+
+export type QuicTransportOptions = {
+  /**
+   * Opt-in to the QUIC ACK frequency extension, used only when the peer supports it too.
+   * Fewer, larger ACKs save CPU at high packet rates but slow loss detection, so latency
+   * suffers under loss. The number of ranges per ACK is not tunable: quinn fixes it at 64.
+   */
+  ackFrequency?: QuicAckFrequencyOptions;
+  /** Idle timeout; `0` disables it. Defaults to 30 s, or twice `keepAliveIntervalMs` if larger. */
+  idleTimeoutMs?: number;
+  /** Keep-alive interval, to stop NATs from dropping quiet connections; must be below the idle timeout. */
+  keepAliveIntervalMs?: number;
+  /**
+   * Run the endpoint's QUIC I/O on one dedicated native thread shared by its connections,
+   * for cache locality. Best-effort: JS-facing calls still hop through the shared runtime.
+   */
+  dedicatedThread?: boolean;
+  /**
+   * Server only: TLS handshakes allowed to run at once, to bound CPU under connect storms.
+   * Excess clients wait until a slot frees up. Unlimited by default or with `0`.
+   */
+  maxConcurrentHandshakes?: number;
+  /**
+   * ALPN protocol ids, most preferred first. Servers accept only these and offer the first
+   * (default `"gann-quic-p2p/1"`, as in the Rust SDK); clients advertise these instead of the
+   * offer's `alpn`. Without a common protocol, `connect` rejects with code `"HANDSHAKE_FAILED"`.
+   */
+  alpn?: string[];
+  /**
+   * Client only: hex SHA-256 of the only server certificate to trust (colons allowed). Any
+   * other certificate, even one matching the offer, fails the handshake with `"HANDSHAKE_FAILED"`.
+   */
+  pinnedCertSha256?: string;
+  /**
+   * Congestion controller for the endpoint's connections. `"bbr"` can fill high-bandwidth,
+   * high-latency paths that Cubic underuses, but quinn's BBR is experimental. Default `"cubic"`.
+   */
+  congestionController?: QuicCongestionController;
+  /** UDP payload size to start with; at least and by default 1200. */
+  initialMtu?: number;
+  /** Largest UDP payload size MTU discovery probes for. Default 1452. */
+  maxMtu?: number;
+  /**
+   * `false` disables MTU discovery so packets stay at `initialMtu`, for paths (some VPNs) that
+   * silently drop larger packets. Default `true`.
+   */
+  mtuDiscovery?: boolean;
+  /**
+   * Bytes the peer may send on one stream before we read. A stream tops out at window / RTT,
+   * so raise it (with `receiveWindow`) to fill high-bandwidth, high-latency links.
+   */
+  streamReceiveWindow?: number;
+  /** Like `streamReceiveWindow`, across all streams of a connection. */
+  receiveWindow?: number;
+  /** Unacknowledged bytes we keep in flight across all streams of a connection. */
+  sendWindow?: number;
+  /**
+   * Bidi streams the peer may have open at once per connection (quinn default 100). Extra
+   * `openBi()` calls on the peer wait until one of its streams closes.
+   */
+  maxConcurrentBidiStreams?: number;
+  /** Same for uni streams; the reliable message stream of `send()` counts as one. */
+  maxConcurrentUniStreams?: number;
+  /**
+   * `PeerServer` only: CIDRs (`10.0.0.0/8`, `2001:db8::/32`, or a bare address) allowed to
+   * connect. Other clients are refused before the handshake and never reach `accept()`.
+   * An empty list refuses everyone.
+   */
+  allowedCidrs?: string[];
+  /**
+   * `PeerServer` only, default `true`. With `false` a client whose address changes is not
+   * followed: its packets from the new address are dropped and the connection closes on
+   * the idle timeout. Migration is the server's decision in QUIC, so clients have no switch.
+   */
+  allowMigration?: boolean;
+  /**
+   * `PeerServer` only, default `false`. Accepts 0-RTT data from clients resuming with
+   * `connect0Rtt()`. Early data can be replayed, so enable it only when every request a
+   * client may send early is idempotent. Without it, `connect0Rtt()` does a full handshake.
+   */
+  acceptEarlyData?: boolean;
+  /**
+   * Largest `maxBytes` a stream read honors, default 16 MiB. `read()` and
+   * `readAllAvailable()` clamp bigger requests; `readExact()` rejects them with
+   * `INVALID_ARGUMENT`.
+   */
+  maxReadBytes?: number;
+  /**
+   * Bytes of received datagrams buffered before the oldest are dropped (default 1.25 MB);
+   * `0` disables receiving datagrams. See `datagramsDropped()` for observing loss.
+   */
+  datagramReceiveBufferSize?: number;
+};
+
+export type QuicCongestionController = "cubic" | "bbr" | "newreno";
+
+export type QuicZeroRttConnection = {
+  connection: QuicPeerConnection;
+  /** Whether `connection` was handed out before the handshake, so data sent now is 0-RTT. */
+  earlyData: boolean;
+  /** `true` once the server accepted the early data; `false` if it was rejected or not sent. */
+  accepted: Promise<boolean>;
+};
+
+export type QuicCwndSample = {
+  /** Milliseconds since sampling started. */
+  t: number;
+  cwnd: number;
+  /** Smoothed RTT in milliseconds. */
+  rtt: number;
+  congestion_events: number;
+};
+
+export type QuicHandshakeInfo = {
+  side: "client" | "server";
+  alpn: string | null;
+  /** The name connected with on the client side; the SNI received on the server side. */
+  server_name: string | null;
+};
+
+/** What `QuicPendingConnection.clientHello()` learns from the ClientHello. */
+export type QuicClientHello = {
+  /** The SNI the client sent. */
+  server_name: string | null;
+  /** The protocol this server chose from the client's ALPN offer. */
+  alpn: string | null;
+};
+
+export type QuicConnectionSummary = {
+  duration_ms: number;
+  /** UDP payload bytes, including QUIC overhead. */
+  bytes_sent: number;
+  bytes_received: number;
+  streams_opened: number;
+  streams_accepted: number;
+  /** Latest smoothed RTT estimate. */
+  rtt_ms: number;
+  lost_packets: number;
+  migration_count: number;
+  close_reason: string | null;
+};
+
+/** Why a connection ended, as resolved by `QuicPeerConnection.closed()`. */
+export type QuicCloseReason =
+  /** Idle timeout; there is no application reason. */
+  | { kind: "timed_out" }
+  /** Closed on this side, by `close()` or by dropping the connection. */
+  | { kind: "locally_closed" }
+  /** The peer closed with an application code. */
+  | { kind: "application_closed"; code: number; reason: string }
+  /** The peer reported a transport error. */
+  | { kind: "connection_closed"; code: number; reason: string }
+  /** A transport error detected on this side. */
+  | { kind: "transport_error"; code: number; reason: string }
+  | { kind: "reset" }
+  | { kind: "version_mismatch" }
+  | { kind: "cids_exhausted" };
+
+export type QuicDelayStats = { min: number; avg: number; max: number };
+
+export type QuicOwdResult = {
+  sent: number;
+  received: number;
+  rtt_ms: QuicDelayStats | null;
+  /** Includes the offset between the two wall clocks. */
+  forward_ms: QuicDelayStats | null;
+  /** Includes the offset between the two wall clocks. */
+  reverse_ms: QuicDelayStats | null;
+  /** Peer clock minus local clock, estimated from the lowest-RTT sample. */
+  clock_offset_ms: number | null;
+};
+
+export type QuicConnectionStats = {
+  rtt_ms: number;
+  cwnd: number;
+  /** UDP payload bytes, including QUIC overhead. */
+  bytes_sent: number;
+  bytes_recv: number;
+  packets_sent: number;
+  packets_lost: number;
+  congestion_events: number;
+  path_mtu: number;
+};
+
+export type QuicOpenStreamCounts = {
+  bidi: number;
+  uni: number;
+};
+
+export type QuicLossStats = {
+  sent_packets: number;
+  lost_packets: number;
+  lost_bytes: number;
+  congestion_events: number;
+  black_holes_detected: number;
+};
+
+export type QuicConnectionEvent = {
+  /** Milliseconds since the event log was enabled. */
+  t: number;
+  kind: string;
+  detail: string;
+};
+
+export type QuicCryptoProvider = "ring" | "aws-lc";
+
+export type QuicCryptoInfo = {
+  /** `"ring"`/`"aws-lc"`, or `"unknown"` if the provider was installed outside this module. */
+  provider: string;
+  fips: boolean;
+  cipher_suites: string[];
+  kx_groups: string[];
+};
+
+export type QuicSendOptions = {
+  /** Defaults to `true`. Unreliable messages may be lost or arrive out of order. */
+  reliable?: boolean;
+};
+
+export type QuicDatagramFlowStats = {
+  delivered: number;
+  /** Datagrams that arrived ahead of a gap and waited in the reorder window. */
+  reordered: number;
+  /** Datagrams that arrived after later ones had already been delivered. */
+  dropped_late: number;
+  /** Sequence numbers given up on after the reorder window or timeout. */
+  lost: number;
+};
+
+export type QuicCompressionCodec = "zstd" | "gzip";
+
+/** What happens to a datagram sent while the capped datagram queue is full. */
+export type QuicDatagramOverflow = "error" | "drop_newest" | "drop_oldest";
+
+/** What a stream does if garbage-collected with a half still open. */
+export type QuicStreamDropBehavior = "finish" | "reset";
+
+export type QuicCandidatePreference = "ipv6" | "ipv4" | "race";
+
+export type QuicConnectOptions = {
+  /** Try candidates of one address family before the other; `race` (default) attempts them all together. */
+  candidatePreference?: QuicCandidatePreference;
+  /**
+   * Rejects with `TIMED_OUT` if no candidate completes the handshake within this many
+   * milliseconds, abandoning the attempts in flight. Without it, an unreachable peer only
+   * fails once the idle timeout (`idleTimeoutMs`) expires.
+   */
+  handshakeTimeoutMs?: number;
+};
+
+/**
+ * Set as `err.code` on errors from the native module when it knows the cause; other errors
+ * keep napi's status code (usually `"GenericFailure"`).
+ */
+export type QuicErrorCode =
+  | "CONNECTION_LOST"
+  | "TIMED_OUT"
+  | "CLOSED"
+  | "HANDSHAKE_FAILED"
+  | "STREAM_RESET"
+  | "STREAM_STOPPED"
+  | "STREAM_CLOSED"
+  | "QUEUE_FULL"
+  | "SESSION_LIMIT"
+  | "INVALID_ARGUMENT"
+  | "CANCELLED"
+  | "NOT_ENCRYPTED";
+
+export type QuicAbortOptions = {
+  /** Cancels the call when aborted. */
+  signal?: AbortSignal;
+};
+
+export type QuicRelayTransportOptions = {
+  /** Cap on sessions bound through one transport; binds beyond it reject with code `"SESSION_LIMIT"`. */
+  maxSessions?: number;
+  /** Rejects with `TIMED_OUT` if connecting to the relay takes longer than this many milliseconds. */
+  handshakeTimeoutMs?: number;
+};
+
+export type QuicRelaySendOptions = {
+  /**
+   * zstd-compresses payloads whose JSON is at least 1 KiB, when that makes them smaller;
+   * receivers expand them transparently. With E2EE, compression happens before encryption.
+   */
+  compress?: boolean;
 };
 
 type Native = {
-  PeerServer: { create(bindAddr: string): unknown };
-  PeerClient: { create(bindAddr: string): unknown };
+  PeerServer: { create(bindAddr: string, optionsJson?: string): unknown };
+  PeerClient: { create(bindAddr: string, optionsJson?: string): unknown };
   RelayClient: { create(bindAddr: string): unknown };
+  RelayReplayGuard: { create(ownSender: string): unknown };
   E2eeKeyPairHandle?: { generate(): unknown };
   E2EeKeyPairHandle?: { generate(): unknown };
-  encrypt_relay_payload(sharedKey: Buffer, sessionId: string, plaintextJson: string): string;
-  decrypt_relay_payload(sharedKey: Buffer, sessionId: string, payloadJson: string): string;
+  encrypt_relay_payload(
+    sharedKey: Buffer,
+    sessionId: string,
+    plaintextJson: string,
+    aad?: Buffer,
+    seq?: number,
+    sender?: string,
+  ): string;
+  decrypt_relay_payload(sharedKey: Buffer, sessionId: string, payloadJson: string, aad?: Buffer): string;
+  encrypt_relay_payload_async(
+    sharedKey: Buffer,
+    sessionId: string,
+    plaintextJson: string,
+    aad?: Buffer,
+    seq?: number,
+    sender?: string,
+  ): Promise<string>;
+  decrypt_relay_payload_async(sharedKey: Buffer, sessionId: string, payloadJson: string, aad?: Buffer): Promise<string>;
+  verify_key_binding(certDer: Buffer, e2eePublicB64: string, proof: Buffer): boolean;
+  crypto_info(): string;
+  init_crypto(provider: QuicCryptoProvider): void;
+  validate_socket_addr(value: string): string;
+  derive_session_id(myPublicB64: string, peerPublicB64: string): string;
+  offer_to_bytes(offerJson: string): Buffer;
+  offer_from_bytes(bytes: Buffer): string;
 };
 
 function resolveMethod(target: any, names: string[]): any {
@@ -51,6 +411,76 @@ function resolveMethod(target: any, names: string[]): any {
   throw new Error(`Native method not found. Tried: ${names.join(", ")}`);
 }
 
+// The native module prefixes coded error messages with the code ("TIMED_OUT: ...") since napi
+// errors carry no custom code; this moves the prefix to `err.code`.
+function liftErrorCode(err: unknown): unknown {
+  if (err instanceof Error) {
+    const [code, message] = splitErrorCode(err.message);
+    if (code) {
+      (err as Error & { code?: string }).code = code;
+      err.message = message;
+    }
+  }
+  return err;
+}
+
+function splitErrorCode(message: string): [string | null, string] {
+  const match = message.match(/^([A-Z][A-Z_]+): ([\s\S]*)$/);
+  return match ? [match[1], match[2]] : [null, message];
+}
+
+function codedFunction(fn: Function, owner: unknown): any {
+  return new Proxy(fn, {
+    apply(_fn, _this, args) {
+      let result: unknown;
+      try {
+        result = Reflect.apply(fn, owner, args);
+      } catch (err) {
+        throw liftErrorCode(err);
+      }
+      if (result instanceof Promise) {
+        return result.catch((err) => {
+          throw liftErrorCode(err);
+        });
+      }
+      return result;
+    },
+    // Static methods, e.g. `PeerServer.create`.
+    get(target, key) {
+      const value = Reflect.get(target, key);
+      return typeof value === "function" ? codedFunction(value, target) : value;
+    },
+  });
+}
+
+/** Wraps a native module or handle so its methods reject with `err.code` set. */
+function withErrorCodes<T>(native: T): T {
+  if (native === null || typeof native !== "object") {
+    return native;
+  }
+  return new Proxy(native as object, {
+    get(target, key) {
+      const value = Reflect.get(target, key);
+      return typeof value === "function" ? codedFunction(value, target) : value;
+    },
+  }) as T;
+}
+
+/** Runs `op`, calling the native `cancel` if `signal` aborts before it settles. */
+async function withAbort<T>(signal: AbortSignal | undefined, cancel: () => void, op: () => Promise<T>): Promise<T> {
+  if (!signal) {
+    return op();
+  }
+  signal.throwIfAborted();
+  const onAbort = () => cancel();
+  signal.addEventListener("abort", onAbort, { once: true });
+  try {
+    return await op();
+  } finally {
+    signal.removeEventListener("abort", onAbort);
+  }
+}
+
 function loadNative(): Native {
   const envPath = process?.env?.GANN_JS_QUIC_NATIVE_PATH || process?.env?.GANN_QUIC_NATIVE_PATH;
   const require = createRequire(import.meta.url);
@@ -58,7 +488,7 @@ function loadNative(): Native {
   // Preferred path (production): prebuilt napi-rs package.
   try {
     // eslint-disable-next-line @typescript-eslint/no-var-requires
-    return require("@soika/gann-sdk-quic-native") as Native;
+    return withErrorCodes(require("@soika/gann-sdk-quic-native") as Native);
   } catch {
     // fall back to local development paths
   }
@@ -72,7 +502,7 @@ function loadNative(): Native {
   for (const candidate of candidates) {
     try {
       // eslint-disable-next-line @typescript-eslint/no-var-requires
-      return require(candidate) as Native;
+      return withErrorCodes(require(candidate) as Native);
     } catch {
       // continue
     }
@@ -143,12 +573,12 @@ export class QuicPeerServer {
   private readonly native: any;
 
   private constructor(native: any) {
-    this.native = native;
+    this.native = withErrorCodes(native);
   }
 
-  static create(bindAddr: string): QuicPeerServer {
+  static create(bindAddr: string, options?: QuicTransportOptions): QuicPeerServer {
     const native = loadNative();
-    return new QuicPeerServer((native.PeerServer as any).create(bindAddr));
+    return new QuicPeerServer((native.PeerServer as any).create(bindAddr, options ? stringifyJson(options) : undefined));
   }
 
   offer(advertisedCandidates?: string[]): QuicOffer {
@@ -157,9 +587,80 @@ export class QuicPeerServer {
     return normalizeOfferCandidates(parseJson<QuicOffer>(raw));
   }
 
-  async accept(): Promise<QuicPeerConnection> {
+  /** The bound address, including the OS-assigned port when created with port `0`. */
+  localAddress(): string {
+    const localAddress = resolveMethod(this.native, ["local_address", "localAddress"]);
+    return localAddress();
+  }
+
+  /** Signs an E2EE public key with this server's certificate key; see `verifyKeyBinding`. */
+  keyBindingProof(e2eePublicB64: string): Buffer {
+    const keyBindingProof = resolveMethod(this.native, ["key_binding_proof", "keyBindingProof"]);
+    return keyBindingProof(e2eePublicB64);
+  }
+
+  /**
+   * Resolves `null` once the server is closed, including for a pending call. Rejects with
+   * "server draining" once `enterDrainMode()` has been called.
+   *
+   * Concurrent calls are safe and fan out: each one gets a different connection and runs
+   * its handshake independently, so a pool of accept workers handles bursts in parallel.
+   */
+  async accept(): Promise<QuicPeerConnection | null> {
     const conn = await this.native.accept();
-    return new QuicPeerConnection(conn);
+    return conn == null ? null : new QuicPeerConnection(conn);
+  }
+
+  /**
+   * Like `accept()`, but also resolves `null` when no client arrives within `timeoutMs`. A
+   * handshake already underway at the deadline still completes and its connection is returned.
+   */
+  async acceptWithTimeout(timeoutMs: number): Promise<QuicPeerConnection | null> {
+    const acceptWithTimeout = resolveMethod(this.native, ["accept_with_timeout", "acceptWithTimeout"]);
+    const conn = await acceptWithTimeout(timeoutMs);
+    return conn == null ? null : new QuicPeerConnection(conn);
+  }
+
+  /**
+   * Two-phase accept: resolves when a client's first packet arrives, before the handshake,
+   * so admission control can `accept()` or `reject()` it from its remote address.
+   */
+  async acceptPending(): Promise<QuicPendingConnection> {
+    const acceptPending = resolveMethod(this.native, ["accept_pending", "acceptPending"]);
+    return new QuicPendingConnection(await acceptPending());
+  }
+
+  /**
+   * Refuses new connections while existing ones keep working, for rolling deploys. Wait for
+   * `openConnections()` to reach 0, then shut down.
+   */
+  enterDrainMode(): void {
+    const enterDrainMode = resolveMethod(this.native, ["enter_drain_mode", "enterDrainMode"]);
+    enterDrainMode();
+  }
+
+  /**
+   * Graceful shutdown for deploys: enters drain mode, waits up to `timeoutMs` for existing
+   * connections to close, then closes the rest with code 0 and reason "server drained".
+   */
+  async drain(timeoutMs: number): Promise<void> {
+    await this.native.drain(timeoutMs);
+  }
+
+  isDraining(): boolean {
+    const isDraining = resolveMethod(this.native, ["is_draining", "isDraining"]);
+    return isDraining();
+  }
+
+  /** Handshakes running now, excluding clients queued by `maxConcurrentHandshakes`. */
+  handshakesInProgress(): number {
+    const handshakesInProgress = resolveMethod(this.native, ["handshakes_in_progress", "handshakesInProgress"]);
+    return handshakesInProgress();
+  }
+
+  openConnections(): number {
+    const openConnections = resolveMethod(this.native, ["open_connections", "openConnections"]);
+    return openConnections();
   }
 
   close(errorCode = 0, reason?: string): void {
@@ -167,58 +668,878 @@ export class QuicPeerServer {
   }
 }
 
+/**
+ * A connection attempt before its handshake: only the remote address is known, and it may be
+ * spoofed unless `remoteAddressValidated()`, until `clientHello()` starts the handshake.
+ * Dropping it ignores the attempt.
+ */
+export class QuicPendingConnection {
+  private readonly native: any;
+
+  constructor(native: any) {
+    this.native = withErrorCodes(native);
+  }
+
+  remoteAddress(): string {
+    const remoteAddress = resolveMethod(this.native, ["remote_address", "remoteAddress"]);
+    return remoteAddress();
+  }
+
+  remoteAddressValidated(): boolean {
+    const remoteAddressValidated = resolveMethod(this.native, ["remote_address_validated", "remoteAddressValidated"]);
+    return remoteAddressValidated();
+  }
+
+  /** Always `null` today: peer endpoints do not use client certificates. */
+  peerCertificates(): Buffer[] | null {
+    const peerCertificates = resolveMethod(this.native, ["peer_certificates", "peerCertificates"]);
+    return peerCertificates() ?? null;
+  }
+
+  /**
+   * Starts the handshake and resolves with the client's SNI and the ALPN protocol chosen from
+   * its offer as soon as the ClientHello is processed, before the handshake completes, for
+   * SNI-based routing before `accept()` or `reject()`. The raw ClientHello and its other
+   * extensions are not available, so JA3-style fingerprinting is not possible. Takes a
+   * `maxConcurrentHandshakes` slot, waiting for one if needed, until `accept()` or `reject()`.
+   */
+  async clientHello(): Promise<QuicClientHello> {
+    const clientHello = resolveMethod(this.native, ["client_hello", "clientHello"]);
+    return parseJson<QuicClientHello>(await clientHello());
+  }
+
+  async accept(): Promise<QuicPeerConnection> {
+    return new QuicPeerConnection(await this.native.accept());
+  }
+
+  /**
+   * Closes the attempt during the handshake with an application `errorCode` and `reason`. A
+   * client still mid-handshake when the close arrives sees a handshake failure without them.
+   */
+  reject(errorCode: number, reason?: string): void {
+    this.native.reject(errorCode, reason);
+  }
+}
+
 export class QuicPeerClient {
   private readonly native: any;
 
   private constructor(native: any) {
-    this.native = native;
+    this.native = withErrorCodes(native);
   }
 
-  static create(bindAddr: string): QuicPeerClient {
+  static create(bindAddr: string, options?: QuicTransportOptions): QuicPeerClient {
     const native = loadNative();
-    return new QuicPeerClient((native.PeerClient as any).create(bindAddr));
+    return new QuicPeerClient((native.PeerClient as any).create(bindAddr, options ? stringifyJson(options) : undefined));
   }
 
-  async connect(offer: QuicOffer): Promise<QuicPeerConnection> {
-    const conn = await this.native.connect(stringifyJson(offer));
+  /** The bound address, including the OS-assigned port when created with port `0`. */
+  localAddress(): string {
+    const localAddress = resolveMethod(this.native, ["local_address", "localAddress"]);
+    return localAddress();
+  }
+
+  /** Moves the client to a new local socket; open connections migrate with it. */
+  async rebind(bindAddr: string): Promise<void> {
+    await this.native.rebind(bindAddr);
+  }
+
+  async connect(offer: QuicOffer, options?: QuicConnectOptions): Promise<QuicPeerConnection> {
+    const conn = await this.native.connect(stringifyJson(offer), options ? stringifyJson(options) : undefined);
     return new QuicPeerConnection(conn);
   }
+
+  /**
+   * Reconnects using 0-RTT when a session ticket from an earlier connection to the same
+   * server certificate is cached: `connection` is usable before the handshake completes. Early
+   * data can be replayed by an attacker, so only send idempotent requests before `accepted`
+   * resolves, and resend them if it resolves `false`. `earlyData` is false, and this does a
+   * full `connect`, unless the server was created with `acceptEarlyData`.
+   */
+  async connect0Rtt(offer: QuicOffer, options?: QuicConnectOptions): Promise<QuicZeroRttConnection> {
+    const connect0Rtt = resolveMethod(this.native, ["connect_0rtt", "connect0Rtt"]);
+    const result = withErrorCodes(await connect0Rtt(stringifyJson(offer), options ? stringifyJson(options) : undefined));
+    return {
+      connection: new QuicPeerConnection(result.connection()),
+      earlyData: resolveMethod(result, ["early_data", "earlyData"])(),
+      accepted: result.accepted(),
+    };
+  }
 }
 
 export class QuicPeerConnection {
   private readonly native: any;
 
   constructor(native: any) {
-    this.native = native;
+    this.native = withErrorCodes(native);
   }
 
+  /** Remote address of the current path; follows the peer across migrations. */
   remoteAddress(): string {
     const remoteAddress = resolveMethod(this.native, ["remote_address", "remoteAddress"]);
     return remoteAddress();
   }
 
-  async openBi(): Promise<QuicBiStream> {
+  /** Remote address changes seen so far, sampled on this call and on `remoteAddress()`. */
+  migrationCount(): number {
+    const migrationCount = resolveMethod(this.native, ["migration_count", "migrationCount"]);
+    return migrationCount();
+  }
+
+  /**
+   * Client side: the offer candidate that won the connect race (hostnames resolved), which
+   * unlike `remoteAddress()` ignores later migrations. `null` on the server side.
+   */
+  winningCandidate(): string | null {
+    const winningCandidate = resolveMethod(this.native, ["winning_candidate", "winningCandidate"]);
+    return winningCandidate() ?? null;
+  }
+
+  /**
+   * A hex identifier that both ends of the connection report identically and that survives
+   * migration, for putting into log context on client and server alike.
+   */
+  stableId(): string {
+    const stableId = resolveMethod(this.native, ["stable_id", "stableId"]);
+    return stableId();
+  }
+
+  remoteAddressFamily(): "ipv4" | "ipv6" {
+    const remoteAddressFamily = resolveMethod(this.native, ["remote_address_family", "remoteAddressFamily"]);
+    return remoteAddressFamily();
+  }
+
+  /**
+   * SNI sent by the client, server side only. To route on it before the handshake completes,
+   * use `QuicPendingConnection.clientHello()`.
+   */
+  serverName(): string | null {
+    const serverName = resolveMethod(this.native, ["server_name", "serverName"]);
+    return serverName() ?? null;
+  }
+
+  /** ALPN protocol negotiated in the handshake. */
+  alpnProtocol(): string | null {
+    const alpnProtocol = resolveMethod(this.native, ["alpn_protocol", "alpnProtocol"]);
+    return alpnProtocol() ?? null;
+  }
+
+  /** ALPN and server name in one record for audit logging. */
+  handshakeInfo(): QuicHandshakeInfo {
+    const handshakeInfo = resolveMethod(this.native, ["handshake_info", "handshakeInfo"]);
+    return parseJson<QuicHandshakeInfo>(handshakeInfo());
+  }
+
+  /**
+   * With an open stream limit set, waits for a slot unless `noWait`, which rejects instead.
+   * `traceId` tags the stream's lifecycle events in the event log (see `enableEventLog`).
+   */
+  async openBi(options?: { noWait?: boolean; traceId?: string }): Promise<QuicBiStream> {
     const openBi = resolveMethod(this.native, ["open_bi", "openBi"]);
-    const stream = await openBi();
+    const stream = await openBi(options?.noWait, options?.traceId);
     return new QuicBiStream(stream);
   }
 
-  async acceptBi(): Promise<QuicBiStream> {
+  /**
+   * Opens `count` streams in one native call. Waits while the local limit or the peer's stream
+   * limit is exhausted; with `noWait`, rejects instead if the local limit cannot fit the batch.
+   */
+  async openBiBatch(count: number, options?: { noWait?: boolean }): Promise<QuicBiStream[]> {
+    const openBiBatch = resolveMethod(this.native, ["open_bi_batch", "openBiBatch"]);
+    const streams: any[] = await openBiBatch(count, options?.noWait);
+    return streams.map((stream) => new QuicBiStream(stream));
+  }
+
+  /**
+   * Caps locally opened streams that are not yet fully closed, compressed and encrypted ones
+   * included; the message stream of `send()` holds one slot once opened. Can only be set once.
+   */
+  setOpenStreamLimit(max: number): void {
+    const setOpenStreamLimit = resolveMethod(this.native, ["set_open_stream_limit", "setOpenStreamLimit"]);
+    setOpenStreamLimit(max);
+  }
+
+  outstandingStreams(): number {
+    const outstandingStreams = resolveMethod(this.native, ["outstanding_streams", "outstandingStreams"]);
+    return outstandingStreams();
+  }
+
+  /**
+   * Streams opened or accepted on this connection that are not done yet, by direction. A
+   * bi stream is done once finished and read to FIN, a uni stream once finished or read to
+   * FIN (or either when garbage-collected). Compressed, encrypted and message streams are not
+   * counted.
+   */
+  openStreamCounts(): QuicOpenStreamCounts {
+    const openStreamCounts = resolveMethod(this.native, ["open_stream_counts", "openStreamCounts"]);
+    return parseJson<QuicOpenStreamCounts>(openStreamCounts());
+  }
+
+  /**
+   * Aborting `signal` rejects with code `"CANCELLED"`; streams the peer opens meanwhile stay
+   * queued. Aborting cancels every `acceptBi()` waiting on this connection, not just this one.
+   */
+  async acceptBi(options?: QuicAbortOptions): Promise<QuicBiStream> {
     const acceptBi = resolveMethod(this.native, ["accept_bi", "acceptBi"]);
-    const stream = await acceptBi();
+    const cancelAccepts = resolveMethod(this.native, ["cancel_accepts", "cancelAccepts"]);
+    const stream = await withAbort(options?.signal, cancelAccepts, () => acceptBi());
     return new QuicBiStream(stream);
   }
 
+  /** Opens a send-only stream for one-way traffic; the peer takes it with `acceptUni()`. */
+  async openUni(): Promise<QuicSendStream> {
+    const openUni = resolveMethod(this.native, ["open_uni", "openUni"]);
+    return new QuicSendStream(await openUni());
+  }
+
+  async acceptUni(): Promise<QuicRecvStream> {
+    const acceptUni = resolveMethod(this.native, ["accept_uni", "acceptUni"]);
+    return new QuicRecvStream(await acceptUni());
+  }
+
+  /**
+   * Opens a stream whose messages are compressed natively. Compression costs CPU to save
+   * bandwidth, so use it for compressible payloads (text, JSON) rather than media.
+   */
+  async openBiCompressed(codec: QuicCompressionCodec): Promise<QuicCompressedStream> {
+    const openBiCompressed = resolveMethod(this.native, ["open_bi_compressed", "openBiCompressed"]);
+    return new QuicCompressedStream(await openBiCompressed(codec));
+  }
+
+  async acceptBiCompressed(): Promise<QuicCompressedStream> {
+    const acceptBiCompressed = resolveMethod(this.native, ["accept_bi_compressed", "acceptBiCompressed"]);
+    return new QuicCompressedStream(await acceptBiCompressed());
+  }
+
+  /** `sharedKey` is pre-shared: the peer must accept with the same 32 bytes. */
+  async openBiEncrypted(sharedKey: Buffer): Promise<QuicEncryptedStream> {
+    const openBiEncrypted = resolveMethod(this.native, ["open_bi_encrypted", "openBiEncrypted"]);
+    return new QuicEncryptedStream(await openBiEncrypted(sharedKey));
+  }
+
+  async acceptBiEncrypted(sharedKey: Buffer): Promise<QuicEncryptedStream> {
+    const acceptBiEncrypted = resolveMethod(this.native, ["accept_bi_encrypted", "acceptBiEncrypted"]);
+    return new QuicEncryptedStream(await acceptBiEncrypted(sharedKey));
+  }
+
+  /**
+   * Datagram request/response without opening a stream. Each datagram starts with a kind byte
+   * (`0xE1` request, `0xE2` response) and a big-endian u32 tag the responder echoes back.
+   */
+  async datagramExchange(payload: Buffer, timeoutMs: number): Promise<Buffer> {
+    const datagramExchange = resolveMethod(this.native, ["datagram_exchange", "datagramExchange"]);
+    return datagramExchange(payload, timeoutMs);
+  }
+
+  async acceptDatagramExchange(): Promise<QuicDatagramRequest> {
+    const acceptDatagramExchange = resolveMethod(this.native, ["accept_datagram_exchange", "acceptDatagramExchange"]);
+    return new QuicDatagramRequest(await acceptDatagramExchange());
+  }
+
+  /** Largest payload `sendDatagram` accepts right now, or `null` if the peer has datagrams disabled. */
+  maxDatagramSize(): number | null {
+    const maxDatagramSize = resolveMethod(this.native, ["max_datagram_size", "maxDatagramSize"]);
+    return maxDatagramSize() ?? null;
+  }
+
+  /** Sends one unreliable, unordered datagram; throws if it exceeds `maxDatagramSize()`. */
+  sendDatagram(data: Buffer): void {
+    const sendDatagram = resolveMethod(this.native, ["send_datagram", "sendDatagram"]);
+    sendDatagram(data);
+  }
+
+  /** Resolves with the next datagram from the peer's `sendDatagram`, or `null` after a clean close. */
+  async readDatagram(): Promise<Buffer | null> {
+    const readDatagram = resolveMethod(this.native, ["read_datagram", "readDatagram"]);
+    return (await readDatagram()) ?? null;
+  }
+
+  /**
+   * Datagrams from the peer's `sendDatagram` discarded because 1024 were already unread.
+   * Call it (or start reading) soon after connecting: before that, arrivals sit in the
+   * `datagramReceiveBufferSize` buffer, whose overflow is not counted.
+   */
+  datagramsDropped(): number {
+    const datagramsDropped = resolveMethod(this.native, ["datagrams_dropped", "datagramsDropped"]);
+    return datagramsDropped();
+  }
+
+  /**
+   * Delivers each datagram from the peer's `sendDatagram` to `onDatagram` instead of
+   * `readDatagram()`. Calling it again replaces the previous callback.
+   */
+  onDatagram(onDatagram: (datagram: Buffer) => void): void {
+    const nativeOnDatagram = resolveMethod(this.native, ["on_datagram", "onDatagram"]);
+    nativeOnDatagram(onDatagram);
+  }
+
+  /**
+   * Estimates one-way delay from `samples` timestamped datagram probes; the peer must call
+   * `enableOwdResponder()`. Absolute one-way delays need synchronized clocks.
+   */
+  async measureOwd(samples: number): Promise<QuicOwdResult> {
+    const measureOwd = resolveMethod(this.native, ["measure_owd", "measureOwd"]);
+    return parseJson<QuicOwdResult>(await measureOwd(samples));
+  }
+
+  enableOwdResponder(): void {
+    const enableOwdResponder = resolveMethod(this.native, ["enable_owd_responder", "enableOwdResponder"]);
+    enableOwdResponder();
+  }
+
+  /**
+   * Opens datagram flow `flowId` (both peers use the same id): sends are sequenced and the
+   * receiver delivers them in order, dropping late datagrams and skipping gaps after a short
+   * reorder window.
+   */
+  openDatagramFlow(flowId: number): QuicDatagramFlow {
+    const openDatagramFlow = resolveMethod(this.native, ["open_datagram_flow", "openDatagramFlow"]);
+    return new QuicDatagramFlow(openDatagramFlow(flowId));
+  }
+
+  /**
+   * Sends one message over the connection. Reliable messages (the default) share an ordered
+   * stream; `reliable: false` uses a datagram, falling back to the stream when too large.
+   */
+  async send(data: Buffer, options?: QuicSendOptions): Promise<void> {
+    await this.native.send(data, options ? stringifyJson(options) : undefined);
+  }
+
+  /** Resolves with the next message sent by the peer's `send`, reliable or not. */
+  async recv(): Promise<Buffer> {
+    return this.native.recv();
+  }
+
+  /**
+   * Bits per second the congestion window allows at the current RTT, or `null` before the
+   * first RTT sample. An upper bound on throughput, suited to bitrate selection.
+   */
+  estimatedBandwidthBps(): number | null {
+    const estimatedBandwidthBps = resolveMethod(this.native, ["estimated_bandwidth_bps", "estimatedBandwidthBps"]);
+    return estimatedBandwidthBps() ?? null;
+  }
+
+  /** Live transport counters, meant to be polled for metrics. */
+  stats(): QuicConnectionStats {
+    return parseJson<QuicConnectionStats>(this.native.stats());
+  }
+
+  /** Smoothed RTT estimate in milliseconds; cheaper than `stats()` for frequent sampling. */
+  rttMs(): number {
+    const rttMs = resolveMethod(this.native, ["rtt_ms", "rttMs"]);
+    return rttMs();
+  }
+
+  /**
+   * Loss-detection counters. quinn does not count spurious retransmits; compare the loss
+   * rate before and after reordering-threshold changes instead.
+   */
+  lossStats(): QuicLossStats {
+    const lossStats = resolveMethod(this.native, ["loss_stats", "lossStats"]);
+    return parseJson<QuicLossStats>(lossStats());
+  }
+
+  /**
+   * Paces stream writes and `send()` toward `targetBps`. After idle periods a burst of one
+   * bandwidth-delay product (`targetBps` × RTT) goes out unpaced. `0` removes the limit.
+   */
+  setAdaptiveRateLimit(targetBps: number): void {
+    const setAdaptiveRateLimit = resolveMethod(this.native, ["set_adaptive_rate_limit", "setAdaptiveRateLimit"]);
+    setAdaptiveRateLimit(targetBps);
+  }
+
+  /** Average rate admitted since the limit was set, or `null` when no limit is set. */
+  effectiveRateBps(): number | null {
+    const effectiveRateBps = resolveMethod(this.native, ["effective_rate_bps", "effectiveRateBps"]);
+    return effectiveRateBps() ?? null;
+  }
+
+  /** Estimated datagrams queued on this connection but not yet sent. */
+  outstandingDatagrams(): number {
+    const outstandingDatagrams = resolveMethod(this.native, ["outstanding_datagrams", "outstandingDatagrams"]);
+    return outstandingDatagrams();
+  }
+
+  /**
+   * Caps queued datagrams; `0` removes the cap. `error` makes sends past the cap throw,
+   * `drop_newest` discards the new datagram and `drop_oldest` the oldest one still waiting,
+   * so the newest get through. With `drop_oldest`, half the cap (rounded up) is handed to the
+   * transport, which cannot discard, and the rest waits where the oldest can be.
+   */
+  setDatagramQueueLimit(limit: number, overflow: QuicDatagramOverflow = "error"): void {
+    const setDatagramQueueLimit = resolveMethod(this.native, ["set_datagram_queue_limit", "setDatagramQueueLimit"]);
+    setDatagramQueueLimit(limit, overflow);
+  }
+
+  /**
+   * Resolves when about `minBytes` can be sent without waiting on congestion control. This is
+   * an estimate from the congestion window and datagram send buffer, not a reservation.
+   */
+  async awaitSendCapacity(minBytes: number): Promise<void> {
+    const awaitSendCapacity = resolveMethod(this.native, ["await_send_capacity", "awaitSendCapacity"]);
+    await awaitSendCapacity(minBytes);
+  }
+
+  /** Samples the congestion window on a native timer; stops on `stopCwndSampling()` or close. */
+  startCwndSampling(intervalMs: number, onSample: (sample: QuicCwndSample) => void): void {
+    const startCwndSampling = resolveMethod(this.native, ["start_cwnd_sampling", "startCwndSampling"]);
+    startCwndSampling(intervalMs, (raw: string) => onSample(parseJson<QuicCwndSample>(raw)));
+  }
+
+  stopCwndSampling(): void {
+    const stopCwndSampling = resolveMethod(this.native, ["stop_cwnd_sampling", "stopCwndSampling"]);
+    stopCwndSampling();
+  }
+
+  /**
+   * Default drop behavior for bi streams opened or accepted after this call; `errorCode` is
+   * required for `reset`. See `QuicBiStream.setDropBehavior`.
+   */
+  setStreamDropBehavior(behavior: QuicStreamDropBehavior, errorCode?: number): void {
+    const setStreamDropBehavior = resolveMethod(this.native, ["set_stream_drop_behavior", "setStreamDropBehavior"]);
+    setStreamDropBehavior(behavior, errorCode);
+  }
+
+  /**
+   * Closes the connection when no application data (stream bytes or datagrams) arrives for
+   * `ms`; keepalives do not count. `0` disables.
+   */
+  setInactivityTimeout(ms: number, errorCode = 0, reason?: string): void {
+    const setInactivityTimeout = resolveMethod(this.native, ["set_inactivity_timeout", "setInactivityTimeout"]);
+    setInactivityTimeout(ms, errorCode, reason);
+  }
+
+  /**
+   * Rotates the connection out after `maxLifetimeMs`: `onExpiring(graceMs)` fires so the app
+   * can open a replacement, then the connection closes once `graceMs` has passed.
+   */
+  setMaxLifetime(maxLifetimeMs: number, graceMs: number, onExpiring: (graceMs: number) => void): void {
+    const setMaxLifetime = resolveMethod(this.native, ["set_max_lifetime", "setMaxLifetime"]);
+    setMaxLifetime(maxLifetimeMs, graceMs, onExpiring);
+  }
+
+  /** Rolled-up connection stats for teardown logging; also available after close. */
+  summary(): QuicConnectionSummary {
+    return parseJson<QuicConnectionSummary>(this.native.summary());
+  }
+
+  /** Keeps the last `capacity` connection events in native memory; readable after close. */
+  enableEventLog(capacity: number): void {
+    const enableEventLog = resolveMethod(this.native, ["enable_event_log", "enableEventLog"]);
+    enableEventLog(capacity);
+  }
+
+  eventLog(): QuicConnectionEvent[] {
+    const eventLog = resolveMethod(this.native, ["event_log", "eventLog"]);
+    return parseJson<QuicConnectionEvent[]>(eventLog());
+  }
+
+  /** Resolves with the categorized close reason once the connection has closed. */
+  async closed(): Promise<QuicCloseReason> {
+    return parseJson<QuicCloseReason>(await this.native.closed());
+  }
+
+  /** Calls `onClose` once the connection has closed, like `closed()` without holding a promise. */
+  onClose(onClose: (reason: QuicCloseReason) => void): void {
+    const nativeOnClose = resolveMethod(this.native, ["on_close", "onClose"]);
+    nativeOnClose((raw: string) => onClose(parseJson<QuicCloseReason>(raw)));
+  }
+
+  /** `false` once the connection has closed; cheap enough to check before reusing a pooled one. */
+  isAlive(): boolean {
+    const isAlive = resolveMethod(this.native, ["is_alive", "isAlive"]);
+    return isAlive();
+  }
+
+  /**
+   * The close reason if the connection has already closed, else `null`. An `application_closed`
+   * reason means the peer called `close()`; the other kinds are transport-level failures.
+   */
+  closeReason(): QuicCloseReason | null {
+    const closeReason = resolveMethod(this.native, ["close_reason", "closeReason"]);
+    const raw = closeReason();
+    return raw == null ? null : parseJson<QuicCloseReason>(raw);
+  }
+
   close(errorCode = 0, reason?: string): void {
     this.native.close(errorCode, reason);
   }
 }
 
+export class QuicDatagramFlow {
+  private readonly native: any;
+
+  constructor(native: any) {
+    this.native = withErrorCodes(native);
+  }
+
+  flowId(): number {
+    const flowId = resolveMethod(this.native, ["flow_id", "flowId"]);
+    return flowId();
+  }
+
+  send(payload: Buffer): void {
+    this.native.send(payload);
+  }
+
+  async recv(): Promise<Buffer> {
+    return this.native.recv();
+  }
+
+  stats(): QuicDatagramFlowStats {
+    return parseJson<QuicDatagramFlowStats>(this.native.stats());
+  }
+}
+
+export class QuicDatagramRequest {
+  private readonly native: any;
+
+  constructor(native: any) {
+    this.native = withErrorCodes(native);
+  }
+
+  tag(): number {
+    return this.native.tag();
+  }
+
+  payload(): Buffer {
+    return this.native.payload();
+  }
+
+  respond(payload: Buffer): void {
+    this.native.respond(payload);
+  }
+}
+
+/** Send-only half of a uni stream; writes are applied in call order. */
+export class QuicSendStream {
+  private readonly native: any;
+  private sendTail: Promise<unknown> = Promise.resolve();
+
+  constructor(native: any) {
+    this.native = withErrorCodes(native);
+  }
+
+  private enqueueSend<T>(op: () => Promise<T>): Promise<T> {
+    const run = this.sendTail.then(op);
+    this.sendTail = run.catch(() => undefined);
+    return run;
+  }
+
+  async write(data: Buffer): Promise<void> {
+    await this.enqueueSend(() => this.native.write(data));
+  }
+
+  async finish(): Promise<void> {
+    await this.enqueueSend(() => this.native.finish());
+  }
+}
+
+/** Receive-only half of a uni stream; `read` behaves like `QuicBiStream.read`. */
+export class QuicRecvStream {
+  private readonly native: any;
+
+  constructor(native: any) {
+    this.native = withErrorCodes(native);
+  }
+
+  async read(maxBytes?: number): Promise<Buffer | null> {
+    const out = await this.native.read(maxBytes);
+    return out ?? null;
+  }
+}
+
 export class QuicBiStream {
   private readonly native: any;
+  private sendTail: Promise<unknown> = Promise.resolve();
+
+  constructor(native: any) {
+    this.native = withErrorCodes(native);
+  }
+
+  /**
+   * Send-side operations run one at a time in call order. The native side only orders
+   * operations by when they reach its lock, which depends on task scheduling, so concurrent
+   * writes issued without awaiting could otherwise be reordered.
+   */
+  private enqueueSend<T>(op: () => Promise<T>): Promise<T> {
+    const run = this.sendTail.then(op);
+    this.sendTail = run.catch(() => undefined);
+    return run;
+  }
+
+  /** Writes are delivered in call order, including concurrent writes that are not awaited. */
+  async write(data: Buffer): Promise<void> {
+    await this.enqueueSend(() => this.native.write(data));
+  }
+
+  /** Writes `chunks` (e.g. a header and a body) back to back; no other write lands between them. */
+  async writeAll(chunks: Buffer[]): Promise<void> {
+    await this.enqueueSend(() => {
+      const writeAll = resolveMethod(this.native, ["write_all", "writeAll"]);
+      return writeAll(chunks);
+    });
+  }
+
+  /**
+   * `write` that reports progress: `onProgress` gets the cumulative bytes written after each
+   * 64 KiB piece. Queued like `write`, so ordering with other writes is kept.
+   */
+  async writeWithProgress(data: Buffer, onProgress: (bytesWritten: number) => void): Promise<void> {
+    await this.enqueueSend(() => {
+      const writeWithProgress = resolveMethod(this.native, ["write_with_progress", "writeWithProgress"]);
+      return writeWithProgress(data, onProgress);
+    });
+  }
+
+  /**
+   * `write` bounded by `timeoutMs`, counted from when earlier queued operations are done. On
+   * expiry the send half is reset with `errorCode` and the promise rejects with code
+   * `"TIMED_OUT"`; the stream can no longer be written.
+   */
+  async writeTimeout(data: Buffer, timeoutMs: number, errorCode?: number): Promise<void> {
+    await this.enqueueSend(() => {
+      const writeTimeout = resolveMethod(this.native, ["write_timeout", "writeTimeout"]);
+      return writeTimeout(data, timeoutMs, errorCode);
+    });
+  }
+
+  /**
+   * Buffers small writes for up to `coalesceMs` and sends them together, cutting packet count
+   * for chatty streams at the cost of latency. `0` disables; use `flush()` for urgent data.
+   */
+  setWriteCoalescing(coalesceMs: number): void {
+    const setWriteCoalescing = resolveMethod(this.native, ["set_write_coalescing", "setWriteCoalescing"]);
+    setWriteCoalescing(coalesceMs);
+  }
+
+  async flush(): Promise<void> {
+    await this.enqueueSend(() => this.native.flush());
+  }
+
+  /** Called each time a write that was blocked on flow control or congestion can proceed. */
+  onWritable(cb: () => void): void {
+    const onWritable = resolveMethod(this.native, ["on_writable", "onWritable"]);
+    onWritable(cb);
+  }
+
+  async finish(): Promise<void> {
+    await this.enqueueSend(() => this.native.finish());
+  }
+
+  /**
+   * Higher numbers are sent first when streams compete for the connection, e.g. to keep a
+   * control stream ahead of bulk transfers. Defaults to `0`; may be negative.
+   */
+  async setPriority(priority: number): Promise<void> {
+    const setPriority = resolveMethod(this.native, ["set_priority", "setPriority"]);
+    await setPriority(priority);
+  }
+
+  /** True for the first bi stream its initiator opened; for accepted streams, the peer's first. */
+  isFirstStream(): boolean {
+    const isFirstStream = resolveMethod(this.native, ["is_first_stream", "isFirstStream"]);
+    return isFirstStream();
+  }
+
+  /** The QUIC stream id, identical on both endpoints; useful as a `stream=<id>` log tag. */
+  id(): string {
+    return this.native.id();
+  }
+
+  /** Abandons the send half; the peer's reads fail with `errorCode`, later writes with "stream reset". */
+  async reset(errorCode: number): Promise<void> {
+    await this.enqueueSend(() => this.native.reset(errorCode));
+  }
+
+  /** Abandons the receive half, telling the peer to stop sending; later reads resolve `null`. */
+  async stop(errorCode: number): Promise<void> {
+    await this.native.stop(errorCode);
+  }
+
+  /**
+   * Chooses what happens if this stream is garbage-collected without `finish()` or `reset()`:
+   * `finish` (default) flushes and sends FIN, `reset` resets the send half and stops the
+   * receive half with `errorCode`. GC timing is not deterministic; close explicitly when the
+   * peer needs to know promptly.
+   */
+  setDropBehavior(behavior: QuicStreamDropBehavior, errorCode?: number): void {
+    const setDropBehavior = resolveMethod(this.native, ["set_drop_behavior", "setDropBehavior"]);
+    setDropBehavior(behavior, errorCode);
+  }
+
+  /**
+   * Aborting `signal` rejects with code `"CANCELLED"` without losing data. It cancels every
+   * `read()` pending on this stream, not just this one.
+   */
+  async read(maxBytes?: number, options?: QuicAbortOptions): Promise<Buffer | null> {
+    const cancelReads = resolveMethod(this.native, ["cancel_reads", "cancelReads"]);
+    const out = await withAbort(options?.signal, cancelReads, () => this.native.read(maxBytes));
+    return out ?? null;
+  }
+
+  /**
+   * Like `read`, but returns everything already buffered (up to `maxTotal` bytes) in one
+   * buffer instead of one chunk, cutting crossings of the native boundary for fast parsers.
+   */
+  async readAllAvailable(maxTotal: number, options?: QuicAbortOptions): Promise<Buffer | null> {
+    const readAllAvailable = resolveMethod(this.native, ["read_all_available", "readAllAvailable"]);
+    const cancelReads = resolveMethod(this.native, ["cancel_reads", "cancelReads"]);
+    const out = await withAbort(options?.signal, cancelReads, () => readAllAvailable(maxTotal));
+    return out ?? null;
+  }
+
+  /**
+   * The receive half as an async iterable, ending at FIN:
+   * `for await (const chunk of stream.chunks()) { ... }`. Each step is one `read(maxBytes)`;
+   * breaking out of the loop leaves the stream open.
+   */
+  async *chunks(maxBytes?: number): AsyncGenerator<Buffer, void, undefined> {
+    for (let chunk = await this.read(maxBytes); chunk !== null; chunk = await this.read(maxBytes)) {
+      yield chunk;
+    }
+  }
+
+  /**
+   * Reads exactly `n` bytes. Resolves `null` if the stream ends before any of them arrive;
+   * throws if it ends partway through.
+   */
+  async readExact(n: number): Promise<Buffer | null> {
+    const readExact = resolveMethod(this.native, ["read_exact", "readExact"]);
+    return (await readExact(n)) ?? null;
+  }
+
+  /**
+   * Reads directly into `buffers` in order. Resolves with the total bytes read, which may leave
+   * later buffers partially filled or untouched, or `null` at end of stream.
+   */
+  async readVectored(buffers: Buffer[]): Promise<number | null> {
+    const readVectored = resolveMethod(this.native, ["read_vectored", "readVectored"]);
+    const out = await readVectored(buffers);
+    return out ?? null;
+  }
+
+  /**
+   * Reads into a buffer from `pool`, waiting for one to be free. Resolves with the handle,
+   * whose `data` holds the bytes read, or `null` at end of stream. Call `release()` when done.
+   */
+  async readPooled(pool: BufferPool): Promise<PooledBuffer | null> {
+    const pooled = await pool.acquire();
+    try {
+      const read = await this.readVectored([pooled.backing]);
+      if (read === null) {
+        pooled.release();
+        return null;
+      }
+      pooled.setLength(read);
+      return pooled;
+    } catch (err) {
+      pooled.release();
+      throw err;
+    }
+  }
+
+  /** Sends a file from disk natively, keeping it off the JS heap. Resolves with bytes sent. */
+  async writeFile(path: string): Promise<number> {
+    const writeFile = resolveMethod(this.native, ["write_file", "writeFile"]);
+    return this.enqueueSend(() => writeFile(path));
+  }
+
+  /** Writes incoming data to `path` until the peer finishes the stream. Resolves with bytes written. */
+  async recvToFile(path: string): Promise<number> {
+    const recvToFile = resolveMethod(this.native, ["recv_to_file", "recvToFile"]);
+    return recvToFile(path);
+  }
+
+  /**
+   * Sends `plaintext` as one message encrypted with the pre-shared `sharedKey`, framed like
+   * `intoEncrypted`. Plain writes stay available; the peer must read in the same order with
+   * `readE2ee`. The framing is stream-specific and cannot be decrypted as a relay payload.
+   */
+  async writeE2ee(sharedKey: Buffer, plaintext: Buffer): Promise<void> {
+    await this.enqueueSend(() => {
+      const writeE2ee = resolveMethod(this.native, ["write_e2ee", "writeE2ee"]);
+      return writeE2ee(sharedKey, plaintext);
+    });
+  }
+
+  /**
+   * Reads the next `writeE2ee` message, or `null` at FIN. Tampered, replayed, reordered or
+   * reflected frames reject.
+   */
+  async readE2ee(sharedKey: Buffer): Promise<Buffer | null> {
+    const readE2ee = resolveMethod(this.native, ["read_e2ee", "readE2ee"]);
+    return (await readE2ee(sharedKey)) ?? null;
+  }
+
+  /**
+   * Sends `data` as one message with a 4-byte big-endian length prefix, for the peer's
+   * `recvMessage()`. Ordered with other writes on this stream.
+   */
+  async sendMessage(data: Buffer): Promise<void> {
+    await this.enqueueSend(() => {
+      const sendMessage = resolveMethod(this.native, ["send_message", "sendMessage"]);
+      return sendMessage(data);
+    });
+  }
+
+  /**
+   * Reads one `sendMessage()` message, or `null` on FIN between messages. Rejects if the
+   * stream ends mid-message or announces more than `maxBytes` (default, and capped at, the
+   * `maxReadBytes` transport option); stop the stream after that.
+   */
+  async recvMessage(maxBytes?: number): Promise<Buffer | null> {
+    const recvMessage = resolveMethod(this.native, ["recv_message", "recvMessage"]);
+    return (await recvMessage(maxBytes ?? null)) ?? null;
+  }
+
+  /** Moves this stream into an encrypted wrapper; this handle must not be used afterwards. */
+  async intoEncrypted(sharedKey: Buffer): Promise<QuicEncryptedStream> {
+    const intoEncrypted = resolveMethod(this.native, ["into_encrypted", "intoEncrypted"]);
+    return new QuicEncryptedStream(await this.enqueueSend(() => intoEncrypted(sharedKey)));
+  }
+
+  /**
+   * Payload bytes written so far through any write method, without protocol overhead
+   * (plaintext size for `writeE2ee`). Includes bytes still in the coalescing buffer.
+   */
+  bytesWritten(): number {
+    const bytesWritten = resolveMethod(this.native, ["bytes_written", "bytesWritten"]);
+    return bytesWritten();
+  }
+
+  /** Payload bytes read so far through any read method, on the same terms as `bytesWritten()`. */
+  bytesRead(): number {
+    const bytesRead = resolveMethod(this.native, ["bytes_read", "bytesRead"]);
+    return bytesRead();
+  }
+
+  /** Stops consuming the stream so the peer is flow-controlled until `resumeReading()`. */
+  pauseReading(): void {
+    const pauseReading = resolveMethod(this.native, ["pause_reading", "pauseReading"]);
+    pauseReading();
+  }
+
+  resumeReading(): void {
+    const resumeReading = resolveMethod(this.native, ["resume_reading", "resumeReading"]);
+    resumeReading();
+  }
+
+  isReadingPaused(): boolean {
+    const isReadingPaused = resolveMethod(this.native, ["is_reading_paused", "isReadingPaused"]);
+    return isReadingPaused();
+  }
+}
+
+export class QuicCompressedStream {
+  private readonly native: any;
 
   constructor(native: any) {
-    this.native = native;
+    this.native = withErrorCodes(native);
+  }
+
+  codec(): QuicCompressionCodec {
+    return this.native.codec();
   }
 
   async write(data: Buffer): Promise<void> {
@@ -229,8 +1550,36 @@ export class QuicBiStream {
     await this.native.finish();
   }
 
-  async read(maxBytes?: number): Promise<Buffer | null> {
-    const out = await this.native.read(maxBytes);
+  async read(): Promise<Buffer | null> {
+    const out = await this.native.read();
+    return out ?? null;
+  }
+}
+
+/**
+ * Message-oriented E2EE over a bi stream with a pre-shared 32-byte key; no key exchange happens
+ * on the stream. Each `write` is sealed into one AEAD frame with a random nonce and a
+ * per-direction sequence number, and `read` returns one decrypted message. Replayed, reordered
+ * or reflected frames are rejected. The framing is specific to streams and is not the relay
+ * payload format.
+ */
+export class QuicEncryptedStream {
+  private readonly native: any;
+
+  constructor(native: any) {
+    this.native = withErrorCodes(native);
+  }
+
+  async write(plaintext: Buffer): Promise<void> {
+    await this.native.write(plaintext);
+  }
+
+  async finish(): Promise<void> {
+    await this.native.finish();
+  }
+
+  async read(): Promise<Buffer | null> {
+    const out = await this.native.read();
     return out ?? null;
   }
 }
@@ -239,7 +1588,7 @@ export class QuicRelayClient {
   private readonly native: any;
 
   private constructor(native: any) {
-    this.native = native;
+    this.native = withErrorCodes(native);
   }
 
   static create(bindAddr: string): QuicRelayClient {
@@ -247,9 +1596,15 @@ export class QuicRelayClient {
     return new QuicRelayClient((native.RelayClient as any).create(bindAddr));
   }
 
-  async connectTransport(relay: QuicRelayInfo): Promise<QuicRelayTransport> {
+  /** The bound address, including the OS-assigned port when created with port `0`. */
+  localAddress(): string {
+    const localAddress = resolveMethod(this.native, ["local_address", "localAddress"]);
+    return localAddress();
+  }
+
+  async connectTransport(relay: QuicRelayInfo, options?: QuicRelayTransportOptions): Promise<QuicRelayTransport> {
     const connectTransport = resolveMethod(this.native, ["connect_transport", "connectTransport"]);
-    const transport = await connectTransport(stringifyJson(relay));
+    const transport = await connectTransport(stringifyJson(relay), options ? stringifyJson(options) : undefined);
     return new QuicRelayTransport(transport);
   }
 }
@@ -258,34 +1613,208 @@ export class QuicRelayTransport {
   private readonly native: any;
 
   constructor(native: any) {
-    this.native = native;
+    this.native = withErrorCodes(native);
   }
 
-  async relayBind(token: string, sessionId: string): Promise<boolean> {
+  /** Rejects with `TIMED_OUT` once every attempt allowed by `options` timed out. */
+  async relayBind(token: string, sessionId: string, options?: QuicRelayBindOptions): Promise<boolean> {
     const relayBind = resolveMethod(this.native, ["relay_bind", "relayBind"]);
-    return relayBind(token, sessionId);
+    return relayBind(token, sessionId, options ? stringifyJson(options) : undefined);
+  }
+
+  /**
+   * Binds many sessions in one round trip where the relay supports it, else one by one.
+   * Results are in input order; a failed session does not fail the others.
+   */
+  async relayBindMulti(token: string, sessionIds: string[]): Promise<QuicRelayBindResult[]> {
+    const relayBindMulti = resolveMethod(this.native, ["relay_bind_multi", "relayBindMulti"]);
+    const results = parseJson<QuicRelayBindResult[]>(await relayBindMulti(token, sessionIds));
+    return results.map((result) => {
+      if (!("error" in result)) {
+        return result;
+      }
+      const [code, error] = splitErrorCode(result.error);
+      return code ? { ...result, error, code: code as QuicErrorCode } : result;
+    });
+  }
+
+  /**
+   * Releases a session on the relay without closing the transport, also freeing its slot
+   * under `maxSessions`. Resolves whether the relay had it bound; rejects with the relay's
+   * message if it declines, including when it cannot unbind.
+   */
+  async relayUnbind(token: string, sessionId: string): Promise<boolean> {
+    const relayUnbind = resolveMethod(this.native, ["relay_unbind", "relayUnbind"]);
+    return relayUnbind(token, sessionId);
+  }
+
+  /** Live transport counters for the relay connection, as on `QuicPeerConnection.stats()`. */
+  stats(): QuicConnectionStats {
+    return parseJson<QuicConnectionStats>(this.native.stats());
+  }
+
+  boundSessionCount(): number {
+    const boundSessionCount = resolveMethod(this.native, ["bound_session_count", "boundSessionCount"]);
+    return boundSessionCount();
   }
 
-  async relaySend(token: string, sessionId: string, payload: unknown): Promise<void> {
+  /** Local bind state only, so it is cheap but may be stale if the relay dropped the binding. */
+  isBound(sessionId: string): boolean {
+    const isBound = resolveMethod(this.native, ["is_bound", "isBound"]);
+    return isBound(sessionId);
+  }
+
+  /**
+   * Session ids the relay has bound under `token`, including the far peer's binds, or `null`
+   * if the relay declines the query, as relays without it do. Poll it to wait for the peer
+   * before sending.
+   */
+  async relayListSessions(token: string): Promise<string[] | null> {
+    const relayListSessions = resolveMethod(this.native, ["relay_list_sessions", "relayListSessions"]);
+    return (await relayListSessions(token)) ?? null;
+  }
+
+  /** Our address as observed by the relay, or `null` if the relay does not support the query. */
+  async reflexiveAddress(): Promise<string | null> {
+    const reflexiveAddress = resolveMethod(this.native, ["reflexive_address", "reflexiveAddress"]);
+    const out = await reflexiveAddress();
+    return out ?? null;
+  }
+
+  /**
+   * Fire-and-forget: resolves once the relay has the frame, not when a peer received it. The
+   * relay drops payloads for sessions nobody is bound to without telling the sender, so
+   * delivery has to be confirmed by the peer replying.
+   */
+  async relaySend(token: string, sessionId: string, payload: unknown, options?: QuicRelaySendOptions): Promise<void> {
     const relaySend = resolveMethod(this.native, ["relay_send", "relaySend"]);
-    await relaySend(token, sessionId, stringifyJson(payload));
+    await relaySend(token, sessionId, stringifyJson(payload), options?.compress);
   }
 
-  async recvRelayData(): Promise<QuicRelayDataFrame> {
+  /**
+   * Sends raw bytes (e.g. protobuf) without a JSON round trip through the caller. The peer
+   * must read them with `recvRelayDataBytes`; `recvRelayData` rejects them.
+   */
+  async relaySendBytes(token: string, sessionId: string, payload: Buffer): Promise<void> {
+    const relaySendBytes = resolveMethod(this.native, ["relay_send_bytes", "relaySendBytes"]);
+    await relaySendBytes(token, sessionId, payload);
+  }
+
+  /**
+   * Resolves `null` once `stopReceiving()` has been called. Rejects frames sent with
+   * `relaySendBytes`, and E2EE frames (from `relaySendE2ee`, or `encryptRelayPayload`
+   * output without `seq`), which need `recvRelayDataE2ee`.
+   */
+  async recvRelayData(): Promise<QuicRelayDataFrame | null> {
     const recvRelayData = resolveMethod(this.native, ["recv_relay_data", "recvRelayData"]);
     const raw = await recvRelayData();
-    return parseJson<QuicRelayDataFrame>(raw);
+    return raw == null ? null : parseJson<QuicRelayDataFrame>(raw);
+  }
+
+  /** Binary counterpart of `recvRelayData` for frames sent with `relaySendBytes`; rejects JSON payloads. */
+  async recvRelayDataBytes(): Promise<QuicRelayBytesFrame | null> {
+    const recvRelayDataBytes = resolveMethod(this.native, ["recv_relay_data_bytes", "recvRelayDataBytes"]);
+    const frame = await recvRelayDataBytes();
+    if (frame == null) {
+      return null;
+    }
+    return {
+      session_id: resolveMethod(frame, ["session_id", "sessionId"])(),
+      from: frame.from(),
+      to: frame.to(),
+      payload: frame.payload(),
+    };
+  }
+
+  /**
+   * Stops the receive side only: pending and future `recvRelayData`/`recvRelayDataBytes`/
+   * `recvRelayDataE2ee` calls resolve `null`, so receive loops can treat `null` as their end,
+   * while sends keep working.
+   */
+  stopReceiving(): void {
+    const stopReceiving = resolveMethod(this.native, ["stop_receiving", "stopReceiving"]);
+    stopReceiving();
   }
 
-  async relaySendE2ee(token: string, sessionId: string, sharedKey: Buffer, plaintext: unknown): Promise<void> {
+  /**
+   * `aad` (e.g. a plaintext routing header sent alongside) is authenticated but not sent; the
+   * peer must pass the same bytes to `recvRelayDataE2ee`.
+   */
+  async relaySendE2ee(
+    token: string,
+    sessionId: string,
+    sharedKey: Buffer,
+    plaintext: unknown,
+    aad?: Buffer,
+    options?: QuicRelaySendOptions,
+  ): Promise<void> {
     const relaySendE2ee = resolveMethod(this.native, ["relay_send_e2ee", "relaySendE2ee"]);
-    await relaySendE2ee(token, sessionId, sharedKey, stringifyJson(plaintext));
+    await relaySendE2ee(token, sessionId, sharedKey, stringifyJson(plaintext), aad, options?.compress);
   }
 
-  async recvRelayDataE2ee(sharedKey: Buffer): Promise<QuicRelayDataFrame> {
+  /**
+   * Encrypts to the peer's long-term public key with a fresh ephemeral key per message
+   * (sender forward secrecy). Costs a key generation and ECDH per message on both sides.
+   * The peer reads it with `recvRelayData` and `E2eeKeyPair.openForwardSecret`.
+   */
+  async relaySendFs(token: string, sessionId: string, peerPublicB64: string, plaintext: unknown): Promise<void> {
+    const relaySendFs = resolveMethod(this.native, ["relay_send_fs", "relaySendFs"]);
+    await relaySendFs(token, sessionId, peerPublicB64, stringifyJson(plaintext));
+  }
+
+  /**
+   * Rejects frames whose `aad` at encryption differs from this one, and with `NOT_ENCRYPTED`
+   * frames that were not sent with `relaySendE2ee`, so a relay cannot pass off plaintext as
+   * the peer's.
+   */
+  async recvRelayDataE2ee(sharedKey: Buffer, aad?: Buffer): Promise<QuicRelayDataFrame | null> {
     const recvRelayDataE2ee = resolveMethod(this.native, ["recv_relay_data_e2ee", "recvRelayDataE2ee"]);
-    const raw = await recvRelayDataE2ee(sharedKey);
-    return parseJson<QuicRelayDataFrame>(raw);
+    const raw = await recvRelayDataE2ee(sharedKey, aad);
+    return raw == null ? null : parseJson<QuicRelayDataFrame>(raw);
+  }
+
+  /**
+   * Incoming frames as an async iterable: `for await (const frame of transport.frames()) { ... }`.
+   * Ends after `stopReceiving()` or once the relay connection closes; other receive errors
+   * (e.g. a `relaySendBytes` frame) are thrown from the loop. Breaking out leaves the transport open.
+   */
+  frames(): AsyncGenerator<QuicRelayDataFrame, void, undefined> {
+    return this.iterate(() => this.recvRelayData());
+  }
+
+  /** `frames()` for payloads sent with `relaySendE2ee`, decrypted as by `recvRelayDataE2ee`. */
+  framesE2ee(sharedKey: Buffer, aad?: Buffer): AsyncGenerator<QuicRelayDataFrame, void, undefined> {
+    return this.iterate(() => this.recvRelayDataE2ee(sharedKey, aad));
+  }
+
+  private async *iterate<T>(recv: () => Promise<T | null>): AsyncGenerator<T, void, undefined> {
+    for (;;) {
+      let frame: T | null;
+      try {
+        frame = await recv();
+      } catch (err) {
+        if (this.closeReason() !== null) {
+          return;
+        }
+        throw err;
+      }
+      if (frame === null) {
+        return;
+      }
+      yield frame;
+    }
+  }
+
+  /** Resolves once the connection to the relay has closed, for any reason. */
+  async closed(): Promise<QuicCloseReason> {
+    return parseJson<QuicCloseReason>(await this.native.closed());
+  }
+
+  /** The close reason if the relay connection has already closed, else `null`. */
+  closeReason(): QuicCloseReason | null {
+    const closeReason = resolveMethod(this.native, ["close_reason", "closeReason"]);
+    const raw = closeReason();
+    return raw == null ? null : parseJson<QuicCloseReason>(raw);
   }
 
   close(errorCode = 0, reason?: string): void {
@@ -297,7 +1826,7 @@ export class E2eeKeyPair {
   private readonly native: any;
 
   private constructor(native: any) {
-    this.native = native;
+    this.native = withErrorCodes(native);
   }
 
   static generate(): E2eeKeyPair {
@@ -314,28 +1843,204 @@ export class E2eeKeyPair {
     return publicKeyB64();
   }
 
+  /**
+   * Throws `INVALID_ARGUMENT` when `peerPublicKeyB64` is not base64, not 32 bytes, or a
+   * low-order X25519 point; the message says which.
+   */
   deriveRelaySharedKey(peerPublicKeyB64: string, sessionId: string): Buffer {
     const deriveRelaySharedKey = resolveMethod(this.native, ["derive_relay_shared_key", "deriveRelaySharedKey"]);
     return deriveRelaySharedKey(peerPublicKeyB64, sessionId);
   }
+
+  /**
+   * Derives the next key in a one-way ratchet from `currentKey`, so both peers can rotate a
+   * long-lived session's shared key in lockstep without a new exchange. Earlier keys cannot
+   * be recovered from later ones.
+   */
+  rotateRelaySharedKey(currentKey: Buffer, sessionId: string): Buffer {
+    const rotateRelaySharedKey = resolveMethod(this.native, ["rotate_relay_shared_key", "rotateRelaySharedKey"]);
+    return rotateRelaySharedKey(currentKey, sessionId);
+  }
+
+  /** Decrypts the payload of a relay frame sent with `relaySendFs` to this key pair. */
+  openForwardSecret(sessionId: string, payload: unknown): unknown {
+    const openForwardSecret = resolveMethod(this.native, ["open_forward_secret", "openForwardSecret"]);
+    return parseJson<unknown>(openForwardSecret(sessionId, stringifyJson(payload)));
+  }
+}
+
+/**
+ * Packs an offer into a versioned binary form about half the size of its JSON, for
+ * size-constrained signaling channels. Decoders skip optional fields they do not know.
+ */
+export function offerToBytes(offer: QuicOffer): Buffer {
+  const native = loadNative();
+  const offerToBytesNative =
+    typeof (native as any).offer_to_bytes === "function" ? (native as any).offer_to_bytes : (native as any).offerToBytes;
+  return offerToBytesNative(stringifyJson(offer));
+}
+
+export function offerFromBytes(bytes: Buffer): QuicOffer {
+  const native = loadNative();
+  const offerFromBytesNative =
+    typeof (native as any).offer_from_bytes === "function"
+      ? (native as any).offer_from_bytes
+      : (native as any).offerFromBytes;
+  return parseJson<QuicOffer>(offerFromBytesNative(bytes));
 }
 
-export function encryptRelayPayload(sharedKey: Buffer, sessionId: string, plaintext: unknown): unknown {
+/**
+ * Derives the same session id on both peers from their two E2EE public keys, without any
+ * negotiation. Order of the arguments does not matter; the hashing is documented natively.
+ */
+export function deriveSessionId(myPublicB64: string, peerPublicB64: string): string {
+  const native = loadNative();
+  const deriveSessionIdNative =
+    typeof (native as any).derive_session_id === "function"
+      ? (native as any).derive_session_id
+      : (native as any).deriveSessionId;
+  return deriveSessionIdNative(myPublicB64, peerPublicB64);
+}
+
+/**
+ * `aad` is authenticated but not part of the output; decryption needs the same bytes. `seq`
+ * adds an authenticated sequence number for `RelayReplayGuard`; keep it increasing per session.
+ * `seq` needs `sender`, an id for this side that the peer knows (e.g. this side's E2EE public
+ * key), so the peer's guard can tell both directions apart and reject reflected payloads.
+ */
+export function encryptRelayPayload(
+  sharedKey: Buffer,
+  sessionId: string,
+  plaintext: unknown,
+  aad?: Buffer,
+  seq?: number,
+  sender?: string,
+): unknown {
   const native = loadNative();
   const encryptRelayPayloadNative =
     typeof (native as any).encrypt_relay_payload === "function"
       ? (native as any).encrypt_relay_payload
       : (native as any).encryptRelayPayload;
-  const raw = encryptRelayPayloadNative(sharedKey, sessionId, stringifyJson(plaintext));
+  const raw = encryptRelayPayloadNative(sharedKey, sessionId, stringifyJson(plaintext), aad, seq, sender);
   return parseJson(raw);
 }
 
-export function decryptRelayPayload(sharedKey: Buffer, sessionId: string, payload: unknown): unknown {
+export function decryptRelayPayload(sharedKey: Buffer, sessionId: string, payload: unknown, aad?: Buffer): unknown {
   const native = loadNative();
   const decryptRelayPayloadNative =
     typeof (native as any).decrypt_relay_payload === "function"
       ? (native as any).decrypt_relay_payload
       : (native as any).decryptRelayPayload;
-  const raw = decryptRelayPayloadNative(sharedKey, sessionId, stringifyJson(payload));
+  const raw = decryptRelayPayloadNative(sharedKey, sessionId, stringifyJson(payload), aad);
+  return parseJson(raw);
+}
+
+/**
+ * `encryptRelayPayload` with the JSON and crypto work on a native worker thread, so large
+ * payloads do not block the event loop. For small payloads the sync version is cheaper.
+ */
+export async function encryptRelayPayloadAsync(
+  sharedKey: Buffer,
+  sessionId: string,
+  plaintext: unknown,
+  aad?: Buffer,
+  seq?: number,
+  sender?: string,
+): Promise<unknown> {
+  const native = loadNative();
+  const encryptRelayPayloadAsyncNative =
+    typeof (native as any).encrypt_relay_payload_async === "function"
+      ? (native as any).encrypt_relay_payload_async
+      : (native as any).encryptRelayPayloadAsync;
+  const raw = await encryptRelayPayloadAsyncNative(
+    sharedKey,
+    sessionId,
+    stringifyJson(plaintext),
+    aad,
+    seq,
+    sender,
+  );
+  return parseJson(raw);
+}
+
+/** `decryptRelayPayload` off the event loop, like `encryptRelayPayloadAsync`. */
+export async function decryptRelayPayloadAsync(
+  sharedKey: Buffer,
+  sessionId: string,
+  payload: unknown,
+  aad?: Buffer,
+): Promise<unknown> {
+  const native = loadNative();
+  const decryptRelayPayloadAsyncNative =
+    typeof (native as any).decrypt_relay_payload_async === "function"
+      ? (native as any).decrypt_relay_payload_async
+      : (native as any).decryptRelayPayloadAsync;
+  const raw = await decryptRelayPayloadAsyncNative(sharedKey, sessionId, stringifyJson(payload), aad);
   return parseJson(raw);
 }
+
+/**
+ * Decrypts payloads from `encryptRelayPayload` with a `seq`, rejecting (`INVALID_ARGUMENT`)
+ * any whose `seq` is not above the last one accepted for its session and sender, so replays
+ * fail. `ownSender` is the `sender` this side encrypts with; payloads carrying it were
+ * reflected back and are rejected too. The state is per guard and in memory only.
+ */
+export class RelayReplayGuard {
+  private readonly native: any;
+
+  constructor(ownSender: string) {
+    this.native = withErrorCodes(loadNative().RelayReplayGuard.create(ownSender));
+  }
+
+  decrypt(sharedKey: Buffer, sessionId: string, payload: unknown, aad?: Buffer): unknown {
+    return parseJson(this.native.decrypt(sharedKey, sessionId, stringifyJson(payload), aad));
+  }
+
+  /** Drops the sequences kept for `sessionId`, e.g. once the session has ended. */
+  forget(sessionId: string): void {
+    this.native.forget(sessionId);
+  }
+}
+
+/**
+ * Checks that `proof` (from the peer's `keyBindingProof`) signs `e2eePublicB64` with the key of
+ * `certDer`, e.g. the offer's certificate, so a relay cannot swap in its own E2EE key.
+ * Pass `Buffer.from(offer.cert_der_b64, "base64")` for an offer's certificate.
+ */
+export function verifyKeyBinding(certDer: Buffer, e2eePublicB64: string, proof: Buffer): boolean {
+  const native = loadNative();
+  const verifyKeyBindingNative =
+    typeof (native as any).verify_key_binding === "function"
+      ? (native as any).verify_key_binding
+      : (native as any).verifyKeyBinding;
+  return verifyKeyBindingNative(certDer, e2eePublicB64, proof);
+}
+
+/**
+ * Selects the TLS crypto provider for the process. Call before creating any endpoint; throws if
+ * a provider is already installed. Defaults to ring when never called.
+ */
+export function initCrypto(provider: QuicCryptoProvider): void {
+  const native = loadNative();
+  const initCryptoNative =
+    typeof (native as any).init_crypto === "function" ? (native as any).init_crypto : (native as any).initCrypto;
+  initCryptoNative(provider);
+}
+
+/** Reports the active TLS crypto provider and its enabled cipher suites and key exchange groups. */
+export function cryptoInfo(): QuicCryptoInfo {
+  const native = loadNative();
+  const cryptoInfoNative =
+    typeof (native as any).crypto_info === "function" ? (native as any).crypto_info : (native as any).cryptoInfo;
+  return parseJson<QuicCryptoInfo>(cryptoInfoNative());
+}
+
+/** Returns the normalized form of a socket address using the native parser, or throws if invalid. */
+export function validateSocketAddr(value: string): string {
+  const native = loadNative();
+  const validateSocketAddrNative =
+    typeof (native as any).validate_socket_addr === "function"
+      ? (native as any).validate_socket_addr
+      : (native as any).validateSocketAddr;
+  return validateSocketAddrNative(value);
+}
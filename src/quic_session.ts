@@ -161,6 +161,9 @@ export async function initiateQuicSessionDirectFirst(
 
   try {
     const connection = await acceptPromise;
+    if (!connection) {
+      throw new Error("direct QUIC server closed");
+    }
 
     // We still want the session id for later bookkeeping; relayEvent will have it.
     const relayEvent = await withTimeout(relayEventPromise, 2000, "session id");
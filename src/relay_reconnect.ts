@@ -0,0 +1,274 @@
+import type {
+  QuicCloseReason,
+  QuicRelayBindOptions,
+  QuicRelayBindResult,
+  QuicRelayBytesFrame,
+  QuicRelayClient,
+  QuicRelayDataFrame,
+  QuicRelayInfo,
+  QuicRelaySendOptions,
+  QuicRelayTransport,
+  QuicRelayTransportOptions,
+} from "./quic.js";
+
+export type RelayReconnectEvent = {
+  /** The attempt that succeeded, counting from `1` for each outage. */
+  attempt: number;
+  /** Why the previous connection ended. */
+  reason: QuicCloseReason;
+  /** Outcome of binding the previously bound sessions again, as from `relayBindMulti`. */
+  rebound: QuicRelayBindResult[];
+};
+
+export type ReconnectingRelayOptions = {
+  transport?: QuicRelayTransportOptions;
+  /** Delay before the first reconnect attempt; doubles after each failed one. Default `250`. */
+  initialDelayMs?: number;
+  /** Upper bound for the delay between attempts. Default `30000`. */
+  maxDelayMs?: number;
+  /** Attempts per outage before giving up; `0` retries forever. Default `10`. */
+  maxAttempts?: number;
+  onDisconnect?(reason: QuicCloseReason): void;
+  onReconnect?(event: RelayReconnectEvent): void;
+  /** Called once reconnecting gives up; every pending and later operation rejects with `error`. */
+  onGiveUp?(error: Error): void;
+};
+
+const DEFAULT_INITIAL_DELAY_MS = 250;
+const DEFAULT_MAX_DELAY_MS = 30_000;
+const DEFAULT_MAX_ATTEMPTS = 10;
+
+function sleep(ms: number): Promise<void> {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+/**
+ * A `QuicRelayTransport` that survives connection loss. When the relay connection closes
+ * (other than through `close()`), it reconnects with the original `QuicRelayInfo` using
+ * exponential backoff and binds the sessions bound through it again. Operations issued during
+ * an outage, and operations that failed because of it, wait for the reconnect and are retried,
+ * so a send may be delivered twice if the relay received it just before the connection died.
+ */
+export class ReconnectingRelayTransport {
+  private current: QuicRelayTransport;
+  private reconnecting: Promise<QuicRelayTransport> | null = null;
+  private failure: Error | null = null;
+  private closedByUser = false;
+  private receivingStopped = false;
+  /** Session id to token, for every session to bind again after a reconnect. */
+  private readonly bindings = new Map<string, string>();
+
+  private constructor(
+    private readonly client: QuicRelayClient,
+    private readonly relay: QuicRelayInfo,
+    private readonly options: ReconnectingRelayOptions,
+    transport: QuicRelayTransport,
+  ) {
+    this.current = transport;
+    this.watch(transport);
+  }
+
+  static async connect(
+    client: QuicRelayClient,
+    relay: QuicRelayInfo,
+    options: ReconnectingRelayOptions = {},
+  ): Promise<ReconnectingRelayTransport> {
+    const transport = await client.connectTransport(relay, options.transport);
+    return new ReconnectingRelayTransport(client, relay, options, transport);
+  }
+
+  /** The live transport. It is replaced on every reconnect, so do not hold on to it. */
+  get transport(): QuicRelayTransport {
+    return this.current;
+  }
+
+  async relayBind(token: string, sessionId: string, options?: QuicRelayBindOptions): Promise<boolean> {
+    const peerReady = await this.run((transport) => transport.relayBind(token, sessionId, options));
+    this.bindings.set(sessionId, token);
+    return peerReady;
+  }
+
+  async relayBindMulti(token: string, sessionIds: string[]): Promise<QuicRelayBindResult[]> {
+    const results = await this.run((transport) => transport.relayBindMulti(token, sessionIds));
+    for (const result of results) {
+      if ("peer_ready" in result) {
+        this.bindings.set(result.session_id, token);
+      }
+    }
+    return results;
+  }
+
+  async relayUnbind(token: string, sessionId: string): Promise<boolean> {
+    const unbound = await this.run((transport) => transport.relayUnbind(token, sessionId));
+    this.bindings.delete(sessionId);
+    return unbound;
+  }
+
+  async relaySend(token: string, sessionId: string, payload: unknown, options?: QuicRelaySendOptions): Promise<void> {
+    await this.run((transport) => transport.relaySend(token, sessionId, payload, options));
+  }
+
+  async relaySendBytes(token: string, sessionId: string, payload: Buffer): Promise<void> {
+    await this.run((transport) => transport.relaySendBytes(token, sessionId, payload));
+  }
+
+  async relaySendE2ee(
+    token: string,
+    sessionId: string,
+    sharedKey: Buffer,
+    plaintext: unknown,
+    aad?: Buffer,
+    options?: QuicRelaySendOptions,
+  ): Promise<void> {
+    await this.run((transport) => transport.relaySendE2ee(token, sessionId, sharedKey, plaintext, aad, options));
+  }
+
+  /** Resolves `null` once `stopReceiving()` has been called, across reconnects. */
+  async recvRelayData(): Promise<QuicRelayDataFrame | null> {
+    return this.receive((transport) => transport.recvRelayData());
+  }
+
+  async recvRelayDataBytes(): Promise<QuicRelayBytesFrame | null> {
+    return this.receive((transport) => transport.recvRelayDataBytes());
+  }
+
+  async recvRelayDataE2ee(sharedKey: Buffer, aad?: Buffer): Promise<QuicRelayDataFrame | null> {
+    return this.receive((transport) => transport.recvRelayDataE2ee(sharedKey, aad));
+  }
+
+  /**
+   * Like `QuicRelayTransport.frames()`, but reconnects instead of ending when the connection
+   * drops: the loop ends after `stopReceiving()` and throws after `close()` or giving up.
+   */
+  async *frames(): AsyncGenerator<QuicRelayDataFrame, void, undefined> {
+    for (let frame = await this.recvRelayData(); frame !== null; frame = await this.recvRelayData()) {
+      yield frame;
+    }
+  }
+
+  async *framesE2ee(sharedKey: Buffer, aad?: Buffer): AsyncGenerator<QuicRelayDataFrame, void, undefined> {
+    const recv = () => this.recvRelayDataE2ee(sharedKey, aad);
+    for (let frame = await recv(); frame !== null; frame = await recv()) {
+      yield frame;
+    }
+  }
+
+  stopReceiving(): void {
+    this.receivingStopped = true;
+    this.current.stopReceiving();
+  }
+
+  /** Closes the relay connection for good; pending operations waiting on a reconnect reject. */
+  close(errorCode = 0, reason?: string): void {
+    this.closedByUser = true;
+    this.current.close(errorCode, reason);
+  }
+
+  private async receive<T>(op: (transport: QuicRelayTransport) => Promise<T | null>): Promise<T | null> {
+    if (this.receivingStopped) {
+      return null;
+    }
+    return this.run(op);
+  }
+
+  /** Runs `op` on the live transport, waiting out and retrying across reconnects. */
+  private async run<T>(op: (transport: QuicRelayTransport) => Promise<T>): Promise<T> {
+    for (;;) {
+      const transport = await this.ready();
+      try {
+        return await op(transport);
+      } catch (err) {
+        // Errors on an open connection are the operation's own.
+        const reason = transport.closeReason();
+        if (this.closedByUser || reason === null) {
+          throw err;
+        }
+        this.disconnected(transport, reason);
+      }
+    }
+  }
+
+  private ready(): Promise<QuicRelayTransport> {
+    if (this.failure) {
+      return Promise.reject(this.failure);
+    }
+    if (this.closedByUser) {
+      return Promise.reject(new Error("relay transport closed"));
+    }
+    return this.reconnecting ?? Promise.resolve(this.current);
+  }
+
+  private watch(transport: QuicRelayTransport): void {
+    transport.closed().then(
+      (reason) => this.disconnected(transport, reason),
+      () => undefined,
+    );
+  }
+
+  /** Starts reconnecting, once per outage: later calls for the same transport are ignored. */
+  private disconnected(transport: QuicRelayTransport, reason: QuicCloseReason): void {
+    if (this.closedByUser || this.failure || this.reconnecting || transport !== this.current) {
+      return;
+    }
+    this.options.onDisconnect?.(reason);
+    const reconnecting = this.reconnect(reason);
+    this.reconnecting = reconnecting;
+    reconnecting.then(
+      () => {
+        this.reconnecting = null;
+      },
+      () => undefined,
+    );
+  }
+
+  private async reconnect(reason: QuicCloseReason): Promise<QuicRelayTransport> {
+    const initialDelayMs = this.options.initialDelayMs ?? DEFAULT_INITIAL_DELAY_MS;
+    const maxDelayMs = this.options.maxDelayMs ?? DEFAULT_MAX_DELAY_MS;
+    const maxAttempts = this.options.maxAttempts ?? DEFAULT_MAX_ATTEMPTS;
+
+    let lastError: unknown;
+    for (let attempt = 1; maxAttempts === 0 || attempt <= maxAttempts; attempt += 1) {
+      await sleep(Math.min(initialDelayMs * 2 ** (attempt - 1), maxDelayMs));
+      if (this.closedByUser) {
+        throw new Error("relay transport closed");
+      }
+      let transport: QuicRelayTransport;
+      try {
+        transport = await this.client.connectTransport(this.relay, this.options.transport);
+      } catch (err) {
+        lastError = err;
+        continue;
+      }
+      if (this.receivingStopped) {
+        transport.stopReceiving();
+      }
+      const rebound = await this.rebind(transport);
+      this.current = transport;
+      this.watch(transport);
+      this.options.onReconnect?.({ attempt, reason, rebound });
+      return transport;
+    }
+
+    const detail = lastError instanceof Error ? `: ${lastError.message}` : "";
+    this.failure = new Error(`relay reconnect gave up after ${maxAttempts} attempts${detail}`);
+    this.options.onGiveUp?.(this.failure);
+    throw this.failure;
+  }
+
+  private async rebind(transport: QuicRelayTransport): Promise<QuicRelayBindResult[]> {
+    const byToken = new Map<string, string[]>();
+    for (const [sessionId, token] of this.bindings) {
+      byToken.set(token, [...(byToken.get(token) ?? []), sessionId]);
+    }
+    const rebound: QuicRelayBindResult[] = [];
+    for (const [token, sessionIds] of byToken) {
+      try {
+        rebound.push(...(await transport.relayBindMulti(token, sessionIds)));
+      } catch (err) {
+        const error = err instanceof Error ? err.message : String(err);
+        rebound.push(...sessionIds.map((sessionId) => ({ session_id: sessionId, error })));
+      }
+    }
+    return rebound;
+  }
+}
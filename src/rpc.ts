@@ -0,0 +1,281 @@
+import type { QuicBiStream, QuicDatagramFlow } from "./quic.js";
+
+/** Per-request view handed to the handler alongside the payload. */
+export type RpcRequestContext = {
+  id: number;
+  /**
+   * Receives best-effort hints the caller attaches to this request while it is in flight.
+   * Hints that arrived before the handler subscribed are replayed.
+   */
+  onHint(listener: (hint: Buffer) => void): void;
+};
+
+export type RpcHandler = (payload: Buffer, context: RpcRequestContext) => Buffer | Promise<Buffer>;
+
+/** One in-flight request started with `RpcChannel.begin()`. */
+export type RpcCall = {
+  id: number;
+  response: Promise<Buffer>;
+  /** Sends an unreliable hint (a cancellation, a priority bump) tagged to this request. */
+  hint(payload: Buffer): void;
+};
+
+export type RpcChannelOptions = {
+  /** Default per-request timeout; `0` disables it. */
+  timeoutMs?: number;
+  /** Serves requests sent by the peer. Without one, inbound requests are answered with an error. */
+  handler?: RpcHandler;
+  /** Upper bound for a single frame, guarding against a peer claiming an enormous length. */
+  maxFrameBytes?: number;
+  /**
+   * Datagram flow carrying request hints, opened with the same flow id on both sides. Without
+   * one, `hint()` throws and inbound hints are not read.
+   */
+  hints?: QuicDatagramFlow;
+};
+
+/**
+ * Frame layout (all integers big-endian):
+ * - u32 length of the remainder
+ * - u8 kind (`0` request, `1` response, `2` error response carrying a UTF-8 message)
+ * - u32 correlation id
+ * - payload
+ */
+const FRAME_HEADER_BYTES = 9;
+const KIND_REQUEST = 0;
+const KIND_RESPONSE = 1;
+const KIND_ERROR = 2;
+
+/**
+ * Hint datagram layout: u32 request id, then the hint payload. Datagrams can overtake the
+ * request frame, so hints for ids not yet seen are held for a bounded number of ids.
+ */
+const HINT_HEADER_BYTES = 4;
+const MAX_EARLY_HINT_IDS = 64;
+
+type ServedRequest = {
+  hints: Buffer[];
+  listener?: (hint: Buffer) => void;
+};
+
+type PendingRequest = {
+  resolve: (payload: Buffer) => void;
+  reject: (err: Error) => void;
+  timer?: NodeJS.Timeout;
+};
+
+function encodeFrame(kind: number, id: number, payload: Buffer): Buffer {
+  const header = Buffer.alloc(FRAME_HEADER_BYTES);
+  header.writeUInt32BE(FRAME_HEADER_BYTES - 4 + payload.length, 0);
+  header.writeUInt8(kind, 4);
+  header.writeUInt32BE(id, 5);
+  return Buffer.concat([header, payload]);
+}
+
+/**
+ * Multiplexes concurrent request/response exchanges over a single bi stream. Either side may
+ * issue requests; responses are routed back to the awaiting caller by correlation id.
+ */
+export class RpcChannel {
+  private readonly stream: QuicBiStream;
+  private readonly timeoutMs: number;
+  private readonly handler?: RpcHandler;
+  private readonly maxFrameBytes: number;
+  private readonly hintFlow?: QuicDatagramFlow;
+  private readonly pending = new Map<number, PendingRequest>();
+  private readonly served = new Map<number, ServedRequest>();
+  private readonly earlyHints = new Map<number, Buffer[]>();
+  private nextId = 1;
+  private closedError?: Error;
+
+  /** Resolves once the read loop ends, after every pending request has been rejected. */
+  readonly closed: Promise<void>;
+
+  constructor(stream: QuicBiStream, options: RpcChannelOptions = {}) {
+    this.stream = stream;
+    this.timeoutMs = options.timeoutMs ?? 30_000;
+    this.handler = options.handler;
+    this.maxFrameBytes = options.maxFrameBytes ?? 16 * 1024 * 1024;
+    this.hintFlow = options.hints;
+    this.closed = this.readLoop();
+    if (this.hintFlow) {
+      void this.hintLoop(this.hintFlow);
+    }
+  }
+
+  async request(payload: Buffer, timeoutMs: number = this.timeoutMs): Promise<Buffer> {
+    return this.begin(payload, timeoutMs).response;
+  }
+
+  /** Like `request()`, but exposes the request id so hints can be attached while it runs. */
+  begin(payload: Buffer, timeoutMs: number = this.timeoutMs): RpcCall {
+    if (this.closedError) {
+      return { id: 0, response: Promise.reject(this.closedError), hint: () => undefined };
+    }
+    const id = this.nextId;
+    this.nextId = this.nextId >= 0xffffffff ? 1 : this.nextId + 1;
+
+    const response = new Promise<Buffer>((resolve, reject) => {
+      const entry: PendingRequest = { resolve, reject };
+      if (timeoutMs > 0) {
+        entry.timer = setTimeout(() => {
+          this.pending.delete(id);
+          reject(new Error(`Timed out (rpc request ${id})`));
+        }, timeoutMs);
+      }
+      this.pending.set(id, entry);
+    });
+
+    this.stream.write(encodeFrame(KIND_REQUEST, id, payload)).catch((err) => {
+      this.settle(id)?.reject(err as Error);
+    });
+    return { id, response, hint: (hint) => this.sendHint(id, hint) };
+  }
+
+  /** Best-effort: the hint is silently lost if the datagram is dropped or the request finished. */
+  sendHint(id: number, payload: Buffer): void {
+    if (!this.hintFlow) {
+      throw new Error("RPC channel has no hint flow");
+    }
+    if (!this.pending.has(id)) {
+      return;
+    }
+    const header = Buffer.alloc(HINT_HEADER_BYTES);
+    header.writeUInt32BE(id, 0);
+    this.hintFlow.send(Buffer.concat([header, payload]));
+  }
+
+  /** Finishes the send half; the channel closes once the peer finishes its side. */
+  async close(): Promise<void> {
+    await this.stream.finish();
+  }
+
+  private settle(id: number): PendingRequest | undefined {
+    const entry = this.pending.get(id);
+    if (!entry) {
+      return undefined;
+    }
+    this.pending.delete(id);
+    if (entry.timer) {
+      clearTimeout(entry.timer);
+    }
+    return entry;
+  }
+
+  private async readLoop(): Promise<void> {
+    let buffered = Buffer.alloc(0);
+    try {
+      while (true) {
+        const chunk = await this.stream.read();
+        if (chunk === null) {
+          break;
+        }
+        buffered = buffered.length ? Buffer.concat([buffered, chunk]) : chunk;
+
+        while (buffered.length >= 4) {
+          const length = buffered.readUInt32BE(0);
+          if (length < FRAME_HEADER_BYTES - 4 || length > this.maxFrameBytes) {
+            throw new Error(`Invalid rpc frame length ${length}`);
+          }
+          if (buffered.length < 4 + length) {
+            break;
+          }
+          const kind = buffered.readUInt8(4);
+          const id = buffered.readUInt32BE(5);
+          const payload = buffered.subarray(FRAME_HEADER_BYTES, 4 + length);
+          buffered = buffered.subarray(4 + length);
+          this.dispatch(kind, id, Buffer.from(payload));
+        }
+      }
+      this.failPending(new Error("RPC channel closed"));
+    } catch (err) {
+      this.failPending(err instanceof Error ? err : new Error(String(err)));
+    }
+  }
+
+  private async hintLoop(flow: QuicDatagramFlow): Promise<void> {
+    while (!this.closedError) {
+      let datagram: Buffer;
+      try {
+        datagram = await flow.recv();
+      } catch {
+        return;
+      }
+      if (datagram.length < HINT_HEADER_BYTES) {
+        continue;
+      }
+      this.deliverHint(datagram.readUInt32BE(0), Buffer.from(datagram.subarray(HINT_HEADER_BYTES)));
+    }
+  }
+
+  private deliverHint(id: number, hint: Buffer): void {
+    const served = this.served.get(id);
+    if (served) {
+      if (served.listener) {
+        served.listener(hint);
+      } else {
+        served.hints.push(hint);
+      }
+      return;
+    }
+    const early = this.earlyHints.get(id);
+    if (early) {
+      early.push(hint);
+      return;
+    }
+    if (this.earlyHints.size >= MAX_EARLY_HINT_IDS) {
+      this.earlyHints.delete(this.earlyHints.keys().next().value as number);
+    }
+    this.earlyHints.set(id, [hint]);
+  }
+
+  private dispatch(kind: number, id: number, payload: Buffer): void {
+    if (kind === KIND_REQUEST) {
+      void this.serve(id, payload);
+    } else if (kind === KIND_RESPONSE) {
+      this.settle(id)?.resolve(payload);
+    } else if (kind === KIND_ERROR) {
+      this.settle(id)?.reject(new Error(payload.toString("utf-8")));
+    }
+  }
+
+  private async serve(id: number, payload: Buffer): Promise<void> {
+    const served: ServedRequest = { hints: this.earlyHints.get(id) ?? [] };
+    this.earlyHints.delete(id);
+    this.served.set(id, served);
+    const context: RpcRequestContext = {
+      id,
+      onHint: (listener) => {
+        served.listener = listener;
+        for (const hint of served.hints.splice(0)) {
+          listener(hint);
+        }
+      },
+    };
+
+    let frame: Buffer;
+    try {
+      if (!this.handler) {
+        throw new Error("No rpc handler registered");
+      }
+      frame = encodeFrame(KIND_RESPONSE, id, await this.handler(payload, context));
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err);
+      frame = encodeFrame(KIND_ERROR, id, Buffer.from(message, "utf-8"));
+    } finally {
+      this.served.delete(id);
+    }
+    try {
+      await this.stream.write(frame);
+    } catch {
+      // The read loop observes the broken stream and fails pending requests.
+    }
+  }
+
+  private failPending(err: Error): void {
+    this.closedError = err;
+    for (const id of [...this.pending.keys()]) {
+      this.settle(id)?.reject(err);
+    }
+  }
+}
@@ -0,0 +1,499 @@
+/// <reference types="node" />
+import assert from "node:assert/strict";
+
+import {
+  encryptRelayPayload,
+  QuicBiStream,
+  QuicPeerClient,
+  QuicPeerConnection,
+  QuicPeerServer,
+  QuicRelayClient,
+  RelayReplayGuard,
+} from "../src/index.js";
+
+type Pair = {
+  server: QuicPeerServer;
+  client: QuicPeerClient;
+  serverConn: QuicPeerConnection;
+  clientConn: QuicPeerConnection;
+};
+
+type CodedError = Error & { code?: string };
+
+const SESSION_ID = "6f9619ff-8b86-d011-b42d-00c04fc964ff";
+
+async function main(): Promise<void> {
+  const pair = await connectPair();
+  try {
+    await verifyZeroLengthIo(pair);
+    await verifyConcurrentWriteOrder(pair);
+    await verifyEncryptedStream(pair);
+    await verifyE2eeReflection(pair);
+    await verifyDatagramDemux(pair);
+    await verifyConcurrentAccepts();
+    await verifyConcurrentConnects();
+    await verifyAcceptTimeoutMidHandshake();
+    verifyOfferDefaults();
+    await verifyFingerprintValidation();
+    await verifyPinMismatch();
+    await verifyClientHello();
+    await verifyEarlyData();
+    await verifyOpenLimitCoversWrappedStreams();
+    await verifyDatagramDropOldest();
+    verifyRelayReplayGuard();
+    await verifyRelayFraming();
+    // Rebinds the client, so it runs last.
+    await verifyAddressAfterMigration(pair);
+
+    console.log("✅ JS SDK QUIC stream scenario completed successfully");
+  } finally {
+    pair.clientConn.close();
+    pair.serverConn.close();
+    pair.server.close();
+  }
+}
+
+function hasCode(code: string, message?: RegExp): (err: CodedError) => boolean {
+  return (err) => {
+    assert.equal(err.code, code);
+    if (message) {
+      assert.match(err.message, message);
+    }
+    return true;
+  };
+}
+
+async function connectPair(): Promise<Pair> {
+  return connectTo(QuicPeerServer.create("127.0.0.1:0"), QuicPeerClient.create("127.0.0.1:0"));
+}
+
+async function connectTo(server: QuicPeerServer, client: QuicPeerClient): Promise<Pair> {
+  const [serverConn, clientConn] = await Promise.all([server.accept(), client.connect(server.offer())]);
+  if (!serverConn) {
+    throw new Error("server closed before accepting");
+  }
+  return { server, client, serverConn, clientConn };
+}
+
+async function openStreamPair(pair: Pair): Promise<[QuicBiStream, QuicBiStream]> {
+  const local = await pair.clientConn.openBi();
+  // Streams are only announced to the peer once data flows.
+  await local.write(Buffer.from([0]));
+  const remote = await pair.serverConn.acceptBi();
+  assert.deepEqual(await remote.read(1), Buffer.from([0]));
+  return [local, remote];
+}
+
+async function verifyZeroLengthIo(pair: Pair): Promise<void> {
+  const [local, remote] = await openStreamPair(pair);
+
+  await local.write(Buffer.alloc(0));
+  const empty = await remote.read(0);
+  assert(empty !== null && empty.length === 0, "max_bytes 0 should yield an empty buffer");
+
+  await local.write(Buffer.from("abc"));
+  await local.finish();
+  assert.equal((await remote.read())?.toString("utf-8"), "abc", "empty write must not emit data");
+  assert.equal(await remote.read(), null, "expected FIN after data");
+  assert.equal(await remote.read(0), null, "max_bytes 0 after FIN should yield null");
+
+  await assert.rejects(local.write(Buffer.alloc(0)), /send stream closed/);
+}
+
+async function verifyConcurrentWriteOrder(pair: Pair): Promise<void> {
+  const [local, remote] = await openStreamPair(pair);
+  const writers = 500;
+
+  const writes: Promise<void>[] = [];
+  for (let i = 0; i < writers; i++) {
+    const record = Buffer.alloc(4);
+    record.writeUInt32BE(i);
+    writes.push(local.write(record));
+  }
+  const finished = local.finish();
+
+  const chunks: Buffer[] = [];
+  for (;;) {
+    const chunk = await remote.read();
+    if (chunk === null) {
+      break;
+    }
+    chunks.push(chunk);
+  }
+  await Promise.all([...writes, finished]);
+
+  const received = Buffer.concat(chunks);
+  assert.equal(received.length, writers * 4, "every concurrent write should arrive");
+  for (let i = 0; i < writers; i++) {
+    assert.equal(received.readUInt32BE(i * 4), i, `write ${i} arrived out of order`);
+  }
+}
+
+async function verifyEncryptedStream(pair: Pair): Promise<void> {
+  const key = Buffer.alloc(32, 1);
+  const local = await pair.clientConn.openBiEncrypted(key);
+  await local.write(Buffer.from("ping"));
+  const remote = await pair.serverConn.acceptBiEncrypted(key);
+  assert.equal((await remote.read())?.toString("utf-8"), "ping");
+
+  await remote.write(Buffer.from("pong"));
+  assert.equal((await local.read())?.toString("utf-8"), "pong");
+
+  await local.finish();
+  assert.equal(await remote.read(), null, "expected FIN after the last message");
+}
+
+async function verifyE2eeReflection(pair: Pair): Promise<void> {
+  const key = Buffer.alloc(32, 2);
+  const local = await pair.clientConn.openBi();
+  await local.writeE2ee(key, Buffer.from("secret"));
+  const remote = await pair.serverConn.acceptBi();
+  const frame = await remote.read();
+  assert(frame !== null);
+
+  // Both directions share the key and start at sequence 0, so only the sender's role in the
+  // associated data tells the echoed frame apart from one the peer sealed.
+  await remote.write(frame);
+  await assert.rejects(local.readE2ee(key), /decryption failed/);
+}
+
+async function verifyDatagramDemux(pair: Pair): Promise<void> {
+  const localFlow = pair.clientConn.openDatagramFlow(7);
+  const remoteFlow = pair.serverConn.openDatagramFlow(7);
+  const responded = pair.serverConn
+    .acceptDatagramExchange()
+    .then((request) => request.respond(Buffer.concat([Buffer.from("re:"), request.payload()])));
+
+  pair.clientConn.sendDatagram(Buffer.from("raw"));
+  localFlow.send(Buffer.from("flow"));
+  await pair.clientConn.send(Buffer.from("message"), { reliable: false });
+  const reply = await pair.clientConn.datagramExchange(Buffer.from("ask"), 2000);
+  await responded;
+
+  assert.equal(reply.toString("utf-8"), "re:ask");
+  assert.equal((await pair.serverConn.readDatagram())?.toString("utf-8"), "raw");
+  assert.equal((await remoteFlow.recv()).toString("utf-8"), "flow");
+  assert.equal((await pair.serverConn.recv()).toString("utf-8"), "message");
+}
+
+async function verifyConcurrentAccepts(): Promise<void> {
+  const server = QuicPeerServer.create("127.0.0.1:0");
+  const clients = Array.from({ length: 8 }, () => QuicPeerClient.create("127.0.0.1:0"));
+  try {
+    const accepts = clients.map(() => server.accept());
+    const connects = clients.map((client) => client.connect(server.offer()));
+    const accepted = await Promise.all(accepts);
+    const connected = await Promise.all(connects);
+
+    assert(accepted.every((conn) => conn !== null), "every concurrent accept should get a connection");
+    const remotes = new Set(accepted.map((conn) => conn!.remoteAddress()));
+    assert.equal(remotes.size, clients.length, "each accept should get a different connection");
+
+    for (const conn of [...accepted, ...connected]) {
+      conn!.close();
+    }
+  } finally {
+    server.close();
+  }
+}
+
+async function verifyConcurrentConnects(): Promise<void> {
+  const stalled = QuicPeerServer.create("127.0.0.1:0");
+  const ready = QuicPeerServer.create("127.0.0.1:0");
+  const client = QuicPeerClient.create("127.0.0.1:0");
+  try {
+    // Nobody accepts on `stalled` yet, so its handshake cannot finish; another connect from
+    // the same client must still complete meanwhile.
+    const stalledConnect = client.connect(stalled.offer());
+    const [readyAccepted, readyConn] = await Promise.all([ready.accept(), client.connect(ready.offer())]);
+    assert(readyAccepted !== null, "ready server should accept");
+
+    const [stalledAccepted, stalledConn] = await Promise.all([stalled.accept(), stalledConnect]);
+    assert(stalledAccepted !== null, "stalled server should accept once asked");
+
+    for (const conn of [readyAccepted, readyConn, stalledAccepted, stalledConn]) {
+      conn!.close();
+    }
+  } finally {
+    stalled.close();
+    ready.close();
+  }
+}
+
+async function verifyAcceptTimeoutMidHandshake(): Promise<void> {
+  const server = QuicPeerServer.create("127.0.0.1:0");
+  const client = QuicPeerClient.create("127.0.0.1:0");
+  try {
+    const connecting = client.connect(server.offer());
+    // Let the client's first packet queue up, so the handshake outlasts the timeout.
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    const accepted = await server.acceptWithTimeout(1);
+    assert(accepted !== null, "a handshake started before the timeout should complete");
+    const conn = await connecting;
+
+    assert.equal(await server.acceptWithTimeout(20), null, "without a client it should resolve null");
+    accepted.close();
+    conn.close();
+  } finally {
+    server.close();
+  }
+}
+
+function verifyOfferDefaults(): void {
+  const server = QuicPeerServer.create("0.0.0.0:0");
+  try {
+    const offer = server.offer();
+    const port = server.localAddress().split(":").pop();
+    assert.deepEqual(offer.candidates, [`127.0.0.1:${port}`], "unspecified bind should offer loopback");
+    assert.deepEqual(server.offer([]).candidates, offer.candidates, "empty candidates should use the defaults");
+    assert.equal(offer.alpn, "gann-quic-p2p/1");
+    assert.equal(offer.server_name, "gann-peer");
+  } finally {
+    server.close();
+  }
+}
+
+async function verifyFingerprintValidation(): Promise<void> {
+  const server = QuicPeerServer.create("127.0.0.1:0");
+  const client = QuicPeerClient.create("127.0.0.1:0");
+  try {
+    const offer = server.offer();
+    for (const fingerprint of ["", "abc", "zz".repeat(32)]) {
+      await assert.rejects(
+        client.connect({ ...offer, fingerprint_sha256: fingerprint }),
+        hasCode("INVALID_ARGUMENT", /64 hex digits/),
+      );
+    }
+    await assert.rejects(
+      client.connect({ ...offer, fingerprint_sha256: "ab".repeat(32) }),
+      hasCode("INVALID_ARGUMENT", /fingerprint mismatch/),
+    );
+
+    const upper = { ...offer, fingerprint_sha256: offer.fingerprint_sha256.toUpperCase() };
+    const [accepted, conn] = await Promise.all([server.accept(), client.connect(upper)]);
+    assert(accepted !== null, "fingerprints should compare case-insensitively");
+    accepted.close();
+    conn.close();
+  } finally {
+    server.close();
+  }
+}
+
+async function verifyPinMismatch(): Promise<void> {
+  const server = QuicPeerServer.create("127.0.0.1:0");
+  const client = QuicPeerClient.create("127.0.0.1:0", { pinnedCertSha256: "00".repeat(32) });
+  try {
+    // The handshake only reaches certificate verification while the server accepts.
+    server.accept().catch(() => null);
+    await assert.rejects(client.connect(server.offer()), hasCode("HANDSHAKE_FAILED", /pin_mismatch/));
+  } finally {
+    server.close();
+  }
+}
+
+async function verifyClientHello(): Promise<void> {
+  const server = QuicPeerServer.create("127.0.0.1:0");
+  const client = QuicPeerClient.create("127.0.0.1:0");
+  try {
+    const connecting = client.connect(server.offer());
+    const pending = await server.acceptPending();
+    assert.deepEqual(await pending.clientHello(), { server_name: "gann-peer", alpn: "gann-quic-p2p/1" });
+
+    const [accepted, conn] = await Promise.all([pending.accept(), connecting]);
+    accepted.close();
+    conn.close();
+  } finally {
+    server.close();
+  }
+}
+
+async function verifyEarlyData(): Promise<void> {
+  for (const acceptEarlyData of [false, true]) {
+    const server = QuicPeerServer.create("127.0.0.1:0", { acceptEarlyData });
+    const client = QuicPeerClient.create("127.0.0.1:0");
+    try {
+      const primed = await connectTo(server, client);
+      // Session tickets follow the handshake; a stream round trip makes sure they arrived.
+      await openStreamPair(primed);
+      primed.clientConn.close();
+      primed.serverConn.close();
+
+      const [accepted, resumed] = await Promise.all([server.accept(), client.connect0Rtt(server.offer())]);
+      assert.equal(resumed.earlyData, acceptEarlyData, `earlyData with acceptEarlyData ${acceptEarlyData}`);
+      assert.equal(await resumed.accepted, acceptEarlyData, `accepted with acceptEarlyData ${acceptEarlyData}`);
+      accepted?.close();
+      resumed.connection.close();
+    } finally {
+      server.close();
+    }
+  }
+}
+
+async function verifyOpenLimitCoversWrappedStreams(): Promise<void> {
+  const pair = await connectPair();
+  try {
+    pair.clientConn.setOpenStreamLimit(1);
+    const key = Buffer.alloc(32, 3);
+    const encrypted = await pair.clientConn.openBiEncrypted(key);
+
+    let compressedOpened = false;
+    const compressed = pair.clientConn.openBiCompressed("zstd").then((stream) => {
+      compressedOpened = true;
+      return stream;
+    });
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    assert.equal(compressedOpened, false, "the encrypted stream should hold the only slot");
+
+    await encrypted.write(Buffer.from("x"));
+    const remote = await pair.serverConn.acceptBiEncrypted(key);
+    await encrypted.finish();
+    while ((await remote.read()) !== null) {}
+    await remote.finish();
+    assert.equal(await encrypted.read(), null);
+
+    await compressed;
+    assert.equal(compressedOpened, true, "closing both sides should free the slot");
+  } finally {
+    pair.clientConn.close();
+    pair.serverConn.close();
+    pair.server.close();
+  }
+}
+
+async function verifyDatagramDropOldest(): Promise<void> {
+  const pair = await connectPair();
+  try {
+    pair.clientConn.setDatagramQueueLimit(8, "drop_oldest");
+    // Far faster than the congestion window drains, so most of these overflow the cap.
+    const total = 500;
+    for (let i = 0; i < total; i++) {
+      const datagram = Buffer.alloc(1000);
+      datagram.writeUInt32BE(i);
+      pair.clientConn.sendDatagram(datagram);
+    }
+
+    const received: number[] = [];
+    while (received[received.length - 1] !== total - 1) {
+      const datagram = await pair.serverConn.readDatagram();
+      assert(datagram !== null, "the newest datagram should be delivered");
+      received.push(datagram.readUInt32BE(0));
+    }
+    assert(received.length < total, "overflowing datagrams should be discarded");
+    assert.deepEqual(received, [...received].sort((a, b) => a - b), "datagrams should keep their order");
+  } finally {
+    pair.clientConn.close();
+    pair.serverConn.close();
+    pair.server.close();
+  }
+}
+
+function verifyRelayReplayGuard(): void {
+  const key = Buffer.alloc(32, 4);
+  const guard = new RelayReplayGuard("bob");
+  const first = encryptRelayPayload(key, SESSION_ID, { n: 1 }, undefined, 1, "alice");
+  const second = encryptRelayPayload(key, SESSION_ID, { n: 2 }, undefined, 2, "alice");
+
+  assert.deepEqual(guard.decrypt(key, SESSION_ID, first), { n: 1 });
+  assert.throws(() => guard.decrypt(key, SESSION_ID, first), hasCode("INVALID_ARGUMENT"), "replay should fail");
+  assert.deepEqual(guard.decrypt(key, SESSION_ID, second), { n: 2 });
+
+  const reflected = encryptRelayPayload(key, SESSION_ID, { n: 3 }, undefined, 3, "bob");
+  assert.throws(() => guard.decrypt(key, SESSION_ID, reflected), hasCode("INVALID_ARGUMENT"), "reflection should fail");
+
+  guard.forget(SESSION_ID);
+  assert.deepEqual(guard.decrypt(key, SESSION_ID, first), { n: 1 }, "forget should reset the session");
+}
+
+// A peer server stands in for the relay: requests arrive on bi streams it can answer. It cannot
+// deliver relay_data, which the relay pushes on raw uni streams.
+async function verifyRelayFraming(): Promise<void> {
+  const relay = QuicPeerServer.create("127.0.0.1:0");
+  const client = QuicRelayClient.create("127.0.0.1:0");
+  try {
+    const offer = relay.offer();
+    const [relayConn, transport] = await Promise.all([
+      relay.accept(),
+      client.connectTransport(
+        {
+          session_id: SESSION_ID,
+          quic_addr: offer.candidates[0],
+          server_fingerprint_sha256: offer.fingerprint_sha256,
+          alpn: offer.alpn,
+          server_name: offer.server_name,
+        },
+        { maxSessions: 1 },
+      ),
+    ]);
+    assert(relayConn !== null);
+    const answer = async (response: unknown): Promise<any> => {
+      const stream = await relayConn.acceptBi();
+      const chunks: Buffer[] = [];
+      for (let chunk = await stream.read(); chunk !== null; chunk = await stream.read()) {
+        chunks.push(chunk);
+      }
+      await stream.write(Buffer.from(JSON.stringify(response)));
+      await stream.finish();
+      return JSON.parse(Buffer.concat(chunks).toString("utf-8"));
+    };
+
+    const ok = { op: "relay_ok" };
+    const [large] = await Promise.all([
+      answer(ok),
+      transport.relaySend("token", SESSION_ID, { text: "x".repeat(4096) }, { compress: true }),
+    ]);
+    assert.equal(large.op, "relay_send");
+    assert.equal(large.payload.session_id, SESSION_ID);
+    assert.equal(large.payload.payload.zstd_v, 1, "large payloads should be compressed");
+    const [small] = await Promise.all([
+      answer(ok),
+      transport.relaySend("token", SESSION_ID, { a: 1 }, { compress: true }),
+    ]);
+    assert.deepEqual(small.payload.payload, { a: 1 }, "small payloads should be sent as is");
+
+    const [unbind, unbound] = await Promise.all([
+      answer({ op: "relay_unbind", data: { unbound: true } }),
+      transport.relayUnbind("token", SESSION_ID),
+    ]);
+    assert.deepEqual(unbind, { op: "relay_unbind", payload: { token: "token", session_id: SESSION_ID } });
+    assert.equal(unbound, true);
+
+    const declined = { op: "error", data: { message: "unknown op" } };
+    const [, sessions] = await Promise.all([answer(declined), transport.relayListSessions("token")]);
+    assert.equal(sessions, null, "an error frame should decline the query");
+    await Promise.all([answer(declined), assert.rejects(transport.relayUnbind("token", SESSION_ID), /unknown op/)]);
+
+    const [, peerReady] = await Promise.all([
+      answer({ op: "relay_bind", data: { peer_ready: true } }),
+      transport.relayBind("token", SESSION_ID),
+    ]);
+    assert.equal(peerReady, true);
+    const otherSession = "7f9619ff-8b86-d011-b42d-00c04fc964ff";
+    await assert.rejects(transport.relayBind("token", otherSession), hasCode("SESSION_LIMIT"));
+    assert.deepEqual(await transport.relayBindMulti("token", [otherSession]), [
+      { session_id: otherSession, error: "session limit reached (1)", code: "SESSION_LIMIT" },
+    ]);
+
+    transport.close();
+    relayConn.close();
+  } finally {
+    relay.close();
+  }
+}
+
+async function verifyAddressAfterMigration(pair: Pair): Promise<void> {
+  const before = pair.serverConn.remoteAddress();
+  assert.equal(pair.serverConn.migrationCount(), 0);
+
+  await pair.client.rebind("127.0.0.1:0");
+  // The server only sees the new path once packets arrive from it.
+  await openStreamPair(pair);
+
+  const after = pair.serverConn.remoteAddress();
+  assert.notEqual(after, before, "remote address should follow the migrated path");
+  assert.equal(pair.serverConn.migrationCount(), 1);
+}
+
+main().catch((err) => {
+  console.error("❌ JS SDK QUIC stream scenario failed", err);
+  process.exit(1);
+});